@@ -0,0 +1,13 @@
+CREATE TABLE TopicSubscription (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    topic TEXT NOT NULL,
+    token TEXT NOT NULL,
+    status TEXT CHECK(status IN('subscribed', 'unsubscribed', 'failed')),
+    error TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE TopicSubscription ADD CONSTRAINT ts_usr FOREIGN KEY (user_id) REFERENCES Users(id);
+ALTER TABLE TopicSubscription ADD CONSTRAINT ts_unique UNIQUE (user_id, topic, token);
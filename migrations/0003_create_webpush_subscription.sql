@@ -0,0 +1,14 @@
+ALTER TABLE Notification DROP CONSTRAINT notification_channel_check;
+ALTER TABLE Notification ADD CONSTRAINT notification_channel_check CHECK(channel IN('email', 'push', 'sms', 'webpush'));
+
+CREATE TABLE WebPushSubscription (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    endpoint TEXT NOT NULL,
+    p256dh TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE WebPushSubscription ADD CONSTRAINT wps_usr FOREIGN KEY (user_id) REFERENCES Users(id);
+ALTER TABLE WebPushSubscription ADD CONSTRAINT wps_unique UNIQUE (user_id, endpoint);
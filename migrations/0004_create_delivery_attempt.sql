@@ -0,0 +1,16 @@
+CREATE TABLE DeliveryAttempt (
+    id UUID PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    attempt_number INTEGER NOT NULL,
+    provider TEXT,
+    http_status INTEGER,
+    response_excerpt TEXT,
+    latency_ms BIGINT NOT NULL,
+    outcome TEXT CHECK(outcome IN('sent', 'failed', 'deferred')),
+    error_kind TEXT,
+    error TEXT,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE DeliveryAttempt ADD CONSTRAINT da_nt FOREIGN KEY (notification_id) REFERENCES Notification(id);
+ALTER TABLE DeliveryAttempt ADD CONSTRAINT da_unique UNIQUE (notification_id, attempt_number);
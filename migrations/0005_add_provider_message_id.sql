@@ -0,0 +1,6 @@
+ALTER TABLE Notification ADD COLUMN provider TEXT;
+ALTER TABLE Notification ADD COLUMN provider_message_id TEXT;
+
+CREATE INDEX nt_provider_message_id ON Notification (provider, provider_message_id);
+
+ALTER TABLE DeliveryAttempt ADD COLUMN provider_message_id TEXT;
@@ -0,0 +1,16 @@
+ALTER TABLE Notification DROP CONSTRAINT notification_status_check;
+ALTER TABLE Notification ADD CONSTRAINT notification_status_check CHECK(status IN('pending', 'sent', 'delivered', 'bounced', 'failed'));
+
+CREATE TABLE NotificationEvent (
+    id UUID PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    provider TEXT NOT NULL,
+    provider_event_id TEXT,
+    event TEXT NOT NULL,
+    detail TEXT,
+    occurred_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE NotificationEvent ADD CONSTRAINT ne_nt FOREIGN KEY (notification_id) REFERENCES Notification(id);
+ALTER TABLE NotificationEvent ADD CONSTRAINT ne_unique UNIQUE (provider, provider_event_id);
@@ -0,0 +1,5 @@
+ALTER TABLE Notification DROP CONSTRAINT notification_status_check;
+
+UPDATE Notification SET status = 'queued' WHERE status = 'pending';
+
+ALTER TABLE Notification ADD CONSTRAINT notification_status_check CHECK(status IN('queued', 'scheduled', 'processing', 'sent', 'delivered', 'bounced', 'failed', 'cancelled', 'expired'));
@@ -0,0 +1,14 @@
+ALTER TABLE Notification DROP CONSTRAINT notification_status_check;
+ALTER TABLE Notification ADD CONSTRAINT notification_status_check CHECK(status IN('queued', 'scheduled', 'processing', 'sent', 'delivered', 'bounced', 'failed', 'cancelled', 'expired', 'suppressed'));
+
+CREATE TABLE Suppression (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    email TEXT NOT NULL,
+    reason TEXT CHECK(reason IN('bounce', 'complaint', 'manual')),
+    detail TEXT,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE Suppression ADD CONSTRAINT sp_usr FOREIGN KEY (user_id) REFERENCES Users(id);
+ALTER TABLE Suppression ADD CONSTRAINT sp_unique UNIQUE (user_id, email);
@@ -0,0 +1,19 @@
+ALTER TABLE Notification DROP CONSTRAINT notification_status_check;
+ALTER TABLE Notification ADD CONSTRAINT notification_status_check CHECK(status IN('queued', 'scheduled', 'processing', 'sent', 'delivered', 'bounced', 'failed', 'cancelled', 'expired', 'suppressed', 'skipped'));
+
+ALTER TABLE Notification ADD COLUMN category TEXT;
+
+-- '*' matches every channel or every category
+CREATE TABLE Preference (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    recipient TEXT NOT NULL,
+    channel TEXT NOT NULL DEFAULT '*',
+    category TEXT NOT NULL DEFAULT '*',
+    enabled BOOLEAN NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE Preference ADD CONSTRAINT pf_usr FOREIGN KEY (user_id) REFERENCES Users(id);
+ALTER TABLE Preference ADD CONSTRAINT pf_unique UNIQUE (user_id, recipient, channel, category);
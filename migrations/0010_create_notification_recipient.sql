@@ -0,0 +1,17 @@
+CREATE TABLE NotificationRecipient (
+    id UUID PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    email TEXT NOT NULL,
+    name TEXT,
+    kind TEXT NOT NULL CHECK(kind IN('to', 'cc', 'bcc')),
+    status TEXT NOT NULL CHECK(status IN('queued', 'retrying', 'sent', 'failed', 'delivered', 'bounced', 'suppressed', 'skipped')),
+    provider TEXT,
+    provider_message_id TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE NotificationRecipient ADD CONSTRAINT nr_nt FOREIGN KEY (notification_id) REFERENCES Notification(id);
+ALTER TABLE NotificationRecipient ADD CONSTRAINT nr_unique UNIQUE (notification_id, email);
+
+CREATE INDEX nr_provider_message_id ON NotificationRecipient (provider, provider_message_id);
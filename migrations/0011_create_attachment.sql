@@ -0,0 +1,11 @@
+CREATE TABLE Attachment (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE Attachment ADD CONSTRAINT at_usr FOREIGN KEY (user_id) REFERENCES Users(id);
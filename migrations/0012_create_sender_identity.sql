@@ -0,0 +1,19 @@
+-- A verified identity lets a user send from an email address, or from every address of a
+-- domain
+CREATE TABLE SenderIdentity (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN('email', 'domain')),
+    identity TEXT NOT NULL,
+    display_name TEXT,
+    reply_to_email TEXT,
+    reply_to_name TEXT,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    verification_token TEXT NOT NULL,
+    verified_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE SenderIdentity ADD CONSTRAINT si_usr FOREIGN KEY (user_id) REFERENCES Users(id);
+ALTER TABLE SenderIdentity ADD CONSTRAINT si_unique UNIQUE (user_id, identity);
+CREATE UNIQUE INDEX si_default ON SenderIdentity (user_id) WHERE is_default;
@@ -0,0 +1,39 @@
+ALTER TABLE Template DROP CONSTRAINT template_type_check;
+ALTER TABLE Template ADD CONSTRAINT template_type_check CHECK(type IN('email', 'push', 'sms', 'webpush'));
+
+ALTER TABLE Template ADD COLUMN published_version INT;
+ALTER TABLE Template ADD COLUMN updated_at TIMESTAMP DEFAULT NOW();
+
+-- The content of a version is the JSON payload it renders, e.g. `subject`, `html` and `text`
+-- of an email. Only drafts are edited, publishing a version retires the previous one
+CREATE TABLE TemplateVersion (
+    id UUID PRIMARY KEY,
+    template_id UUID NOT NULL,
+    version INT NOT NULL,
+    status TEXT NOT NULL CHECK(status IN('draft', 'published', 'retired')),
+    content TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    published_at TIMESTAMP
+);
+
+ALTER TABLE TemplateVersion ADD CONSTRAINT tv_tp FOREIGN KEY (template_id) REFERENCES Template(id);
+ALTER TABLE TemplateVersion ADD CONSTRAINT tv_unique UNIQUE (template_id, version);
+CREATE UNIQUE INDEX tv_draft ON TemplateVersion (template_id) WHERE status = 'draft';
+
+-- Existing templates become their published version 1. Their content moves to the body field
+-- of the template type: `body` for push notifications, `html` or `text` for emails
+INSERT INTO TemplateVersion (id, template_id, version, status, content, published_at)
+SELECT gen_random_uuid(), id, 1, 'published', json_build_object(
+    CASE
+        WHEN type <> 'email' THEN 'body'
+        WHEN content ~ '<[A-Za-z!/]' THEN 'html'
+        ELSE 'text'
+    END,
+    content
+)::text, NOW()
+FROM Template;
+UPDATE Template SET published_version = 1;
+ALTER TABLE Template DROP COLUMN content;
+
+-- Version of the template a notification was rendered with
+ALTER TABLE Notification ADD COLUMN template_version INT;
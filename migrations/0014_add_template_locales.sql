@@ -0,0 +1,9 @@
+-- Variants of the content per locale, a JSON object such as `{"pt-BR": {...}, "pt": {...}}`.
+-- The content of the version is the default variant
+ALTER TABLE TemplateVersion ADD COLUMN locales TEXT NOT NULL DEFAULT '{}';
+
+-- Locale of the notifications of a user which do not set one
+ALTER TABLE Users ADD COLUMN locale TEXT;
+
+-- Locale of the template variant a notification was rendered with, NULL for the default
+ALTER TABLE Notification ADD COLUMN template_locale TEXT;
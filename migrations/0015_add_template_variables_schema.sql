@@ -0,0 +1,6 @@
+-- JSON Schema the `variables` of the notifications rendering a version must match, NULL
+-- accepts any variables
+ALTER TABLE TemplateVersion ADD COLUMN variables_schema TEXT;
+
+-- Whether rendering fails on placeholders without a variable instead of keeping them
+ALTER TABLE TemplateVersion ADD COLUMN strict BOOLEAN NOT NULL DEFAULT FALSE;
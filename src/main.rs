@@ -6,7 +6,8 @@ use dotenvy::dotenv;
 use env_logger::Env;
 use log::info;
 use module::{
-    notification_delivery_module::NotiDelivModule, notification_service_module::NotiServiceModule,
+    notification_delivery_module::NotiDelivModule,
+    notification_service_module::{utils::attachment_policy::AttachmentPolicy, NotiServiceModule},
 };
 use sqlx::migrate;
 
@@ -36,17 +37,41 @@ async fn main() -> std::io::Result<()> {
     migrator.run(&*pg_pool).await.expect("Migration failed");
     info!("Migration success");
 
+    // read configuration
+    let attachment_policy = Arc::new(AttachmentPolicy::from_env().map_err(std::io::Error::other)?);
+
     // init modules
-    let noti_srv_module = NotiServiceModule::new(pg_pool.clone(), redis_pool.clone());
+    let noti_srv_module =
+        NotiServiceModule::new(pg_pool.clone(), redis_pool.clone(), attachment_policy);
     let noti_deliv_module = NotiDelivModule::new(pg_pool.clone(), redis_pool.clone()).await;
     let _worker_addr = noti_deliv_module.queue_worker_addr;
+    let topic_controller = noti_deliv_module.topic_controller;
+    let worker_controller = noti_deliv_module.worker_controller;
 
     info!("Starting server...");
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(noti_srv_module.noti_controller.clone()))
-            .configure(NotiServiceModule::routes_config)
+            .app_data(web::Data::new(noti_srv_module.webpush_controller.clone()))
+            .app_data(web::Data::new(
+                noti_srv_module.event_webhook_controller.clone(),
+            ))
+            .app_data(web::Data::new(
+                noti_srv_module.suppression_controller.clone(),
+            ))
+            .app_data(web::Data::new(
+                noti_srv_module.preference_controller.clone(),
+            ))
+            .app_data(web::Data::new(noti_srv_module.tracking_controller.clone()))
+            .app_data(web::Data::new(
+                noti_srv_module.attachment_controller.clone(),
+            ))
+            .app_data(web::Data::new(noti_srv_module.sender_controller.clone()))
+            .app_data(web::Data::new(noti_srv_module.template_controller.clone()))
+            .app_data(web::Data::new(topic_controller.clone()))
+            .app_data(web::Data::new(worker_controller.clone()))
+            .configure(|cfg| noti_srv_module.routes_config(cfg))
             .configure(NotiDelivModule::routes_config)
     })
     .workers(1)
@@ -1 +1,2 @@
+pub mod topic_controller;
 pub mod worker_controller;
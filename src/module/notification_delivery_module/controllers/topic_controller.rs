@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use actix_web::{
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_delivery_module::{
+    models::topic::{TopicSubscriptionQuery, TopicSubscriptionRequest},
+    services::topic_service::TopicService,
+};
+
+pub struct TopicController {
+    topic_service: Arc<TopicService>,
+}
+
+impl TopicController {
+    pub fn new(topic_service: Arc<TopicService>) -> Self {
+        Self { topic_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/topic")
+                .route("/subscribe", web::post().to(Self::subscribe))
+                .route("/unsubscribe", web::post().to(Self::unsubscribe))
+                .route("/{topic}/subscriptions", web::get().to(Self::list)),
+        );
+    }
+
+    async fn subscribe(
+        self_controller: web::Data<Arc<TopicController>>,
+        request: Json<TopicSubscriptionRequest>,
+    ) -> impl Responder {
+        match self_controller.topic_service.subscribe(request.0).await {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn unsubscribe(
+        self_controller: web::Data<Arc<TopicController>>,
+        request: Json<TopicSubscriptionRequest>,
+    ) -> impl Responder {
+        match self_controller.topic_service.unsubscribe(request.0).await {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn list(
+        self_controller: web::Data<Arc<TopicController>>,
+        topic: Path<String>,
+        query: Query<TopicSubscriptionQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .topic_service
+            .list(&query.user_id, &topic)
+            .await
+        {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
@@ -1 +1,24 @@
+use std::sync::Arc;
 
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::module::notification_delivery_module::utils::circuit_breaker::CircuitBreakerRegistry;
+
+pub struct WorkerController {
+    breakers: Arc<CircuitBreakerRegistry>,
+}
+
+impl WorkerController {
+    pub fn new(breakers: Arc<CircuitBreakerRegistry>) -> Self {
+        Self { breakers }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::scope("/worker").route("/breakers", web::get().to(Self::breakers)));
+    }
+
+    /// Returns the circuit breaker state of every provider
+    async fn breakers(self_controller: web::Data<Arc<WorkerController>>) -> impl Responder {
+        HttpResponse::Ok().json(self_controller.breakers.snapshots())
+    }
+}
@@ -1,4 +1,4 @@
-use std::env::VarError;
+use std::{env::VarError, time::Duration};
 
 use actix_web::{HttpResponse, ResponseError};
 use deadpool_redis::PoolError;
@@ -33,6 +33,52 @@ pub enum NotiDeliverError {
 
     #[display("Request failed")]
     RequestFailed,
+
+    /// The provider answered with a server error or could not be reached by its transport
+    #[display("Provider unavailable: {_0}")]
+    ProviderUnavailable(#[error(not(source))] String),
+
+    #[display("{_0}")]
+    InvalidDataField(#[error(not(source))] String),
+
+    #[display("Permanent failure: {_0}")]
+    PermanentFailure(#[error(not(source))] String),
+
+    #[display("Delivery deferred for {_0:?}")]
+    Deferred(#[error(not(source))] Duration),
+}
+
+impl NotiDeliverError {
+    /// Short machine readable name of the error, stored with delivery attempts
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotiDeliverError::DatabaseError(_) => "database_error",
+            NotiDeliverError::MissingEnvError(_) => "missing_env",
+            NotiDeliverError::RedisConnectionError(_) => "redis_connection_error",
+            NotiDeliverError::RedisQueuePopError(_) => "redis_queue_pop_error",
+            NotiDeliverError::NoneValue => "none_value",
+            NotiDeliverError::JsonParseError => "json_parse_error",
+            NotiDeliverError::RequestError(_) => "request_error",
+            NotiDeliverError::GCPAuthError(_) => "gcp_auth_error",
+            NotiDeliverError::RequestFailed => "request_failed",
+            NotiDeliverError::ProviderUnavailable(_) => "provider_unavailable",
+            NotiDeliverError::InvalidDataField(_) => "invalid_data_field",
+            NotiDeliverError::PermanentFailure(_) => "permanent_failure",
+            NotiDeliverError::Deferred(_) => "deferred",
+        }
+    }
+
+    /// Whether the error signals that the provider itself is unavailable, as opposed to a
+    /// problem with a single notification
+    ///
+    /// Only transport errors and server errors count, a provider rejecting a notification
+    /// (e.g. a bad device token) is healthy. Throttling, `Deferred`, is handled on its own
+    pub fn is_provider_outage(&self) -> bool {
+        matches!(
+            self,
+            NotiDeliverError::RequestError(_) | NotiDeliverError::ProviderUnavailable(_)
+        )
+    }
 }
 
 impl ResponseError for NotiDeliverError {
@@ -50,6 +96,10 @@ impl ResponseError for NotiDeliverError {
                 .body(self.to_string())
                 .map_into_boxed_body(),
 
+            NotiDeliverError::InvalidDataField(_) => HttpResponse::BadRequest()
+                .body(self.to_string())
+                .map_into_boxed_body(),
+
             _ => HttpResponse::InternalServerError().finish(),
         }
     }
@@ -1,14 +1,25 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, env, sync::Arc};
 
 use actix::{Actor, Addr, Recipient};
 use actix_web::web;
+use controllers::{topic_controller::TopicController, worker_controller::WorkerController};
 use deadpool_redis::Pool;
-use repositories::{notification_repository::NotificationRepo, redis_repository::RedisRepository};
+use repositories::{
+    attachment_repository::AttachmentRepo, notification_repository::NotificationRepo,
+    redis_repository::RedisRepository, topic_repository::TopicRepo,
+    webpush_repository::WebPushRepo,
+};
+use services::topic_service::TopicService;
 use sqlx::PgPool;
-use utils::fcm_token_manager::TokenManager;
+use utils::{
+    circuit_breaker::{BreakerConfig, CircuitBreakerRegistry},
+    fcm_token_manager::TokenManager,
+    rate_limiter::RateLimiter,
+};
 use workers::queue_worker::{
-    email_worker::EmailWorker, notification_worker_actor::NotificationWorkerActor,
-    push_worker::PushWorker, NotificationMessage, QueueWorker,
+    apns_worker::ApnsWorker, email_providers::EmailProviderRouter, email_worker::EmailWorker,
+    notification_worker_actor::NotificationWorkerActor, push_worker::PushWorker,
+    webpush_worker::WebPushWorker, NotificationMessage, QueueWorker,
 };
 
 pub mod controllers;
@@ -21,48 +32,104 @@ pub mod workers;
 
 pub struct NotiDelivModule {
     pub queue_worker_addr: Addr<QueueWorker>,
+    pub topic_controller: Arc<TopicController>,
+    pub worker_controller: Arc<WorkerController>,
 }
 
 impl NotiDelivModule {
     pub async fn new(pg_pool: Arc<PgPool>, redis_pool: Arc<Pool>) -> Self {
         // init repositories
-        let noti_repo = Arc::new(NotificationRepo::new(pg_pool));
+        let noti_repo = Arc::new(NotificationRepo::new(pg_pool.clone()));
         let redis_repo = Arc::new(RedisRepository::new(redis_pool));
+        let topic_repo = Arc::new(TopicRepo::new(pg_pool.clone()));
+        let webpush_repo = Arc::new(WebPushRepo::new(pg_pool.clone()));
+        let attachment_repo = Arc::new(AttachmentRepo::new(pg_pool));
 
-        // init services
-        // let noti_service = Arc::new(NotificationService::new(
-        //     noti_repo.clone(),
-        //     redis_repo.clone(),
-        // ));
+        let token_manager = Arc::new(TokenManager::new().await);
+        let breakers = Arc::new(CircuitBreakerRegistry::new(BreakerConfig::from_env()));
+        let limiter = Arc::new(RateLimiter::from_env());
 
-        let token_manager = TokenManager::new().await;
+        // init services
+        let topic_service = Arc::new(TopicService::new(token_manager.clone(), topic_repo.clone()));
 
         let push_worker = NotificationWorkerActor::new(
-            Arc::new(PushWorker::new(token_manager).await),
+            Arc::new(PushWorker::new(token_manager.clone()).await),
             noti_repo.clone(),
             redis_repo.clone(),
+            breakers.clone(),
+            limiter.clone(),
         )
         .start();
+        // Register breakers upfront so every provider shows up in the API
+        let email_router = EmailProviderRouter::from_env();
+        for provider in email_router.provider_names() {
+            breakers.get(provider);
+        }
+        breakers.get("fcm");
+
         let email_worker = NotificationWorkerActor::new(
-            Arc::new(EmailWorker::new()),
+            Arc::new(EmailWorker::new(
+                email_router,
+                breakers.clone(),
+                limiter.clone(),
+                attachment_repo.clone(),
+            )),
             noti_repo.clone(),
             redis_repo.clone(),
+            breakers.clone(),
+            limiter.clone(),
         )
         .start();
 
         let mut workers: HashMap<String, Recipient<NotificationMessage>> = HashMap::new();
 
-        workers.insert("push".to_string(), push_worker.recipient());
+        workers.insert("push".to_string(), push_worker.clone().recipient());
+        workers.insert("push/fcm".to_string(), push_worker.recipient());
         workers.insert("email".to_string(), email_worker.recipient());
 
+        // APNs is optional, it is only started when its credentials are configured
+        if env::var("APNS_KEY_PATH").is_ok() {
+            let apns_worker = NotificationWorkerActor::new(
+                Arc::new(ApnsWorker::new()),
+                noti_repo.clone(),
+                redis_repo.clone(),
+                breakers.clone(),
+                limiter.clone(),
+            )
+            .start();
+            workers.insert("push/apns".to_string(), apns_worker.recipient());
+        }
+
+        // Web push is optional, it is only started when VAPID keys are configured
+        if env::var("VAPID_PRIVATE_KEY").is_ok() {
+            let webpush_worker = NotificationWorkerActor::new(
+                Arc::new(WebPushWorker::new(webpush_repo.clone())),
+                noti_repo.clone(),
+                redis_repo.clone(),
+                breakers.clone(),
+                limiter.clone(),
+            )
+            .start();
+            workers.insert("webpush".to_string(), webpush_worker.recipient());
+        }
+
         let queue_worker = QueueWorker::new(redis_repo.clone(), noti_repo.clone(), workers);
         let queue_worker_addr = queue_worker.start();
 
         // init controllers
+        let topic_controller = TopicController::new(topic_service.clone());
+        let worker_controller = WorkerController::new(breakers.clone());
 
         // generate module
-        Self { queue_worker_addr }
+        Self {
+            queue_worker_addr,
+            topic_controller: Arc::new(topic_controller),
+            worker_controller: Arc::new(worker_controller),
+        }
     }
 
-    pub fn routes_config(_cfg: &mut web::ServiceConfig) {}
+    pub fn routes_config(cfg: &mut web::ServiceConfig) {
+        TopicController::routes(cfg);
+        WorkerController::routes(cfg);
+    }
 }
@@ -0,0 +1,43 @@
+use reqwest::StatusCode;
+
+// Maximum length of a provider response stored with an attempt
+const RESPONSE_EXCERPT_LENGTH: usize = 512;
+
+/// What a worker learned from the provider while sending a notification
+///
+/// Workers fill it during `send`, `NotificationWorkerActor` stores it in `DeliveryAttempt`
+/// along with the latency and the outcome of the attempt
+#[derive(Debug, Default)]
+pub struct DeliveryAttempt {
+    pub provider: Option<String>,
+    pub http_status: Option<u16>,
+    pub response_excerpt: Option<String>,
+    /// Id given by the provider to the accepted message, used to reconcile with the provider
+    /// dashboards and delivery events
+    pub provider_message_id: Option<String>,
+}
+
+impl DeliveryAttempt {
+    /// Records the answer of the provider, keeping the beginning of the body
+    pub fn record_response(&mut self, status: StatusCode, body: &str) {
+        self.http_status = Some(status.as_u16());
+        self.response_excerpt = if body.is_empty() {
+            None
+        } else {
+            Some(body.chars().take(RESPONSE_EXCERPT_LENGTH).collect())
+        };
+    }
+
+    /// Records the provider message id found in a string field of a JSON response
+    pub fn record_message_id(&mut self, body: &str, field: &str) {
+        self.provider_message_id = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|body| body.get(field)?.as_str().map(str::to_string));
+    }
+
+    /// Whether a request reached the provider during the attempt, which answered with an
+    /// HTTP status or an SMTP reply
+    pub fn reached_provider(&self) -> bool {
+        self.http_status.is_some() || self.response_excerpt.is_some()
+    }
+}
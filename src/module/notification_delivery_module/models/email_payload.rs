@@ -1,11 +1,23 @@
+use std::collections::HashMap;
+
+use derive_more::Display;
+use lettre::message::Mailbox;
 use serde::{Deserialize, Serialize};
 
+use crate::module::notification_delivery_module::utils::html_to_text::html_to_text;
+
+/// Email part of a notification payload
+///
+/// The body is given as `html` and/or `text` parts, or as a single `content` of
+/// `content_type`
 #[derive(Debug, Deserialize)]
 pub struct EmailPayload {
     pub subject: String,
-    pub content: String,
+    pub content: Option<String>,
     #[serde(default = "default_content_type")]
     pub content_type: String,
+    pub html: Option<String>,
+    pub text: Option<String>,
     pub optionals: Option<serde_json::Value>,
 }
 
@@ -13,23 +25,162 @@ fn default_content_type() -> String {
     "text/plain".to_string()
 }
 
+/// Whether a content type is HTML, parameters such as `charset` aside
+pub fn is_html(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .eq_ignore_ascii_case("text/html")
+}
+
+impl EmailPayload {
+    /// Returns the plain text and the HTML parts of the email
+    ///
+    /// Every email has a plain text part, it is generated from the HTML when only HTML is
+    /// given. Returns `None` when the payload has no body
+    pub fn into_parts(self) -> Option<(String, Option<String>)> {
+        let (mut text, mut html) = (self.text, self.html);
+        if let Some(content) = self.content {
+            if is_html(&self.content_type) {
+                html.get_or_insert(content);
+            } else {
+                text.get_or_insert(content);
+            }
+        }
+
+        match (text, html) {
+            (Some(text), html) => Some((text, html)),
+            (None, Some(html)) => Some((html_to_text(&html), Some(html))),
+            (None, None) => None,
+        }
+    }
+}
+
 // Variables options
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Attachments {
+    /// Uploaded attachment whose content is loaded when the email is sent
+    #[serde(default, skip_serializing)]
+    pub id: Option<String>,
+    /// Base64 encoded content
+    #[serde(default)]
     pub content: String,
     pub filename: String,
     #[serde(rename = "type")]
     pub r#type: String,
     #[serde(default = "default_disposition")]
     pub disposition: String,
+    /// Content id of an inline attachment, referenced as `cid:<content_id>` in the HTML part
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+}
+
+impl Attachments {
+    pub fn is_inline(&self) -> bool {
+        self.disposition == "inline"
+    }
+
+    /// Name the inline attachment is referenced by, its content id or else its filename
+    pub fn inline_id(&self) -> &str {
+        self.content_id.as_deref().unwrap_or(&self.filename)
+    }
 }
 
 fn default_disposition() -> String {
     "attachment".to_string()
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ReplyTo {
     pub email: String,
     pub name: String,
 }
+
+/// An email address with its optional display name
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EmailAddress {
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl EmailAddress {
+    /// Parses a mailbox such as `"Jane Doe" <jane@example.com>` or `jane@example.com`
+    pub fn from_mailbox(value: &str) -> Result<Self, String> {
+        let mailbox: Mailbox = value
+            .parse()
+            .map_err(|e| format!("invalid address '{}': {}", value, e))?;
+        Ok(Self {
+            email: mailbox.email.to_string(),
+            name: mailbox.name,
+        })
+    }
+
+    /// Formats the address as a mailbox, e.g. `"Jane Doe" <jane@example.com>`
+    pub fn mailbox(&self) -> String {
+        match &self.name {
+            Some(name) => format!(
+                "\"{}\" <{}>",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                self.email
+            ),
+            None => self.email.clone(),
+        }
+    }
+}
+
+/// A recipient of an email notification, as queued by the notification service
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EmailRecipient {
+    #[serde(flatten)]
+    pub address: EmailAddress,
+    /// Values replacing `{{key}}` in the subject and the body sent to this recipient
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub substitutions: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsubscribe_url: Option<String>,
+}
+
+/// The `to`, `cc` and `bcc` recipients of an email notification
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EmailRecipients {
+    pub to: Vec<EmailRecipient>,
+    #[serde(default)]
+    pub cc: Vec<EmailRecipient>,
+    #[serde(default)]
+    pub bcc: Vec<EmailRecipient>,
+}
+
+/// Outcome of sending an email to a recipient, stored in `NotificationRecipient.status`
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum RecipientStatus {
+    #[display("sent")]
+    Sent,
+    /// Failed with a retryable error, the next attempt sends to the recipient again
+    #[display("retrying")]
+    Retrying,
+    #[display("failed")]
+    Failed,
+}
+
+/// Provider independent representation of an email ready to be sent
+#[derive(Debug)]
+pub struct OutgoingEmail {
+    pub from: EmailAddress,
+    pub to: Vec<EmailAddress>,
+    pub cc: Vec<EmailAddress>,
+    pub bcc: Vec<EmailAddress>,
+    pub subject: String,
+    /// Plain text part, always sent
+    pub text: String,
+    /// HTML part, sent along the text part as a `multipart/alternative` body
+    pub html: Option<String>,
+    pub attachments: Vec<Attachments>,
+    pub reply_to: Option<ReplyTo>,
+    /// Extra headers as (name, value) pairs
+    pub headers: Vec<(String, String)>,
+    /// Tags grouping the email in the statistics of the provider
+    pub tags: Vec<String>,
+}
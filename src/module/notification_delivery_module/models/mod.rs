@@ -1,3 +1,6 @@
+pub mod delivery_attempt;
 pub mod email_payload;
 pub mod notification;
 pub mod push_payload;
+pub mod topic;
+pub mod webpush_payload;
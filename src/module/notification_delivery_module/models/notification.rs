@@ -1,14 +1,77 @@
+use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
+use super::email_payload::EmailRecipients;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NotificationDeQueue {
     pub notification_id: String,
+    pub user_id: Option<String>,
     pub recipient: String,
     pub recipient_type: Option<String>,
     pub sender: Option<String>,
     pub channel: String,
+    pub provider: Option<String>,
+    pub category: Option<String>,
+    /// Recipients of an email, `recipient` is the only recipient when unset
+    pub recipients: Option<EmailRecipients>,
     pub template_id: Option<String>,
+    /// Locale the template was rendered with
+    #[serde(default)]
+    pub template_locale: Option<String>,
     pub payload: serde_json::Value,
     #[serde(default)]
     pub retry_count: u8,
+    /// Times the provider asked to try again later, which do not count as retries
+    #[serde(default)]
+    pub deferral_count: u8,
+}
+
+impl NotificationDeQueue {
+    /// Key of the worker responsible for this notification
+    ///
+    /// Notifications targeting a specific provider are routed to `<channel>/<provider>`
+    pub fn worker_key(&self) -> String {
+        match &self.provider {
+            Some(provider) => format!("{}/{}", self.channel, provider),
+            None => self.channel.clone(),
+        }
+    }
+}
+
+/// Lifecycle of a notification, stored in `Notification.status`
+///
+/// Only the statuses involved in the transitions made by the delivery workers are listed,
+/// the full lifecycle lives in the notification service
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum NotificationStatus {
+    #[display("queued")]
+    Queued,
+    #[display("scheduled")]
+    Scheduled,
+    #[display("processing")]
+    Processing,
+    #[display("sent")]
+    Sent,
+    #[display("failed")]
+    Failed,
+}
+
+impl NotificationStatus {
+    /// Statuses a notification may be in to move to this status
+    ///
+    /// The repository only updates rows in one of these statuses, so a late retry cannot
+    /// move a sent notification back to `failed`
+    pub fn allowed_from(&self) -> &'static [NotificationStatus] {
+        use NotificationStatus::*;
+
+        match self {
+            Queued => &[Scheduled],
+            Scheduled => &[],
+            // A retried notification is picked up again while processing
+            Processing => &[Queued, Processing],
+            Sent => &[Queued, Processing],
+            Failed => &[Queued, Scheduled, Processing],
+        }
+    }
 }
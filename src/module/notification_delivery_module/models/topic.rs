@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct TopicSubscriptionRequest {
+    pub user_id: String,
+    pub topic: String,
+    pub tokens: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopicSubscriptionQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopicSubscriptionResponse {
+    pub topic: String,
+    pub results: Vec<TopicTokenResult>,
+}
+
+/// Result of a subscribe/unsubscribe operation for a single device token
+#[derive(Debug, Serialize)]
+pub struct TopicTokenResult {
+    pub token: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// A device token membership row recorded in `TopicSubscription`
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TopicSubscription {
+    pub token: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Response body of the FCM Instance ID batch APIs (`:batchAdd` / `:batchRemove`)
+#[derive(Debug, Deserialize)]
+pub struct IidBatchResponse {
+    #[serde(default)]
+    pub results: Vec<IidBatchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IidBatchResult {
+    pub error: Option<String>,
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebPushPayload {
+    pub title: String,
+    pub body: String,
+    pub data: Option<serde_json::Value>,
+}
+
+/// A browser `PushSubscription` stored in `WebPushSubscription`
+#[derive(Debug, sqlx::FromRow)]
+pub struct WebPushSubscription {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
@@ -0,0 +1,3 @@
+INSERT INTO DeliveryAttempt (id, notification_id, attempt_number, provider, http_status, response_excerpt, provider_message_id, latency_ms, outcome, error_kind, error)
+SELECT $1, $2, COALESCE(MAX(attempt_number), 0) + 1, $3, $4, $5, $6, $7, $8, $9, $10
+FROM DeliveryAttempt WHERE notification_id = $2;
@@ -0,0 +1 @@
+SELECT content FROM Attachment WHERE id = $1;
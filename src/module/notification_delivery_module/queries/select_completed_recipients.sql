@@ -0,0 +1 @@
+SELECT email, status FROM NotificationRecipient WHERE notification_id = $1 AND status NOT IN ('queued', 'retrying');
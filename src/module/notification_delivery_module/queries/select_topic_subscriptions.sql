@@ -0,0 +1,3 @@
+SELECT token, status, error, created_at, updated_at FROM TopicSubscription
+WHERE user_id = $1 AND topic = $2
+ORDER BY updated_at DESC;
@@ -0,0 +1 @@
+SELECT endpoint, p256dh, auth FROM WebPushSubscription WHERE id = $1;
@@ -1 +1 @@
-UPDATE Notification SET status = $1, updated_at = NOW() WHERE id = $2;
\ No newline at end of file
+UPDATE Notification SET status = $1, updated_at = NOW() WHERE id = $2 AND status = ANY($3);
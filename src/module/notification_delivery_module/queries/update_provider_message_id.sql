@@ -0,0 +1 @@
+UPDATE Notification SET provider = $1, provider_message_id = $2, updated_at = NOW() WHERE id = $3;
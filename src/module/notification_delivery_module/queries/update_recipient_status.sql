@@ -0,0 +1,2 @@
+UPDATE NotificationRecipient SET status = $1, provider = $2, provider_message_id = $3, updated_at = NOW()
+WHERE notification_id = $4 AND email = ANY($5) AND status IN ('queued', 'retrying', 'failed');
@@ -0,0 +1,4 @@
+INSERT INTO TopicSubscription(id, user_id, topic, token, status, error)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (user_id, topic, token)
+DO UPDATE SET status = EXCLUDED.status, error = EXCLUDED.error, updated_at = NOW();
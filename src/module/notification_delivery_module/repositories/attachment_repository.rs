@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct AttachmentRepo {
+    pg_pool: Arc<PgPool>,
+}
+
+impl AttachmentRepo {
+    pub fn new(pg_pool: Arc<PgPool>) -> Self {
+        Self { pg_pool }
+    }
+
+    /// Returns the content of an uploaded attachment
+    pub async fn find_content(&self, id: &Uuid) -> Result<Option<Vec<u8>>, sqlx::Error> {
+        let stm = include_str!("../queries/select_attachment_content.sql");
+
+        sqlx::query_scalar(stm)
+            .bind(id)
+            .fetch_optional(&*self.pg_pool)
+            .await
+    }
+}
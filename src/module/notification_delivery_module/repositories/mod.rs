@@ -1,2 +1,5 @@
+pub mod attachment_repository;
 pub mod notification_repository;
 pub mod redis_repository;
+pub mod topic_repository;
+pub mod webpush_repository;
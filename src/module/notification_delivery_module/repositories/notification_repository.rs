@@ -4,6 +4,11 @@ use log::info;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::module::notification_delivery_module::models::{
+    delivery_attempt::DeliveryAttempt, email_payload::RecipientStatus,
+    notification::NotificationStatus,
+};
+
 pub struct NotificationRepo {
     pg_pool: Arc<PgPool>,
 }
@@ -13,18 +18,27 @@ impl NotificationRepo {
         Self { pg_pool }
     }
 
+    /// Moves a notification to `status`, if its current status allows the transition
+    ///
+    /// Returns 0 when the notification is not in one of `status.allowed_from()`
     pub async fn update_notification_status(
         &self,
         noti_id: &str,
-        status: &str,
+        status: NotificationStatus,
     ) -> Result<u64, sqlx::Error> {
         let stm = include_str!("../queries/update_notification_status.sql");
 
-        let noti_id = Uuid::parse_str(noti_id).unwrap();
+        let noti_id = Uuid::parse_str(noti_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let allowed_from: Vec<String> = status
+            .allowed_from()
+            .iter()
+            .map(|status| status.to_string())
+            .collect();
 
         let result = sqlx::query(stm)
-            .bind(status)
+            .bind(status.to_string())
             .bind(noti_id)
+            .bind(allowed_from)
             .execute(&*self.pg_pool)
             .await?;
 
@@ -33,4 +47,100 @@ impl NotificationRepo {
 
         Ok(rows_affected)
     }
+
+    /// Stores an attempt to deliver a notification, numbered after the previous attempts
+    ///
+    /// `error` holds the kind and the message of the error of a failed attempt
+    pub async fn insert_delivery_attempt(
+        &self,
+        noti_id: &str,
+        attempt: &DeliveryAttempt,
+        latency_ms: i64,
+        outcome: &str,
+        error: Option<(&str, String)>,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/insert_delivery_attempt.sql");
+
+        let noti_id = Uuid::parse_str(noti_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let (error_kind, error) = error.unzip();
+
+        let result = sqlx::query(stm)
+            .bind(Uuid::new_v4())
+            .bind(noti_id)
+            .bind(&attempt.provider)
+            .bind(attempt.http_status.map(i32::from))
+            .bind(&attempt.response_excerpt)
+            .bind(&attempt.provider_message_id)
+            .bind(latency_ms)
+            .bind(outcome)
+            .bind(error_kind)
+            .bind(error)
+            .execute(&*self.pg_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Stores the provider which accepted the notification and its message id
+    pub async fn update_provider_message_id(
+        &self,
+        noti_id: &str,
+        provider: Option<&str>,
+        provider_message_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_provider_message_id.sql");
+
+        let noti_id = Uuid::parse_str(noti_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        let result = sqlx::query(stm)
+            .bind(provider)
+            .bind(provider_message_id)
+            .bind(noti_id)
+            .execute(&*self.pg_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns the addresses of an email notification no attempt needs to send to anymore,
+    /// the ones sent to or failed permanently, with their status
+    pub async fn find_completed_recipients(
+        &self,
+        noti_id: &str,
+    ) -> Result<Vec<(String, String)>, sqlx::Error> {
+        let stm = include_str!("../queries/select_completed_recipients.sql");
+
+        let noti_id = Uuid::parse_str(noti_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        sqlx::query_as(stm)
+            .bind(noti_id)
+            .fetch_all(&*self.pg_pool)
+            .await
+    }
+
+    /// Records the outcome of sending an email notification to some of its recipients
+    ///
+    /// Recipients already delivered or bounced are left untouched
+    pub async fn update_recipient_status(
+        &self,
+        noti_id: &str,
+        emails: &[String],
+        status: RecipientStatus,
+        attempt: &DeliveryAttempt,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_recipient_status.sql");
+
+        let noti_id = Uuid::parse_str(noti_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        let result = sqlx::query(stm)
+            .bind(status.to_string())
+            .bind(&attempt.provider)
+            .bind(&attempt.provider_message_id)
+            .bind(noti_id)
+            .bind(emails)
+            .execute(&*self.pg_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
 }
@@ -2,7 +2,18 @@ use std::sync::Arc;
 
 use deadpool_redis::{Pool, PoolError};
 use log::info;
-use redis::AsyncCommands;
+use redis::{AsyncCommands, Script};
+
+// Moves the jobs of the sorted set KEYS[1] due at ARGV[1] to the queue KEYS[2], at most
+// ARGV[2] at once. Atomic so that two gateways never requeue the same job
+const REQUEUE_DUE_SCRIPT: &str = r#"
+local jobs = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, ARGV[2])
+for _, job in ipairs(jobs) do
+    redis.call('ZREM', KEYS[1], job)
+    redis.call('LPUSH', KEYS[2], job)
+end
+return #jobs
+"#;
 
 pub struct RedisRepository {
     pub pool: Arc<Pool>,
@@ -19,4 +30,32 @@ impl RedisRepository {
         info!("Redis push to: {}", key);
         Ok(())
     }
+
+    /// Stores a job in the sorted set `key` until `due_at`, a Unix timestamp in milliseconds
+    pub async fn schedule(&self, key: &str, value: &str, due_at: i64) -> Result<(), PoolError> {
+        let mut redis_conn = self.pool.get().await?;
+        let _: () = redis_conn.zadd(key, value, due_at).await?;
+        info!("Redis schedule to: {}", key);
+        Ok(())
+    }
+
+    /// Moves the jobs of the sorted set `key` due at `now`, in milliseconds, back to the
+    /// queue `queue_key`, returns how many were moved
+    pub async fn requeue_due(
+        &self,
+        key: &str,
+        queue_key: &str,
+        now: i64,
+        limit: usize,
+    ) -> Result<usize, PoolError> {
+        let mut redis_conn = self.pool.get().await?;
+        let moved: usize = Script::new(REQUEUE_DUE_SCRIPT)
+            .key(key)
+            .key(queue_key)
+            .arg(now)
+            .arg(limit)
+            .invoke_async(&mut redis_conn)
+            .await?;
+        Ok(moved)
+    }
 }
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_delivery_module::models::topic::TopicSubscription;
+
+pub struct TopicRepo {
+    pg_pool: Arc<PgPool>,
+}
+
+impl TopicRepo {
+    pub fn new(pg_pool: Arc<PgPool>) -> Self {
+        Self { pg_pool }
+    }
+
+    /// Records the latest membership state of a device token for a topic
+    pub async fn upsert_subscription(
+        &self,
+        user_id: &Uuid,
+        topic: &str,
+        token: &str,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/upsert_topic_subscription.sql");
+
+        let result = sqlx::query(stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(topic)
+            .bind(token)
+            .bind(status)
+            .bind(error)
+            .execute(&*self.pg_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_by_topic(
+        &self,
+        user_id: &Uuid,
+        topic: &str,
+    ) -> Result<Vec<TopicSubscription>, sqlx::Error> {
+        let stm = include_str!("../queries/select_topic_subscriptions.sql");
+
+        sqlx::query_as::<_, TopicSubscription>(stm)
+            .bind(user_id)
+            .bind(topic)
+            .fetch_all(&*self.pg_pool)
+            .await
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_delivery_module::models::webpush_payload::WebPushSubscription;
+
+pub struct WebPushRepo {
+    pg_pool: Arc<PgPool>,
+}
+
+impl WebPushRepo {
+    pub fn new(pg_pool: Arc<PgPool>) -> Self {
+        Self { pg_pool }
+    }
+
+    pub async fn find_subscription(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<WebPushSubscription>, sqlx::Error> {
+        let stm = include_str!("../queries/select_webpush_subscription.sql");
+
+        sqlx::query_as::<_, WebPushSubscription>(stm)
+            .bind(id)
+            .fetch_optional(&*self.pg_pool)
+            .await
+    }
+
+    pub async fn delete_subscription(&self, id: &Uuid) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/delete_webpush_subscription.sql");
+
+        let result = sqlx::query(stm).bind(id).execute(&*self.pg_pool).await?;
+
+        let rows_affected = result.rows_affected();
+        info!("Rows affected: {}", rows_affected);
+
+        Ok(rows_affected)
+    }
+}
@@ -0,0 +1,240 @@
+use std::{env, sync::Arc};
+
+use log::{error, info, warn};
+use uuid::Uuid;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::topic::{
+        IidBatchResponse, TopicSubscription, TopicSubscriptionRequest, TopicSubscriptionResponse,
+        TopicTokenResult,
+    },
+    repositories::topic_repository::TopicRepo,
+    utils::fcm_token_manager::TokenManager,
+};
+
+// FCM accepts at most 1000 registration tokens per batch request
+const MAX_TOKENS_PER_BATCH: usize = 1000;
+// FCM topic names are limited to 900 characters
+const MAX_TOPIC_LENGTH: usize = 900;
+
+/// Operation performed against the FCM Instance ID batch API
+#[derive(Debug, Clone, Copy)]
+enum TopicOperation {
+    Subscribe,
+    Unsubscribe,
+}
+
+impl TopicOperation {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            TopicOperation::Subscribe => "iid/v1:batchAdd",
+            TopicOperation::Unsubscribe => "iid/v1:batchRemove",
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match self {
+            TopicOperation::Subscribe => "subscribed",
+            TopicOperation::Unsubscribe => "unsubscribed",
+        }
+    }
+}
+
+/// `TopicService` manages FCM topic memberships of device tokens.
+/// Every operation is forwarded to the FCM Instance ID API and recorded locally for auditing
+pub struct TopicService {
+    client: reqwest::Client,
+    base_url: String,
+    token_manager: Arc<TokenManager>,
+    topic_repo: Arc<TopicRepo>,
+}
+
+impl TopicService {
+    pub fn new(token_manager: Arc<TokenManager>, topic_repo: Arc<TopicRepo>) -> Self {
+        // The base URL can be overridden to point at a local mock server
+        let base_url = env::var("FCM_IID_BASE_URL")
+            .unwrap_or_else(|_| "https://iid.googleapis.com".to_string());
+
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token_manager,
+            topic_repo,
+        }
+    }
+
+    pub async fn subscribe(
+        &self,
+        request: TopicSubscriptionRequest,
+    ) -> Result<TopicSubscriptionResponse, NotiDeliverError> {
+        self.execute(TopicOperation::Subscribe, request).await
+    }
+
+    pub async fn unsubscribe(
+        &self,
+        request: TopicSubscriptionRequest,
+    ) -> Result<TopicSubscriptionResponse, NotiDeliverError> {
+        self.execute(TopicOperation::Unsubscribe, request).await
+    }
+
+    /// Lists the recorded memberships of a topic for a user
+    pub async fn list(
+        &self,
+        user_id: &str,
+        topic: &str,
+    ) -> Result<Vec<TopicSubscription>, NotiDeliverError> {
+        let user_id = Self::parse_user_id(user_id)?;
+        Self::validate_topic(topic)?;
+
+        self.topic_repo
+            .find_by_topic(&user_id, topic)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiDeliverError::DatabaseError(e)
+            })
+    }
+
+    async fn execute(
+        &self,
+        operation: TopicOperation,
+        request: TopicSubscriptionRequest,
+    ) -> Result<TopicSubscriptionResponse, NotiDeliverError> {
+        let user_id = Self::parse_user_id(&request.user_id)?;
+        Self::validate_topic(&request.topic)?;
+
+        if request.tokens.is_empty() || request.tokens.len() > MAX_TOKENS_PER_BATCH {
+            return Err(NotiDeliverError::InvalidDataField(format!(
+                "'tokens' must contain between 1 and {} device tokens",
+                MAX_TOKENS_PER_BATCH
+            )));
+        }
+        if request.tokens.iter().any(|token| token.trim().is_empty()) {
+            return Err(NotiDeliverError::InvalidDataField(
+                "'tokens' must not contain empty values".to_string(),
+            ));
+        }
+
+        let batch = self
+            .send_batch(operation, &request.topic, &request.tokens)
+            .await?;
+
+        // FCM returns one result per token, in the same order as the request
+        let mut results = Vec::with_capacity(request.tokens.len());
+        for (index, token) in request.tokens.into_iter().enumerate() {
+            let error = batch
+                .results
+                .get(index)
+                .and_then(|result| result.error.clone());
+
+            let status = match error {
+                Some(_) => "failed",
+                None => operation.status(),
+            };
+
+            self.topic_repo
+                .upsert_subscription(&user_id, &request.topic, &token, status, error.as_deref())
+                .await
+                .map_err(|e| {
+                    error!("Database upsert error: {}", e);
+                    NotiDeliverError::DatabaseError(e)
+                })?;
+
+            results.push(TopicTokenResult {
+                token,
+                success: error.is_none(),
+                error,
+            });
+        }
+
+        Ok(TopicSubscriptionResponse {
+            topic: request.topic,
+            results,
+        })
+    }
+
+    /// Sends a batch request to the Instance ID API, refreshing the access token once if it expired
+    async fn send_batch(
+        &self,
+        operation: TopicOperation,
+        topic: &str,
+        tokens: &[String],
+    ) -> Result<IidBatchResponse, NotiDeliverError> {
+        let url = format!("{}/{}", self.base_url, operation.endpoint());
+        let body = serde_json::json!({
+            "to": format!("/topics/{}", topic),
+            "registration_tokens": tokens,
+        });
+
+        for _i in 0..2 {
+            let token = match self.token_manager.get_token() {
+                Some(token) => token,
+                None => {
+                    error!("Empty token");
+                    return Err(NotiDeliverError::NoneValue);
+                }
+            };
+
+            let response = self
+                .client
+                .post(&url)
+                .bearer_auth(token.as_str())
+                .header("access_token_auth", "true")
+                .header("Content-Type", "application/json")
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Can not send request: {}", e);
+                    NotiDeliverError::RequestError(e)
+                })?;
+
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                warn!("Token expired, refreshing token...");
+                self.token_manager.update_token().await;
+                continue;
+            }
+
+            if !response.status().is_success() {
+                error!("Instance ID request failed: {}", response.status());
+                return Err(NotiDeliverError::RequestFailed);
+            }
+
+            info!("Topic {:?} request sent for '{}'", operation, topic);
+            let text = response.text().await.map_err(|e| {
+                error!("Can not read response: {}", e);
+                NotiDeliverError::RequestError(e)
+            })?;
+            return serde_json::from_str::<IidBatchResponse>(&text).map_err(|e| {
+                error!("Invalid Instance ID response: {}", e);
+                NotiDeliverError::JsonParseError
+            });
+        }
+
+        error!("Can not send request");
+        Err(NotiDeliverError::RequestFailed)
+    }
+
+    fn parse_user_id(user_id: &str) -> Result<Uuid, NotiDeliverError> {
+        Uuid::parse_str(user_id)
+            .map_err(|_| NotiDeliverError::InvalidDataField("Invalid 'user_id'".to_string()))
+    }
+
+    /// Topic names must match `[a-zA-Z0-9-_.~%]{1,900}`
+    fn validate_topic(topic: &str) -> Result<(), NotiDeliverError> {
+        let valid = !topic.is_empty()
+            && topic.len() <= MAX_TOPIC_LENGTH
+            && topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.~%".contains(c));
+
+        if !valid {
+            return Err(NotiDeliverError::InvalidDataField(format!(
+                "Invalid topic name '{}'",
+                topic
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Calls go through, failures are tracked
+    Closed,
+    /// Calls are short-circuited until `open_duration` elapsed
+    Open,
+    /// A single probe call is allowed to test whether the provider recovered
+    HalfOpen,
+}
+
+/// Thresholds shared by every circuit breaker
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    /// Consecutive failures opening the breaker
+    pub failure_threshold: u32,
+    /// Error rate (0.0 - 1.0) over the sliding window opening the breaker
+    pub error_rate_threshold: f64,
+    /// Number of recent calls the error rate is computed on
+    pub window_size: usize,
+    /// Time the breaker stays open before a probe is allowed
+    pub open_duration: Duration,
+}
+
+impl BreakerConfig {
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            failure_threshold: parse("BREAKER_FAILURE_THRESHOLD", 5),
+            error_rate_threshold: parse("BREAKER_ERROR_RATE", 0.5),
+            window_size: parse("BREAKER_WINDOW_SIZE", 20),
+            open_duration: Duration::from_secs(parse("BREAKER_OPEN_SECS", 30)),
+        }
+    }
+}
+
+/// Public view of a circuit breaker, exposed through the API
+#[derive(Debug, Serialize)]
+pub struct BreakerSnapshot {
+    pub provider: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub error_rate: f64,
+    pub calls_in_window: usize,
+    /// Seconds before an open breaker allows a probe
+    pub retry_in_secs: Option<u64>,
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    // Outcome of the most recent calls, `true` for failures
+    window: VecDeque<bool>,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// `CircuitBreaker` protects a provider: once it keeps failing, calls are short-circuited
+/// so notifications can fail over or wait instead of burning their retries
+pub struct CircuitBreaker {
+    provider: String,
+    config: BreakerConfig,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(provider: &str, config: BreakerConfig) -> Self {
+        Self {
+            provider: provider.to_string(),
+            config,
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                window: VecDeque::new(),
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// Returns whether a call may go through, moving an expired open breaker to half-open
+    pub fn try_acquire(&self) -> bool {
+        let Ok(mut inner) = self.inner.lock() else {
+            error!("Cannot lock circuit breaker of {}", self.provider);
+            return true;
+        };
+
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open => {
+                let expired = inner
+                    .opened_at
+                    .is_none_or(|opened_at| opened_at.elapsed() >= self.config.open_duration);
+                if expired {
+                    info!("Circuit breaker of {} is half-open", self.provider);
+                    inner.state = BreakerState::HalfOpen;
+                    inner.probe_in_flight = true;
+                }
+                expired
+            }
+            BreakerState::HalfOpen => {
+                if inner.probe_in_flight {
+                    false
+                } else {
+                    inner.probe_in_flight = true;
+                    true
+                }
+            }
+        }
+    }
+
+    /// Gives back a call acquired but not made, freeing the probe of a half-open breaker
+    pub fn release(&self) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        if inner.state == BreakerState::HalfOpen {
+            inner.probe_in_flight = false;
+        }
+    }
+
+    /// Time left before an open breaker allows a probe
+    pub fn retry_in(&self) -> Option<Duration> {
+        let inner = self.inner.lock().ok()?;
+        match inner.state {
+            BreakerState::Open => inner.opened_at.map(|opened_at| {
+                self.config
+                    .open_duration
+                    .saturating_sub(opened_at.elapsed())
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        if inner.state != BreakerState::Closed {
+            info!("Circuit breaker of {} is closed", self.provider);
+            inner.window.clear();
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+        self.push_outcome(&mut inner, false);
+    }
+
+    pub fn record_failure(&self) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        inner.consecutive_failures += 1;
+        self.push_outcome(&mut inner, true);
+
+        let should_open = match inner.state {
+            // A failed probe means the provider is still down
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => {
+                inner.consecutive_failures >= self.config.failure_threshold
+                    || (inner.window.len() >= self.config.window_size
+                        && Self::error_rate(&inner) >= self.config.error_rate_threshold)
+            }
+            BreakerState::Open => false,
+        };
+
+        if should_open {
+            warn!("Circuit breaker of {} is open", self.provider);
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.probe_in_flight = false;
+        }
+    }
+
+    pub fn snapshot(&self) -> Option<BreakerSnapshot> {
+        let inner = self.inner.lock().ok()?;
+
+        Some(BreakerSnapshot {
+            provider: self.provider.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            error_rate: Self::error_rate(&inner),
+            calls_in_window: inner.window.len(),
+            retry_in_secs: match inner.state {
+                BreakerState::Open => inner.opened_at.map(|opened_at| {
+                    self.config
+                        .open_duration
+                        .saturating_sub(opened_at.elapsed())
+                        .as_secs()
+                }),
+                _ => None,
+            },
+        })
+    }
+
+    fn push_outcome(&self, inner: &mut BreakerInner, failed: bool) {
+        inner.window.push_back(failed);
+        while inner.window.len() > self.config.window_size {
+            inner.window.pop_front();
+        }
+    }
+
+    fn error_rate(inner: &BreakerInner) -> f64 {
+        if inner.window.is_empty() {
+            return 0.0;
+        }
+        let failures = inner.window.iter().filter(|failed| **failed).count();
+        failures as f64 / inner.window.len() as f64
+    }
+}
+
+/// Holds one `CircuitBreaker` per provider
+pub struct CircuitBreakerRegistry {
+    config: BreakerConfig,
+    breakers: RwLock<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+impl CircuitBreakerRegistry {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the breaker of a provider, creating it on first use
+    pub fn get(&self, provider: &str) -> Arc<CircuitBreaker> {
+        if let Ok(breakers) = self.breakers.read() {
+            if let Some(breaker) = breakers.get(provider) {
+                return breaker.clone();
+            }
+        }
+
+        match self.breakers.write() {
+            Ok(mut breakers) => breakers
+                .entry(provider.to_string())
+                .or_insert_with(|| Arc::new(CircuitBreaker::new(provider, self.config.clone())))
+                .clone(),
+            Err(_) => {
+                error!("Cannot register circuit breaker of {}", provider);
+                Arc::new(CircuitBreaker::new(provider, self.config.clone()))
+            }
+        }
+    }
+
+    pub fn snapshots(&self) -> Vec<BreakerSnapshot> {
+        let mut snapshots: Vec<BreakerSnapshot> = match self.breakers.read() {
+            Ok(breakers) => breakers
+                .values()
+                .filter_map(|breaker| breaker.snapshot())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        snapshots.sort_by(|a, b| a.provider.cmp(&b.provider));
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "sendgrid",
+            BreakerConfig {
+                failure_threshold: 3,
+                error_rate_threshold: 0.5,
+                window_size: 4,
+                open_duration,
+            },
+        )
+    }
+
+    fn state(breaker: &CircuitBreaker) -> BreakerState {
+        breaker.snapshot().unwrap().state
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        for _ in 0..2 {
+            assert!(breaker.try_acquire());
+            breaker.record_failure();
+        }
+        assert_eq!(state(&breaker), BreakerState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(state(&breaker), BreakerState::Open);
+        assert!(!breaker.try_acquire());
+        assert!(breaker.retry_in().unwrap() > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn opens_on_error_rate_over_a_full_window() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(state(&breaker), BreakerState::Closed);
+
+        // Half of the last 4 calls failed
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(state(&breaker), BreakerState::Open);
+    }
+
+    #[test]
+    fn success_resets_consecutive_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_success();
+        breaker.record_success();
+        breaker.record_failure();
+        let snapshot = breaker.snapshot().unwrap();
+        assert_eq!(snapshot.state, BreakerState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 1);
+        assert_eq!(snapshot.error_rate, 0.25);
+    }
+
+    #[test]
+    fn half_open_allows_a_single_probe() {
+        let breaker = breaker(Duration::ZERO);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+
+        assert!(breaker.try_acquire());
+        assert_eq!(state(&breaker), BreakerState::HalfOpen);
+        assert!(!breaker.try_acquire());
+
+        // A probe given back without a call lets the next one through
+        breaker.release();
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+    }
+
+    #[test]
+    fn probe_outcome_closes_or_reopens() {
+        let breaker = breaker(Duration::ZERO);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert!(breaker.try_acquire());
+        breaker.record_failure();
+        assert_eq!(state(&breaker), BreakerState::Open);
+
+        assert!(breaker.try_acquire());
+        breaker.record_success();
+        let snapshot = breaker.snapshot().unwrap();
+        assert_eq!(snapshot.state, BreakerState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert_eq!(snapshot.calls_in_window, 1);
+        assert!(breaker.try_acquire());
+    }
+
+    #[test]
+    fn release_leaves_closed_breakers_alone() {
+        let breaker = breaker(Duration::from_secs(60));
+        assert!(breaker.try_acquire());
+        breaker.release();
+        assert_eq!(state(&breaker), BreakerState::Closed);
+        assert!(breaker.try_acquire());
+    }
+}
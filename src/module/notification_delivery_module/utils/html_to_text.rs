@@ -0,0 +1,274 @@
+/// Converts an HTML document to the plain text alternative of an email
+///
+/// Keeps the readable structure of the document: paragraphs and headings are separated by
+/// blank lines, list items are prefixed with `- ` and links are followed by their target, e.g.
+/// `Read more (https://example.com)`. Scripts, styles, the head of the document and comments
+/// are dropped
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    // Closing tag of the element whose content is dropped (`script`, `style`, ...)
+    let mut skipped: Option<String> = None;
+    // Target of the open link, and where its label starts in `text`
+    let mut link: Option<(String, usize)> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skipped.is_none() {
+            push_text(&mut text, &rest[..start]);
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            // Not a tag, e.g. `a < b`
+            if skipped.is_none() {
+                push_text(&mut text, rest);
+            }
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if let Some(skipped_name) = &skipped {
+            if closing && &name == skipped_name {
+                skipped = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("script" | "style" | "head" | "title", false) => skipped = Some(name),
+            ("br", _) => push_line_break(&mut text),
+            ("p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "table" | "ul" | "ol", _)
+            | ("blockquote" | "hr", _) => push_paragraph_break(&mut text),
+            ("div" | "tr" | "section" | "article" | "header" | "footer", _) => {
+                push_line_break(&mut text)
+            }
+            ("li", false) => {
+                push_line_break(&mut text);
+                text.push_str("- ");
+            }
+            ("td" | "th", false) => push_text(&mut text, " "),
+            ("a", false) => link = attribute(tag, "href").map(|href| (href, text.len())),
+            ("a", true) => {
+                if let Some((href, label_start)) = link.take() {
+                    let label = text[label_start..].trim();
+                    let readable = href.starts_with("http://")
+                        || href.starts_with("https://")
+                        || href.starts_with("mailto:");
+                    if readable && label != href && label != href.trim_start_matches("mailto:") {
+                        push_text(&mut text, &format!(" ({})", href));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    if skipped.is_none() {
+        push_text(&mut text, rest);
+    }
+
+    // Trim every line and keep at most one blank line between paragraphs
+    let mut output = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !output.is_empty() {
+            output.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        output.push_str(line);
+        blank_lines = 0;
+    }
+    output
+}
+
+/// Appends text content, collapsing whitespace as a browser would
+fn push_text(text: &mut String, content: &str) {
+    for c in decode_entities(content).chars() {
+        if c.is_whitespace() {
+            if !text.is_empty() && !text.ends_with([' ', '\n']) {
+                text.push(' ');
+            }
+        } else {
+            text.push(c);
+        }
+    }
+}
+
+fn push_line_break(text: &mut String) {
+    let trimmed = text.trim_end_matches(' ').len();
+    text.truncate(trimmed);
+    text.push('\n');
+}
+
+fn push_paragraph_break(text: &mut String) {
+    push_line_break(text);
+    text.push('\n');
+}
+
+/// Reads the quoted value of an attribute of a tag, with its entities decoded
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+
+    while let Some(found) = lower[from..].find(name) {
+        let after_name = &tag[from + found + name.len()..];
+        // The name must not be the end of another attribute, e.g. `data-href`
+        let standalone = lower[..from + found].ends_with(char::is_whitespace);
+        from += found + name.len();
+        if !standalone {
+            continue;
+        }
+
+        let Some(value) = after_name.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        let end = value.find(quote)?;
+        return Some(decode_entities(value[..end].trim()));
+    }
+    None
+}
+
+/// Decodes the named entities common in emails and every numeric entity
+fn decode_entities(content: &str) -> String {
+    let mut decoded = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // Entities are short, a far `;` belongs to the text
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "hellip" => Some('…'),
+            "mdash" => Some('—'),
+            "ndash" => Some('–'),
+            _ => {
+                let code = match entity.strip_prefix('#')? {
+                    hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16),
+                    decimal => decimal.parse(),
+                };
+                code.ok().and_then(char::from_u32)
+            }
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_blocks() {
+        assert_eq!(
+            html_to_text("<h1>Title</h1><p>First</p><p>Second<br>line</p><div>Div</div>"),
+            "Title\n\nFirst\n\nSecond\nline\n\nDiv"
+        );
+    }
+
+    #[test]
+    fn collapses_whitespace() {
+        assert_eq!(html_to_text("<p>  Hello \n\t  world  </p>"), "Hello world");
+    }
+
+    #[test]
+    fn lists_items() {
+        assert_eq!(
+            html_to_text("<ul><li>One</li><li>Two</li></ul>"),
+            "- One\n- Two"
+        );
+    }
+
+    #[test]
+    fn appends_link_targets() {
+        assert_eq!(
+            html_to_text(r#"<a href="https://example.com">Read more</a>"#),
+            "Read more (https://example.com)"
+        );
+        assert_eq!(
+            html_to_text(r#"<a href="https://example.com">https://example.com</a>"#),
+            "https://example.com"
+        );
+        assert_eq!(
+            html_to_text(r#"<a href="mailto:team@example.com">team@example.com</a>"#),
+            "team@example.com"
+        );
+        assert_eq!(html_to_text(r##"<a href="#top">Top</a>"##), "Top");
+    }
+
+    #[test]
+    fn reads_href_not_data_href() {
+        assert_eq!(
+            html_to_text(r#"<a data-href="https://a.example" href="https://b.example">B</a>"#),
+            "B (https://b.example)"
+        );
+    }
+
+    #[test]
+    fn drops_scripts_styles_head_and_comments() {
+        let html = concat!(
+            "<html><head><title>T</title><style>p { color: red }</style></head>",
+            "<body><!-- hidden --><script>alert(1)</script><p>Shown</p></body></html>"
+        );
+        assert_eq!(html_to_text(html), "Shown");
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            html_to_text("Tom &amp; Jerry &lt;3 &#169; &#x2014; &nbsp;x &unknown; R&D"),
+            "Tom & Jerry <3 \u{a9} \u{2014} x &unknown; R&D"
+        );
+    }
+
+    #[test]
+    fn keeps_text_that_is_not_a_tag() {
+        assert_eq!(html_to_text("a < b"), "a < b");
+        assert_eq!(html_to_text("<p>unclosed"), "unclosed");
+        assert_eq!(html_to_text(""), "");
+    }
+}
@@ -0,0 +1,25 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+/// Builds a compact JWT signed with ES256 (ECDSA P-256 + SHA-256)
+///
+/// The signature is encoded as the raw `r || s` pair, as required by RFC 7518
+pub fn sign_es256(
+    header: &serde_json::Value,
+    claims: &serde_json::Value,
+    key: &SigningKey,
+) -> String {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let signature: Signature = key.sign(signing_input.as_bytes());
+
+    format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
@@ -0,0 +1,122 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lettre::{
+    message::{
+        header::{ContentType, HeaderName, HeaderValue},
+        Attachment, Mailbox, MultiPart, SinglePart,
+    },
+    Message,
+};
+
+use crate::module::notification_delivery_module::models::email_payload::{
+    Attachments, EmailAddress, OutgoingEmail,
+};
+
+/// Builds a MIME message from an `OutgoingEmail`
+///
+/// The body is a `multipart/alternative` of the plain text and HTML parts, or a single plain
+/// text part. Inline attachments are grouped with the HTML part in a `multipart/related`
+/// part. With other attachments, the body and every attachment are wrapped in a
+/// `multipart/mixed` message
+pub fn build_message(email: &OutgoingEmail) -> Result<Message, String> {
+    let from = address_mailbox(&email.from)?;
+
+    let mut builder = Message::builder().from(from).subject(email.subject.clone());
+    for address in &email.to {
+        builder = builder.to(address_mailbox(address)?);
+    }
+    for address in &email.cc {
+        builder = builder.cc(address_mailbox(address)?);
+    }
+    // Kept in the envelope only, lettre drops the `Bcc` header from the message
+    for address in &email.bcc {
+        builder = builder.bcc(address_mailbox(address)?);
+    }
+
+    if let Some(reply_to) = &email.reply_to {
+        let address = reply_to
+            .email
+            .parse()
+            .map_err(|e| format!("invalid reply_to address '{}': {}", reply_to.email, e))?;
+        builder = builder.reply_to(Mailbox::new(Some(reply_to.name.clone()), address));
+    }
+
+    for (name, value) in &email.headers {
+        let name = HeaderName::new_from_ascii(name.clone())
+            .map_err(|e| format!("invalid header name '{}': {}", name, e))?;
+        builder = builder.raw_header(HeaderValue::new(name, value.clone()));
+    }
+
+    // Inline attachments are shown by the HTML part, a plain text email attaches them
+    let (inline, attached): (Vec<&Attachments>, Vec<&Attachments>) = email
+        .attachments
+        .iter()
+        .partition(|attachment| attachment.is_inline() && email.html.is_some());
+
+    let body = match &email.html {
+        None => Body::Single(SinglePart::plain(email.text.clone())),
+        Some(html) if inline.is_empty() => Body::Multi(MultiPart::alternative_plain_html(
+            email.text.clone(),
+            html.clone(),
+        )),
+        Some(html) => {
+            let mut related = MultiPart::related().singlepart(SinglePart::html(html.clone()));
+            for attachment in inline {
+                related = related.singlepart(attachment_part(attachment)?);
+            }
+            Body::Multi(
+                MultiPart::alternative()
+                    .singlepart(SinglePart::plain(email.text.clone()))
+                    .multipart(related),
+            )
+        }
+    };
+
+    let message = if attached.is_empty() {
+        match body {
+            Body::Single(part) => builder.singlepart(part),
+            Body::Multi(multipart) => builder.multipart(multipart),
+        }
+    } else {
+        let mut mixed = match body {
+            Body::Single(part) => MultiPart::mixed().singlepart(part),
+            Body::Multi(multipart) => MultiPart::mixed().multipart(multipart),
+        };
+        for attachment in attached {
+            mixed = mixed.singlepart(attachment_part(attachment)?);
+        }
+        builder.multipart(mixed)
+    };
+
+    message.map_err(|e| format!("cannot build message: {}", e))
+}
+
+enum Body {
+    Single(SinglePart),
+    Multi(MultiPart),
+}
+
+fn attachment_part(attachment: &Attachments) -> Result<SinglePart, String> {
+    let content = STANDARD
+        .decode(&attachment.content)
+        .map_err(|e| format!("invalid attachment '{}': {}", attachment.filename, e))?;
+    let content_type = ContentType::parse(&attachment.r#type)
+        .map_err(|e| format!("invalid attachment type '{}': {}", attachment.r#type, e))?;
+
+    let part = if attachment.is_inline() {
+        Attachment::new_inline_with_name(
+            attachment.inline_id().to_string(),
+            attachment.filename.clone(),
+        )
+    } else {
+        Attachment::new(attachment.filename.clone())
+    };
+    Ok(part.body(content, content_type))
+}
+
+fn address_mailbox(address: &EmailAddress) -> Result<Mailbox, String> {
+    let email = address
+        .email
+        .parse()
+        .map_err(|e| format!("invalid address '{}': {}", address.email, e))?;
+    Ok(Mailbox::new(address.name.clone(), email))
+}
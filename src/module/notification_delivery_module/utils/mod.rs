@@ -1 +1,8 @@
+pub mod circuit_breaker;
 pub mod fcm_token_manager;
+pub mod html_to_text;
+pub mod jwt;
+pub mod mime_builder;
+pub mod rate_limiter;
+pub mod sigv4;
+pub mod webpush_crypto;
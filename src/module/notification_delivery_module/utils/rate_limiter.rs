@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::module::notification_delivery_module::errors::NotiDeliverError;
+
+// Delay applied to a throttled request whose response has no usable `Retry-After` header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+// Longest back off honored, `Retry-After` is set by the provider
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+struct TokenBucket {
+    // Tokens added per second
+    rate: f64,
+    // Maximum number of tokens, i.e. the allowed burst
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+    // Set when the provider asked us to back off with `Retry-After`
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, capacity: f64) -> Self {
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    /// Takes a token, or returns the time to wait before one is available
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(paused_until) = self.paused_until {
+            if paused_until > now {
+                return Err(paused_until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// `RateLimiter` keeps outbound traffic under the quotas of the providers with one token
+/// bucket per key
+///
+/// Keys are provider names (`fcm`, `apns`, `webpush`, `sendgrid`, ...) and sender domains
+/// (`domain:example.com`). Limits are configured with `RATE_LIMITS`, comma separated rules
+/// `<key>=<per_second>[:<burst>]` such as `fcm=500,sendgrid=100:200,domain:example.com=10`.
+/// Keys without a rule are not limited
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn from_env() -> Self {
+        let mut buckets = HashMap::new();
+
+        if let Ok(rules) = env::var("RATE_LIMITS") {
+            for rule in rules.split(',').filter(|rule| !rule.trim().is_empty()) {
+                let (key, limit) = rule
+                    .rsplit_once('=')
+                    .unwrap_or_else(|| panic!("Invalid RATE_LIMITS rule: {}", rule));
+                let (rate, burst) = match limit.split_once(':') {
+                    Some((rate, burst)) => (rate, Some(burst)),
+                    None => (limit, None),
+                };
+
+                let rate: f64 = rate
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|rate: &f64| *rate > 0.0)
+                    .unwrap_or_else(|| panic!("Invalid RATE_LIMITS rate: {}", rule));
+                let capacity: f64 = match burst {
+                    Some(burst) => burst
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|burst: &f64| *burst >= 1.0)
+                        .unwrap_or_else(|| panic!("Invalid RATE_LIMITS burst: {}", rule)),
+                    None => rate.max(1.0),
+                };
+
+                let key = key.trim().to_lowercase();
+                info!("Rate limit of {}: {}/s (burst {})", key, rate, capacity);
+                buckets.insert(key, TokenBucket::new(rate, capacity));
+            }
+        }
+
+        Self {
+            buckets: Mutex::new(buckets),
+        }
+    }
+
+    /// Takes a token from the bucket of every key, or returns the longest time to wait
+    ///
+    /// Tokens are only taken when every bucket has one, so a throttled request does not
+    /// consume the quota of the other keys
+    pub fn try_acquire(&self, keys: &[&str]) -> Result<(), Duration> {
+        let Ok(mut buckets) = self.buckets.lock() else {
+            error!("Cannot lock rate limiter");
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut taken = Vec::new();
+        let mut wait = Duration::ZERO;
+
+        for key in keys {
+            let key = key.to_lowercase();
+            if let Some(bucket) = buckets.get_mut(&key) {
+                match bucket.try_take(now) {
+                    Ok(()) => taken.push(key),
+                    Err(delay) => wait = wait.max(delay),
+                }
+            }
+        }
+
+        if wait.is_zero() {
+            return Ok(());
+        }
+
+        // Give back the tokens taken before a bucket ran out
+        for key in taken {
+            if let Some(bucket) = buckets.get_mut(&key) {
+                bucket.tokens = (bucket.tokens + 1.0).min(bucket.capacity);
+            }
+        }
+        Err(wait)
+    }
+
+    /// Blocks a key for the given delay, at most `MAX_RETRY_AFTER`, after the provider
+    /// answered with `Retry-After`
+    pub fn pause(&self, key: &str, delay: Duration) {
+        let Ok(mut buckets) = self.buckets.lock() else {
+            error!("Cannot lock rate limiter");
+            return;
+        };
+
+        let delay = delay.min(MAX_RETRY_AFTER);
+        warn!("{} is throttling requests, pausing for {:?}", key, delay);
+        let until = Instant::now() + delay;
+        let bucket = buckets
+            .entry(key.to_lowercase())
+            // Unlimited keys still honor the pause, the bucket itself never runs out
+            .or_insert_with(|| TokenBucket::new(f64::MAX, f64::MAX));
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date
+///
+/// The delay is capped at `MAX_RETRY_AFTER`
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&Utc) - Utc::now()).to_std().ok()?
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// Builds the error of a throttled (429) response, deferring the notification for the time
+/// requested by the provider
+pub fn throttled_error(provider: &str, headers: &HeaderMap) -> NotiDeliverError {
+    let delay = retry_after(headers).unwrap_or(DEFAULT_RETRY_AFTER);
+    warn!(
+        "{} throttled the request, retrying in {:?}",
+        provider, delay
+    );
+    NotiDeliverError::Deferred(delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    fn limiter(rules: &[(&str, f64, f64)]) -> RateLimiter {
+        RateLimiter {
+            buckets: Mutex::new(
+                rules
+                    .iter()
+                    .map(|(key, rate, capacity)| {
+                        (key.to_string(), TokenBucket::new(*rate, *capacity))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers(" 0 ")), Some(Duration::ZERO));
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn caps_retry_after() {
+        assert_eq!(
+            retry_after(&headers("18446744073709551615")),
+            Some(MAX_RETRY_AFTER)
+        );
+        let far = (Utc::now() + chrono::Duration::days(30)).to_rfc2822();
+        assert_eq!(retry_after(&headers(&far)), Some(MAX_RETRY_AFTER));
+    }
+
+    #[test]
+    fn parses_retry_after_dates() {
+        let later = (Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        let delay = retry_after(&headers(&later)).unwrap();
+        assert!(delay > Duration::from_secs(80) && delay <= Duration::from_secs(90));
+
+        // A date in the past is not a delay
+        let past = (Utc::now() - chrono::Duration::seconds(90)).to_rfc2822();
+        assert_eq!(retry_after(&headers(&past)), None);
+    }
+
+    #[test]
+    fn buckets_allow_bursts_then_refill() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 3.0);
+        bucket.last_refill = start;
+
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(start), Ok(()));
+        }
+        assert_eq!(bucket.try_take(start), Err(Duration::from_millis(500)));
+
+        // Half a second brings one token back, never more than the capacity
+        assert_eq!(bucket.try_take(start + Duration::from_millis(500)), Ok(()));
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(later), Ok(()));
+        }
+        assert!(bucket.try_take(later).is_err());
+    }
+
+    #[test]
+    fn acquires_every_key_or_none() {
+        let limiter = limiter(&[("sendgrid", 1.0, 2.0), ("domain:example.com", 1.0, 1.0)]);
+
+        assert_eq!(
+            limiter.try_acquire(&["SendGrid", "domain:example.com"]),
+            Ok(())
+        );
+        assert!(limiter
+            .try_acquire(&["sendgrid", "domain:example.com"])
+            .is_err());
+        // The sendgrid token was given back when the domain ran out
+        assert_eq!(limiter.try_acquire(&["sendgrid", "unlimited"]), Ok(()));
+        assert!(limiter.try_acquire(&["sendgrid"]).is_err());
+    }
+
+    #[test]
+    fn pauses_keys() {
+        let limiter = limiter(&[("fcm", 100.0, 100.0)]);
+
+        limiter.pause("fcm", Duration::from_secs(30));
+        let wait = limiter.try_acquire(&["fcm"]).unwrap_err();
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+
+        // A shorter pause does not shorten the current one
+        limiter.pause("fcm", Duration::from_secs(1));
+        assert!(limiter.try_acquire(&["fcm"]).unwrap_err() > Duration::from_secs(29));
+
+        // Unlimited keys are paused too, and huge delays are capped
+        limiter.pause("apns", Duration::MAX);
+        let wait = limiter.try_acquire(&["apns"]).unwrap_err();
+        assert!(wait <= MAX_RETRY_AFTER && wait > MAX_RETRY_AFTER - Duration::from_secs(1));
+    }
+}
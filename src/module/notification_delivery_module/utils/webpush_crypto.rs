@@ -0,0 +1,207 @@
+use aes_gcm::{aead::Aead, Aes128Gcm, KeyInit, Nonce};
+use hkdf::Hkdf;
+use p256::{ecdh::diffie_hellman, elliptic_curve::sec1::ToEncodedPoint, PublicKey, SecretKey};
+use rand::rngs::OsRng;
+use sha2::Sha256;
+
+// Size of the single record used for every message
+const RECORD_SIZE: u32 = 4096;
+// Header of the body: salt (16), record size (4), key id length (1) and key id, the
+// uncompressed application server key (65)
+const HEADER_SIZE: usize = 16 + 4 + 1 + 65;
+// Push services accept bodies of at most 4096 bytes, header included. The record also holds
+// the padding delimiter and the 16 bytes AEAD tag next to the plaintext
+pub const MAX_PLAINTEXT_SIZE: usize = 4096 - HEADER_SIZE - 17;
+
+/// Encrypts a push message for a browser subscription as described in RFC 8291, using the
+/// `aes128gcm` content coding of RFC 8188
+///
+/// `ua_public` is the subscription `p256dh` key and `auth_secret` its `auth` secret, both decoded
+pub fn encrypt(plaintext: &[u8], ua_public: &[u8], auth_secret: &[u8]) -> Result<Vec<u8>, String> {
+    if plaintext.len() > MAX_PLAINTEXT_SIZE {
+        return Err(format!(
+            "payload exceeds {} bytes after serialization",
+            MAX_PLAINTEXT_SIZE
+        ));
+    }
+
+    // Ephemeral application server key pair and salt, used for this message only
+    let as_secret = SecretKey::random(&mut OsRng);
+    let salt: [u8; 16] = rand::random();
+
+    encrypt_with(plaintext, ua_public, auth_secret, &as_secret, &salt)
+}
+
+/// Encrypts with the given application server key and salt, both random outside of tests
+fn encrypt_with(
+    plaintext: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    as_secret: &SecretKey,
+    salt: &[u8; 16],
+) -> Result<Vec<u8>, String> {
+    let ua_key = PublicKey::from_sec1_bytes(ua_public).map_err(|_| "invalid p256dh key")?;
+
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let ecdh_secret = diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    // IKM = HKDF(auth_secret, ecdh_secret, "WebPush: info" || 0x00 || ua_public || as_public)
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(as_public.as_bytes());
+
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), ecdh_secret.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| "cannot derive input keying material")?;
+
+    // Content encryption key and nonce are derived from the salt
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+
+    let mut cek = [0u8; 16];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|_| "cannot derive content encryption key")?;
+    let mut nonce = [0u8; 12];
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|_| "cannot derive nonce")?;
+
+    // The single (and last) record is terminated by the 0x02 padding delimiter
+    let mut record = plaintext.to_vec();
+    record.push(0x02);
+
+    let cipher = Aes128Gcm::new_from_slice(&cek).map_err(|_| "invalid content encryption key")?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .map_err(|_| "cannot encrypt payload")?;
+
+    // Header: salt (16) || record size (4) || key id length (1) || key id (as_public)
+    let mut body = Vec::with_capacity(HEADER_SIZE + ciphertext.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    use super::*;
+
+    /// Decrypts a body as the user agent does
+    fn decrypt(body: &[u8], ua_secret: &SecretKey, auth_secret: &[u8]) -> Vec<u8> {
+        let salt = &body[..16];
+        let key_id_length = body[20] as usize;
+        let as_public = &body[21..21 + key_id_length];
+        let ciphertext = &body[21 + key_id_length..];
+
+        let ua_public = ua_secret.public_key().to_encoded_point(false);
+        let as_key = PublicKey::from_sec1_bytes(as_public).unwrap();
+        let ecdh_secret =
+            p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_key.as_affine());
+
+        let mut key_info = b"WebPush: info\0".to_vec();
+        key_info.extend_from_slice(ua_public.as_bytes());
+        key_info.extend_from_slice(as_public);
+        let mut ikm = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(auth_secret), ecdh_secret.raw_secret_bytes())
+            .expand(&key_info, &mut ikm)
+            .unwrap();
+
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+        let mut cek = [0u8; 16];
+        hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+            .unwrap();
+        let mut nonce = [0u8; 12];
+        hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+            .unwrap();
+
+        let mut record = Aes128Gcm::new_from_slice(&cek)
+            .unwrap()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .unwrap();
+        assert_eq!(record.pop(), Some(0x02));
+        record
+    }
+
+    fn subscription() -> (SecretKey, Vec<u8>, [u8; 16]) {
+        let ua_secret = SecretKey::random(&mut OsRng);
+        let ua_public = ua_secret
+            .public_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+        (ua_secret, ua_public, rand::random())
+    }
+
+    #[test]
+    fn round_trips() {
+        let (ua_secret, ua_public, auth_secret) = subscription();
+        let body = encrypt(b"{\"title\":\"Hello\"}", &ua_public, &auth_secret).unwrap();
+
+        assert_eq!(&body[16..20], &RECORD_SIZE.to_be_bytes());
+        assert_eq!(body[20], 65);
+        assert_eq!(
+            decrypt(&body, &ua_secret, &auth_secret),
+            b"{\"title\":\"Hello\"}"
+        );
+    }
+
+    #[test]
+    fn largest_payload_fits_in_4096_bytes() {
+        let (ua_secret, ua_public, auth_secret) = subscription();
+        let plaintext = vec![b'a'; MAX_PLAINTEXT_SIZE];
+        let body = encrypt(&plaintext, &ua_public, &auth_secret).unwrap();
+
+        assert_eq!(MAX_PLAINTEXT_SIZE, 3993);
+        assert_eq!(body.len(), 4096);
+        assert_eq!(decrypt(&body, &ua_secret, &auth_secret), plaintext);
+    }
+
+    #[test]
+    fn rejects_oversized_payload() {
+        let (_, ua_public, auth_secret) = subscription();
+        let plaintext = vec![b'a'; MAX_PLAINTEXT_SIZE + 1];
+
+        assert!(encrypt(&plaintext, &ua_public, &auth_secret).is_err());
+    }
+
+    // Example of RFC 8291 section 5
+    #[test]
+    fn matches_rfc8291_example() {
+        let decode = |value: &str| URL_SAFE_NO_PAD.decode(value).unwrap();
+        let as_secret =
+            SecretKey::from_slice(&decode("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+        let ua_public = decode(
+            "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+        );
+        let auth_secret = decode("BTBZMqHH6r4Tts7J_aSIgg");
+        let salt: [u8; 16] = decode("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+
+        let body = encrypt_with(
+            b"When I grow up, I want to be a watermelon",
+            &ua_public,
+            &auth_secret,
+            &as_secret,
+            &salt,
+        )
+        .unwrap();
+
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocI\
+             nmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNW\
+             QexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_key() {
+        let (_, _, auth_secret) = subscription();
+
+        assert!(encrypt(b"{}", &[4; 65], &auth_secret).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    env, fs,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use log::{error, info, warn};
+use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+use serde_json::json;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        notification::{NotificationDeQueue, NotificationStatus},
+        push_payload::PushPayload,
+    },
+    repositories::notification_repository::NotificationRepo,
+    utils::{jwt::sign_es256, rate_limiter::throttled_error},
+};
+
+use super::notification_worker_actor::NotificationWorker;
+
+// APNs rejects provider tokens older than one hour, so they are renewed a bit earlier
+const PROVIDER_TOKEN_LIFETIME: Duration = Duration::from_secs(50 * 60);
+
+/// `ApnsWorker` is responsible for sending push notifications directly to Apple Push Notification
+/// service over its HTTP/2 provider API, authenticating with a token signed by a `.p8` key
+pub struct ApnsWorker {
+    client: reqwest::Client,
+    endpoint: String,
+    topic: String,
+    key_id: String,
+    team_id: String,
+    signing_key: SigningKey,
+    // Cached provider token and the moment it was issued
+    provider_token: RwLock<Option<(Arc<String>, Instant)>>,
+}
+
+impl ApnsWorker {
+    /// Creates a new instance of `ApnsWorker`
+    pub fn new() -> Self {
+        let key_path = env::var("APNS_KEY_PATH").expect("APNS_KEY_PATH must be set");
+        let key_id = env::var("APNS_KEY_ID").expect("APNS_KEY_ID must be set");
+        let team_id = env::var("APNS_TEAM_ID").expect("APNS_TEAM_ID must be set");
+        // The bundle id of the app receiving the notifications
+        let topic = env::var("APNS_TOPIC").expect("APNS_TOPIC must be set");
+        // Can point at the sandbox (https://api.sandbox.push.apple.com) or a local mock
+        let endpoint =
+            env::var("APNS_ENDPOINT").unwrap_or_else(|_| "https://api.push.apple.com".to_string());
+
+        let key = fs::read_to_string(&key_path).expect("Cannot read APNs key file");
+        let signing_key = SigningKey::from_pkcs8_pem(&key).expect("Invalid APNs key file");
+
+        // APNs only speaks HTTP/2. Over TLS it is negotiated through ALPN, while a plain
+        // http:// endpoint (local mock) needs HTTP/2 with prior knowledge
+        let mut builder = reqwest::Client::builder();
+        if endpoint.starts_with("http://") {
+            builder = builder.http2_prior_knowledge();
+        }
+        let client = builder.build().expect("Cannot build APNs client");
+
+        Self {
+            client,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            topic,
+            key_id,
+            team_id,
+            signing_key,
+            provider_token: RwLock::new(None),
+        }
+    }
+
+    /// Returns the cached provider token, signing a new one when it is missing or too old
+    fn get_provider_token(&self, force_refresh: bool) -> Result<Arc<String>, NotiDeliverError> {
+        if !force_refresh {
+            if let Ok(cached) = self.provider_token.read() {
+                if let Some((token, issued_at)) = cached.as_ref() {
+                    if issued_at.elapsed() < PROVIDER_TOKEN_LIFETIME {
+                        return Ok(token.clone());
+                    }
+                }
+            }
+        }
+
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| NotiDeliverError::NoneValue)?
+            .as_secs();
+
+        let header = json!({ "alg": "ES256", "kid": self.key_id });
+        let claims = json!({ "iss": self.team_id, "iat": issued_at });
+        let token = Arc::new(sign_es256(&header, &claims, &self.signing_key));
+
+        match self.provider_token.write() {
+            Ok(mut cached) => *cached = Some((token.clone(), Instant::now())),
+            Err(_) => error!("Cannot rewrite APNs provider token"),
+        }
+
+        Ok(token)
+    }
+
+    /// Attempts to send a push notification request to APNs
+    async fn try_send(
+        &self,
+        provider_token: &str,
+        device_token: &str,
+        message: &serde_json::Value,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .post(format!("{}/3/device/{}", self.endpoint, device_token))
+            .header("authorization", format!("bearer {}", provider_token))
+            .header("apns-topic", &self.topic)
+            .header("apns-push-type", "alert")
+            .header("apns-priority", "10")
+            .body(message.to_string())
+            .send()
+            .await
+    }
+}
+
+#[async_trait]
+impl NotificationWorker for ApnsWorker {
+    /// Sends a notification using APNs
+    async fn send(
+        &self,
+        notification: &NotificationDeQueue,
+        repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        // Parse the notification payload into `PushPayload`
+        let payload =
+            serde_json::from_value::<PushPayload>(notification.payload.clone()).map_err(|e| {
+                error!("Invalid data type: {}", e);
+                NotiDeliverError::JsonParseError
+            })?;
+
+        // APNs only delivers to device tokens
+        if notification.recipient_type.as_deref() != Some("token") {
+            error!("APNs only supports 'token' recipients");
+            return Err(NotiDeliverError::JsonParseError);
+        }
+
+        // Construct the APNs request message
+        let message = json!({
+            "aps": {
+                "alert": {
+                    "title": payload.title,
+                    "body": payload.body
+                }
+            }
+        });
+
+        // Try sending the request, and retry once with a new token if it was rejected as expired
+        for i in 0..2 {
+            let provider_token = self.get_provider_token(i > 0)?;
+
+            match self
+                .try_send(&provider_token, &notification.recipient, &message)
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+                    attempt.record_response(status, "");
+                    if status.is_success() {
+                        // APNs identifies the notification with the `apns-id` header
+                        attempt.provider_message_id = response
+                            .headers()
+                            .get("apns-id")
+                            .and_then(|value| value.to_str().ok())
+                            .map(str::to_string);
+
+                        // Successfully sent notification, update database status
+                        let result = repo
+                            .update_notification_status(
+                                &notification.notification_id,
+                                NotificationStatus::Sent,
+                            )
+                            .await
+                            .map_err(|e| {
+                                error!("Update error: {}", e);
+                                NotiDeliverError::DatabaseError(e)
+                            })?;
+                        info!("Update row affected: {}", result);
+                        return Ok(());
+                    }
+
+                    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        return Err(throttled_error("apns", response.headers()));
+                    }
+
+                    // APNs explains every rejection with a `reason` field
+                    let body = response.text().await.unwrap_or_default();
+                    attempt.record_response(status, &body);
+                    let reason = serde_json::from_str::<serde_json::Value>(&body)
+                        .ok()
+                        .and_then(|body| body.get("reason")?.as_str().map(str::to_string))
+                        .unwrap_or_default();
+
+                    if status == reqwest::StatusCode::FORBIDDEN && reason == "ExpiredProviderToken"
+                    {
+                        warn!("APNs provider token expired, refreshing token...");
+                        continue;
+                    }
+
+                    // The device token will never be accepted again, retrying is pointless
+                    if (status == reqwest::StatusCode::BAD_REQUEST && reason == "BadDeviceToken")
+                        || status == reqwest::StatusCode::GONE
+                    {
+                        error!("APNs rejected device token ({}): {}", status, reason);
+                        return Err(NotiDeliverError::PermanentFailure(format!(
+                            "APNs rejected device token: {}",
+                            reason
+                        )));
+                    }
+
+                    // Failed to send notification
+                    error!("APNs rejected notification ({}): {}", status, reason);
+                    if status.is_server_error() {
+                        return Err(NotiDeliverError::ProviderUnavailable(format!(
+                            "APNs returned {}",
+                            status
+                        )));
+                    }
+                    return Err(NotiDeliverError::RequestFailed);
+                }
+                Err(e) => {
+                    error!("Can not send request: {}", e);
+                    return Err(NotiDeliverError::RequestError(e));
+                }
+            }
+        }
+
+        error!("Can not send request");
+        Err(NotiDeliverError::RequestFailed)
+    }
+
+    fn provider(&self) -> Option<&'static str> {
+        Some("apns")
+    }
+}
@@ -0,0 +1,176 @@
+use std::env;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{error, warn};
+use reqwest::multipart::{Form, Part};
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        email_payload::{EmailAddress, OutgoingEmail},
+    },
+};
+
+use super::{rejection_error, EmailProvider};
+
+// Mailgun keeps at most 3 tags per message
+const MAX_TAGS: usize = 3;
+
+/// `MailgunProvider` delivers emails through the Mailgun Messages API
+///
+/// Mailgun keeps at most 3 tags per message, the first 3 tags of an email are sent and the
+/// others are dropped. The provider is only known once the email is routed, so emails with
+/// more tags are not rejected when they are queued
+pub struct MailgunProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    // Sending domain, defaults to the domain of each sender address
+    domain: Option<String>,
+}
+
+impl MailgunProvider {
+    pub fn new() -> Self {
+        let api_key = env::var("MAILGUN_API_KEY").expect("MAILGUN_API_KEY must be set");
+        let domain = env::var("MAILGUN_DOMAIN").ok();
+        // Use https://api.eu.mailgun.net for EU domains, or a local mock server
+        let base_url =
+            env::var("MAILGUN_BASE_URL").unwrap_or_else(|_| "https://api.mailgun.net".to_string());
+
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            domain,
+        }
+    }
+
+    /// Maps the email to the multipart form expected by Mailgun
+    fn build_form(email: &OutgoingEmail) -> Result<Form, NotiDeliverError> {
+        let mut form = Form::new()
+            .text("from", email.from.mailbox())
+            .text("subject", email.subject.clone())
+            .text("text", email.text.clone());
+
+        for (field, addresses) in [("to", &email.to), ("cc", &email.cc), ("bcc", &email.bcc)] {
+            for address in addresses {
+                form = form.text(field, address.mailbox());
+            }
+        }
+
+        if let Some(html) = &email.html {
+            form = form.text("html", html.clone());
+        }
+
+        if let Some(reply_to) = &email.reply_to {
+            let address = EmailAddress {
+                email: reply_to.email.clone(),
+                name: Some(reply_to.name.clone()).filter(|name| !name.is_empty()),
+            };
+            form = form.text("h:Reply-To", address.mailbox());
+        }
+
+        for (name, value) in &email.headers {
+            form = form.text(format!("h:{}", name), value.clone());
+        }
+
+        if email.tags.len() > MAX_TAGS {
+            warn!(
+                "Mailgun keeps {} of the {} tags of the email",
+                MAX_TAGS,
+                email.tags.len()
+            );
+        }
+        for tag in email.tags.iter().take(MAX_TAGS) {
+            form = form.text("o:tag", tag.clone());
+        }
+
+        for attachment in &email.attachments {
+            let content = STANDARD.decode(&attachment.content).map_err(|e| {
+                error!("Invalid attachment '{}': {}", attachment.filename, e);
+                NotiDeliverError::PermanentFailure(format!(
+                    "invalid attachment '{}'",
+                    attachment.filename
+                ))
+            })?;
+            // Mailgun references inline attachments as `cid:` by their filename
+            let filename = if attachment.is_inline() {
+                attachment.inline_id()
+            } else {
+                &attachment.filename
+            };
+            let part = Part::bytes(content)
+                .file_name(filename.to_string())
+                .mime_str(&attachment.r#type)
+                .map_err(|_| {
+                    NotiDeliverError::PermanentFailure(format!(
+                        "invalid attachment type '{}'",
+                        attachment.r#type
+                    ))
+                })?;
+            let field = if attachment.is_inline() {
+                "inline"
+            } else {
+                "attachment"
+            };
+            form = form.part(field, part);
+        }
+
+        Ok(form)
+    }
+}
+
+#[async_trait]
+impl EmailProvider for MailgunProvider {
+    fn name(&self) -> &'static str {
+        "mailgun"
+    }
+
+    async fn send(
+        &self,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        let domain = match &self.domain {
+            Some(domain) => domain.clone(),
+            None => email
+                .from
+                .email
+                .rsplit_once('@')
+                .map(|(_, domain)| domain.to_string())
+                .ok_or_else(|| {
+                    NotiDeliverError::PermanentFailure("Invalid sender address".to_string())
+                })?,
+        };
+
+        let form = Self::build_form(email)?;
+
+        let response = self
+            .client
+            .post(format!("{}/v3/{}/messages", self.base_url, domain))
+            .basic_auth("api", Some(&self.api_key))
+            .multipart(form)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) => {
+                if response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    attempt.record_response(status, &body);
+                    attempt.record_message_id(&body, "id");
+                    Ok(())
+                } else {
+                    Err(rejection_error(self.name(), response, attempt).await)
+                }
+            }
+            Err(e) => {
+                error!("Can not send request: {}", e);
+                Err(NotiDeliverError::RequestError(e))
+            }
+        }
+    }
+}
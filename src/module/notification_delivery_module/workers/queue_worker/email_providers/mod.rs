@@ -0,0 +1,188 @@
+use std::{collections::HashMap, env, sync::Arc};
+
+use async_trait::async_trait;
+use log::{error, info};
+use mailgun_provider::MailgunProvider;
+use reqwest::StatusCode;
+use sendgrid_provider::SendGridProvider;
+use ses_provider::SesProvider;
+use smtp_provider::SmtpProvider;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{delivery_attempt::DeliveryAttempt, email_payload::OutgoingEmail},
+    utils::rate_limiter::throttled_error,
+};
+
+pub mod mailgun_provider;
+pub mod sendgrid_provider;
+pub mod ses_provider;
+pub mod smtp_provider;
+
+/// Trait defining a backend able to deliver an `OutgoingEmail`
+///
+/// Implementations map the email to their own wire format and normalize failures into
+/// `NotiDeliverError`, so `EmailWorker` does not depend on a specific provider
+#[async_trait]
+pub trait EmailProvider: Send + Sync {
+    /// Name of the provider, used in logs and configuration
+    fn name(&self) -> &'static str;
+
+    /// Delivers the email to the provider, recording its answer in `attempt`
+    async fn send(
+        &self,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError>;
+}
+
+/// Normalizes an unsuccessful provider response into a `NotiDeliverError`
+///
+/// Client errors will fail again on every retry and are reported as permanent failures,
+/// except for timeouts and rate limiting. Rate limited emails are deferred for the time
+/// requested by the provider, server errors report the provider unavailable
+pub async fn rejection_error(
+    provider: &str,
+    response: reqwest::Response,
+    attempt: &mut DeliveryAttempt,
+) -> NotiDeliverError {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        attempt.record_response(status, "");
+        return throttled_error(provider, response.headers());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    attempt.record_response(status, &body);
+    error!(
+        "{} rejected email ({}): {}",
+        provider,
+        status,
+        attempt.response_excerpt.as_deref().unwrap_or_default()
+    );
+
+    if status.is_server_error() {
+        NotiDeliverError::ProviderUnavailable(format!("{} returned {}", provider, status))
+    } else if status.is_client_error() && status != StatusCode::REQUEST_TIMEOUT {
+        NotiDeliverError::PermanentFailure(format!("{} returned {}", provider, status))
+    } else {
+        NotiDeliverError::RequestFailed
+    }
+}
+
+/// Extracts the lowercased domain of a sender address, e.g. `Team <team@example.com>`
+pub fn sender_domain(sender: &str) -> Option<String> {
+    sender
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim_end_matches('>').to_lowercase())
+}
+
+/// `EmailProviderRouter` selects the provider delivering an email
+///
+/// The provider of a tenant (`user_id`) takes precedence over the provider of the sender
+/// domain, and both fall back to the default provider. Routing is configured with:
+/// - `EMAIL_PROVIDER`: default provider (`sendgrid`, `smtp`, `ses` or `mailgun`), `sendgrid`
+///   if unset
+/// - `EMAIL_PROVIDER_ROUTES`: comma separated rules such as
+///   `tenant:<user_id>=mailgun,domain:example.com=ses`
+/// - `EMAIL_FALLBACK_PROVIDER`: provider used when the routed one is unavailable
+///
+/// Only the providers referenced by the configuration are created
+pub struct EmailProviderRouter {
+    providers: HashMap<String, Arc<dyn EmailProvider>>,
+    default_provider: String,
+    fallback_provider: Option<String>,
+    tenants: HashMap<String, String>,
+    domains: HashMap<String, String>,
+}
+
+impl EmailProviderRouter {
+    pub fn from_env() -> Self {
+        let default_provider =
+            env::var("EMAIL_PROVIDER").unwrap_or_else(|_| "sendgrid".to_string());
+        let fallback_provider = env::var("EMAIL_FALLBACK_PROVIDER").ok();
+
+        let mut tenants = HashMap::new();
+        let mut domains = HashMap::new();
+        if let Ok(routes) = env::var("EMAIL_PROVIDER_ROUTES") {
+            for rule in routes.split(',').filter(|rule| !rule.trim().is_empty()) {
+                let (target, provider) = rule
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("Invalid EMAIL_PROVIDER_ROUTES rule: {}", rule));
+                let provider = provider.trim().to_string();
+
+                match target.trim().split_once(':') {
+                    Some(("tenant", user_id)) => tenants.insert(user_id.to_string(), provider),
+                    Some(("domain", domain)) => domains.insert(domain.to_lowercase(), provider),
+                    _ => panic!("Invalid EMAIL_PROVIDER_ROUTES rule: {}", rule),
+                };
+            }
+        }
+
+        let mut providers = HashMap::new();
+        for name in tenants
+            .values()
+            .chain(domains.values())
+            .chain([&default_provider])
+            .chain(fallback_provider.as_ref())
+        {
+            if !providers.contains_key(name) {
+                info!("Email provider enabled: {}", name);
+                providers.insert(name.clone(), Self::create_provider(name));
+            }
+        }
+
+        Self {
+            providers,
+            default_provider,
+            fallback_provider,
+            tenants,
+            domains,
+        }
+    }
+
+    fn create_provider(name: &str) -> Arc<dyn EmailProvider> {
+        match name {
+            "sendgrid" => Arc::new(SendGridProvider::new()),
+            "smtp" => Arc::new(SmtpProvider::new()),
+            "ses" => Arc::new(SesProvider::new()),
+            "mailgun" => Arc::new(MailgunProvider::new()),
+            _ => panic!("Unsupported email provider: {}", name),
+        }
+    }
+
+    /// Names of every configured provider
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers
+            .values()
+            .map(|provider| provider.name())
+            .collect()
+    }
+
+    /// Returns the providers to try in order: the one configured for the tenant or the sender
+    /// domain, then the fallback provider
+    pub fn candidates(&self, user_id: Option<&str>, sender: &str) -> Vec<Arc<dyn EmailProvider>> {
+        let primary = self.select(user_id, sender);
+
+        let mut candidates = vec![primary.clone()];
+        if let Some(fallback) = &self.fallback_provider {
+            if fallback != primary.name() {
+                candidates.push(self.providers[fallback].clone());
+            }
+        }
+        candidates
+    }
+
+    /// Returns the provider configured for the tenant or the sender domain
+    fn select(&self, user_id: Option<&str>, sender: &str) -> Arc<dyn EmailProvider> {
+        let domain = sender_domain(sender);
+
+        let name = user_id
+            .and_then(|user_id| self.tenants.get(user_id))
+            .or_else(|| domain.and_then(|domain| self.domains.get(&domain)))
+            .unwrap_or(&self.default_provider);
+
+        // Every routed provider is created in `from_env`
+        self.providers[name].clone()
+    }
+}
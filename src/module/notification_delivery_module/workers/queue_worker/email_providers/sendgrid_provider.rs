@@ -0,0 +1,132 @@
+use std::env;
+
+use async_trait::async_trait;
+use log::error;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{delivery_attempt::DeliveryAttempt, email_payload::OutgoingEmail},
+};
+
+use super::{rejection_error, EmailProvider};
+
+/// `SendGridProvider` delivers emails through the SendGrid v3 Mail Send API
+pub struct SendGridProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+impl SendGridProvider {
+    pub fn new() -> Self {
+        let client = reqwest::Client::new();
+        // The base URL can be overridden to point at a local mock server
+        let base_url = env::var("SENDGRID_BASE_URL")
+            .unwrap_or_else(|_| "https://api.sendgrid.com".to_string());
+        let url = format!("{}/v3/mail/send", base_url.trim_end_matches('/'));
+        let api_key = env::var("SENDGRID_API_KEY").expect("SENDGRID_API_KEY must be set");
+
+        Self {
+            client,
+            url,
+            api_key,
+        }
+    }
+
+    async fn try_send(
+        &self,
+        message: &serde_json::Value,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .header("Content-Type", "application/json")
+            .body(message.to_string())
+            .send()
+            .await
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SendGridProvider {
+    fn name(&self) -> &'static str {
+        "sendgrid"
+    }
+
+    async fn send(
+        &self,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        // Construct the request message
+        let mut message = serde_json::json!({
+            "personalizations": [{
+                "to": email.to
+            }],
+            "from": email.from,
+            "subject": email.subject,
+            "content": [{
+                "type": "text/plain",
+                "value": email.text
+            }]
+        });
+
+        // SendGrid expects the HTML part after the plain text one
+        if let Some(html) = &email.html {
+            message["content"]
+                .as_array_mut()
+                .expect("content is an array")
+                .push(serde_json::json!({"type": "text/html", "value": html}));
+        }
+
+        if !email.cc.is_empty() {
+            message["personalizations"][0]["cc"] = serde_json::json!(email.cc);
+        }
+        if !email.bcc.is_empty() {
+            message["personalizations"][0]["bcc"] = serde_json::json!(email.bcc);
+        }
+
+        if !email.attachments.is_empty() {
+            message["attachments"] = serde_json::json!(email.attachments);
+        }
+
+        if let Some(rep) = &email.reply_to {
+            message["reply_to"] = serde_json::json!(rep);
+        }
+
+        if !email.headers.is_empty() {
+            let headers: serde_json::Map<String, serde_json::Value> = email
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::json!(value)))
+                .collect();
+            message["headers"] = serde_json::Value::Object(headers);
+        }
+
+        if !email.tags.is_empty() {
+            message["categories"] = serde_json::json!(email.tags);
+        }
+
+        // Attempt to send the email
+        match self.try_send(&message).await {
+            Ok(response) => {
+                if response.status().is_success() {
+                    attempt.record_response(response.status(), "");
+                    attempt.provider_message_id = response
+                        .headers()
+                        .get("X-Message-Id")
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                    Ok(())
+                } else {
+                    // Failed to send email
+                    Err(rejection_error(self.name(), response, attempt).await)
+                }
+            }
+            Err(e) => {
+                error!("Can not send request: {}", e);
+                Err(NotiDeliverError::RequestError(e))
+            }
+        }
+    }
+}
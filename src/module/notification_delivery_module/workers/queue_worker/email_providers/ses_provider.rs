@@ -0,0 +1,172 @@
+use std::env;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use log::error;
+use serde_json::json;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        email_payload::{EmailAddress, OutgoingEmail},
+    },
+    utils::{mime_builder, sigv4::AwsSigner},
+};
+
+use super::{rejection_error, EmailProvider};
+
+/// `SesProvider` delivers emails through the Amazon SES v2 `SendEmail` API, signing requests
+/// with AWS Signature Version 4
+pub struct SesProvider {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    signer: AwsSigner,
+}
+
+impl SesProvider {
+    pub fn new() -> Self {
+        let region = env::var("SES_REGION")
+            .or_else(|_| env::var("AWS_REGION"))
+            .expect("SES_REGION or AWS_REGION must be set");
+        let access_key_id = env::var("AWS_ACCESS_KEY_ID").expect("AWS_ACCESS_KEY_ID must be set");
+        let secret_access_key =
+            env::var("AWS_SECRET_ACCESS_KEY").expect("AWS_SECRET_ACCESS_KEY must be set");
+        let session_token = env::var("AWS_SESSION_TOKEN").ok();
+
+        // The endpoint can be overridden to point at a local mock server
+        let base_url = env::var("SES_BASE_URL")
+            .unwrap_or_else(|_| format!("https://email.{}.amazonaws.com", region));
+        let url = reqwest::Url::parse(&format!(
+            "{}/v2/email/outbound-emails",
+            base_url.trim_end_matches('/')
+        ))
+        .expect("Invalid SES_BASE_URL");
+
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            signer: AwsSigner {
+                access_key_id,
+                secret_access_key,
+                session_token,
+                region,
+                service: "ses".to_string(),
+            },
+        }
+    }
+
+    /// Maps the email to a `SendEmail` request body
+    ///
+    /// Simple content cannot carry attachments, so those emails are sent as a raw MIME message
+    fn build_request(email: &OutgoingEmail) -> Result<serde_json::Value, NotiDeliverError> {
+        let content = if email.attachments.is_empty() {
+            let mut body = json!({ "Text": { "Data": email.text, "Charset": "UTF-8" } });
+            if let Some(html) = &email.html {
+                body["Html"] = json!({ "Data": html, "Charset": "UTF-8" });
+            }
+            let headers: Vec<serde_json::Value> = email
+                .headers
+                .iter()
+                .map(|(name, value)| json!({ "Name": name, "Value": value }))
+                .collect();
+            json!({
+                "Simple": {
+                    "Subject": { "Data": email.subject, "Charset": "UTF-8" },
+                    "Body": body,
+                    "Headers": headers
+                }
+            })
+        } else {
+            let message = mime_builder::build_message(email).map_err(|e| {
+                error!("Cannot build email: {}", e);
+                NotiDeliverError::PermanentFailure(e)
+            })?;
+            json!({ "Raw": { "Data": STANDARD.encode(message.formatted()) } })
+        };
+
+        let mut request = json!({
+            "FromEmailAddress": email.from.mailbox(),
+            "Destination": { "ToAddresses": mailboxes(&email.to) },
+            "Content": content
+        });
+
+        if !email.cc.is_empty() {
+            request["Destination"]["CcAddresses"] = json!(mailboxes(&email.cc));
+        }
+        if !email.bcc.is_empty() {
+            request["Destination"]["BccAddresses"] = json!(mailboxes(&email.bcc));
+        }
+
+        if let Some(reply_to) = &email.reply_to {
+            request["ReplyToAddresses"] = json!([reply_to.email]);
+        }
+
+        // SES tags are name/value pairs, each tag is a name flagged `true`
+        if !email.tags.is_empty() {
+            let tags: Vec<serde_json::Value> = email
+                .tags
+                .iter()
+                .map(|tag| json!({ "Name": tag, "Value": "true" }))
+                .collect();
+            request["EmailTags"] = json!(tags);
+        }
+
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SesProvider {
+    fn name(&self) -> &'static str {
+        "ses"
+    }
+
+    async fn send(
+        &self,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        let body = Self::build_request(email)?.to_string();
+
+        let content_type = "application/json";
+        let auth_headers = self.signer.sign(
+            "POST",
+            &self.url,
+            &[("content-type", content_type)],
+            body.as_bytes(),
+            Utc::now(),
+        );
+
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header("Content-Type", content_type);
+        for (name, value) in auth_headers {
+            request = request.header(name, value);
+        }
+
+        match request.body(body).send().await {
+            Ok(response) => {
+                if response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    attempt.record_response(status, &body);
+                    attempt.record_message_id(&body, "MessageId");
+                    Ok(())
+                } else {
+                    Err(rejection_error(self.name(), response, attempt).await)
+                }
+            }
+            Err(e) => {
+                error!("Can not send request: {}", e);
+                Err(NotiDeliverError::RequestError(e))
+            }
+        }
+    }
+}
+
+fn mailboxes(addresses: &[EmailAddress]) -> Vec<String> {
+    addresses.iter().map(EmailAddress::mailbox).collect()
+}
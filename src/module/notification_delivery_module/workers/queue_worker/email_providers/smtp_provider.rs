@@ -0,0 +1,115 @@
+use std::env;
+
+use async_trait::async_trait;
+use lettre::{
+    transport::smtp::{authentication::Credentials, PoolConfig},
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+};
+use log::error;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{delivery_attempt::DeliveryAttempt, email_payload::OutgoingEmail},
+    utils::mime_builder,
+};
+
+use super::EmailProvider;
+
+/// `SmtpProvider` delivers emails directly to an SMTP server through a pool of connections
+///
+/// The connection security is selected with `SMTP_TLS`:
+/// - `starttls` (default): plain connection upgraded with STARTTLS, port 587
+/// - `tls`: implicit TLS, port 465
+/// - `none`: unencrypted connection, e.g. a local MailHog-style sink on port 1025
+pub struct SmtpProvider {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpProvider {
+    pub fn new() -> Self {
+        let host = env::var("SMTP_HOST").expect("SMTP_HOST must be set");
+        let tls = env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+        let pool_size = env::var("SMTP_POOL_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(10);
+
+        let (builder, default_port) = match tls.as_str() {
+            "starttls" => (
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+                    .expect("Invalid SMTP_HOST"),
+                587,
+            ),
+            "tls" => (
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&host).expect("Invalid SMTP_HOST"),
+                465,
+            ),
+            "none" => (
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+                25,
+            ),
+            _ => panic!("Unsupported SMTP_TLS: {}", tls),
+        };
+
+        let port = env::var("SMTP_PORT")
+            .ok()
+            .map(|value| value.parse().expect("SMTP_PORT must be a number"))
+            .unwrap_or(default_port);
+
+        let mut builder = builder
+            .port(port)
+            .pool_config(PoolConfig::new().max_size(pool_size));
+
+        // Authentication is optional, local sinks usually accept anonymous clients
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+        {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Self {
+            transport: builder.build(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SmtpProvider {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(
+        &self,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        // A message that cannot be built will never succeed, do not retry it
+        let message = mime_builder::build_message(email).map_err(|e| {
+            error!("Cannot build email: {}", e);
+            NotiDeliverError::PermanentFailure(e)
+        })?;
+
+        match self.transport.send(message).await {
+            Ok(response) => {
+                // SMTP replies have no HTTP status, keep the text of the server reply
+                attempt.response_excerpt = Some(response.message().collect::<Vec<_>>().join(" "));
+                Ok(())
+            }
+            Err(e) if e.is_permanent() => {
+                attempt.response_excerpt = Some(e.to_string());
+                error!("SMTP server rejected email: {}", e);
+                Err(NotiDeliverError::PermanentFailure(e.to_string()))
+            }
+            Err(e) if e.is_transient() => {
+                attempt.response_excerpt = Some(e.to_string());
+                error!("SMTP server deferred email: {}", e);
+                Err(NotiDeliverError::RequestFailed)
+            }
+            Err(e) => {
+                // Connection, TLS or protocol failure, the server is unreachable
+                error!("Can not send email: {}", e);
+                Err(NotiDeliverError::ProviderUnavailable(e.to_string()))
+            }
+        }
+    }
+}
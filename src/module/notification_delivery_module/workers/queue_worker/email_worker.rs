@@ -1,49 +1,222 @@
-use std::{env, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use log::{error, info};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{error, info, warn};
+use serde_json::{Map, Value};
+use uuid::Uuid;
 
 use crate::module::notification_delivery_module::{
     errors::NotiDeliverError,
     models::{
-        email_payload::{Attachments, EmailPayload, ReplyTo},
-        notification::NotificationDeQueue,
+        delivery_attempt::DeliveryAttempt,
+        email_payload::{
+            Attachments, EmailAddress, EmailPayload, EmailRecipient, EmailRecipients,
+            OutgoingEmail, RecipientStatus, ReplyTo,
+        },
+        notification::{NotificationDeQueue, NotificationStatus},
+    },
+    repositories::{
+        attachment_repository::AttachmentRepo, notification_repository::NotificationRepo,
     },
-    repositories::notification_repository::NotificationRepo,
+    utils::{circuit_breaker::CircuitBreakerRegistry, rate_limiter::RateLimiter},
 };
+use crate::module::notification_service_module::utils::template_renderer::render_text;
 
-use super::notification_worker_actor::NotificationWorker;
+use super::{
+    email_providers::{sender_domain, EmailProviderRouter},
+    notification_worker_actor::{NotificationWorker, MAX_ATTEMPTS},
+};
 
+// Placeholder replaced by the unsubscribe link in the content of the email
+const UNSUBSCRIBE_URL_KEY: &str = "unsubscribe_url";
+
+/// `EmailWorker` is responsible for sending email notifications.
+/// It parses the payload into an `OutgoingEmail` and delivers it through the `EmailProvider`
+/// routed for the tenant or the sender domain, failing over to the fallback provider when
+/// the routed one is down. Emails are rate limited per provider and per sender domain
 pub struct EmailWorker {
-    client: reqwest::Client,
-    url: String,
-    api_key: String,
+    router: EmailProviderRouter,
+    breakers: Arc<CircuitBreakerRegistry>,
+    limiter: Arc<RateLimiter>,
+    attachment_repo: Arc<AttachmentRepo>,
 }
 
 impl EmailWorker {
-    pub fn new() -> Self {
-        let client = reqwest::Client::new();
-        let url = "https://api.sendgrid.com/v3/mail/send".to_string();
-        let api_key = env::var("SENDGRID_API_KEY").expect("SENDGRID_API_KEY must be set");
-
+    pub fn new(
+        router: EmailProviderRouter,
+        breakers: Arc<CircuitBreakerRegistry>,
+        limiter: Arc<RateLimiter>,
+        attachment_repo: Arc<AttachmentRepo>,
+    ) -> Self {
         Self {
-            client,
-            url,
-            api_key,
+            router,
+            breakers,
+            limiter,
+            attachment_repo,
         }
     }
 
-    async fn try_send(
+    /// Loads the content of the attachments referenced by id
+    async fn load_attachments(
         &self,
-        message: &serde_json::Value,
-    ) -> Result<reqwest::Response, reqwest::Error> {
-        self.client
-            .post(&self.url)
-            .bearer_auth(&self.api_key)
-            .header("Content-Type", "application/json")
-            .body(message.to_string())
-            .send()
-            .await
+        attachments: &mut [Attachments],
+    ) -> Result<(), NotiDeliverError> {
+        for attachment in attachments.iter_mut() {
+            let Some(id) = &attachment.id else {
+                continue;
+            };
+            let uuid = Uuid::parse_str(id).map_err(|_| {
+                NotiDeliverError::PermanentFailure(format!("invalid attachment id '{}'", id))
+            })?;
+
+            let content = self
+                .attachment_repo
+                .find_content(&uuid)
+                .await
+                .map_err(|e| {
+                    error!("Select error: {}", e);
+                    NotiDeliverError::DatabaseError(e)
+                })?
+                .ok_or_else(|| {
+                    // The attachment was deleted after the email was queued
+                    NotiDeliverError::PermanentFailure(format!("attachment {} not found", id))
+                })?;
+            attachment.content = STANDARD.encode(content);
+        }
+        Ok(())
+    }
+
+    /// Delivers the email through the first available provider
+    async fn send_with_failover(
+        &self,
+        user_id: Option<&str>,
+        email: &OutgoingEmail,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        let mut last_error = None;
+        let mut retry_in: Option<Duration> = None;
+        let domain_key =
+            sender_domain(&email.from.email).map(|domain| format!("domain:{}", domain));
+        *attempt = DeliveryAttempt::default();
+
+        for provider in self.router.candidates(user_id, &email.from.email) {
+            // An open circuit fails over without spending the quota of the provider
+            let breaker = self.breakers.get(provider.name());
+            if !breaker.try_acquire() {
+                warn!("Circuit of {} is open, skipping provider", provider.name());
+                retry_in = match (retry_in, breaker.retry_in()) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                continue;
+            }
+
+            // The attempt keeps the answer of the last provider tried
+            *attempt = DeliveryAttempt {
+                provider: Some(provider.name().to_string()),
+                ..Default::default()
+            };
+
+            // Stay under the quotas, the email waits for the provider instead of failing over
+            let mut keys = vec![provider.name()];
+            keys.extend(domain_key.as_deref());
+            if let Err(wait) = self.limiter.try_acquire(&keys) {
+                info!("Rate limit of {} reached, deferring email", provider.name());
+                breaker.release();
+                return Err(NotiDeliverError::Deferred(wait));
+            }
+
+            match provider.send(email, attempt).await {
+                Ok(()) => {
+                    breaker.record_success();
+                    info!("Email sent through {}", provider.name());
+                    return Ok(());
+                }
+                Err(e) if e.is_provider_outage() => {
+                    breaker.record_failure();
+                    warn!("{} is unavailable: {}", provider.name(), e);
+                    last_error = Some(e);
+                }
+                Err(NotiDeliverError::Deferred(delay)) => {
+                    // The provider is throttling us, hold every email it would deliver.
+                    // Being throttled counts against its circuit like a server error
+                    breaker.record_failure();
+                    self.limiter.pause(provider.name(), delay);
+                    return Err(NotiDeliverError::Deferred(delay));
+                }
+                Err(e) if attempt.reached_provider() => {
+                    // The provider answered, the email itself was rejected
+                    breaker.record_success();
+                    return Err(e);
+                }
+                Err(e) => {
+                    // Failed before any request, e.g. a message that cannot be built
+                    breaker.release();
+                    return Err(e);
+                }
+            }
+        }
+
+        // Every provider failed or is short-circuited
+        Err(last_error.unwrap_or(NotiDeliverError::Deferred(
+            retry_in.unwrap_or(Duration::from_secs(5)),
+        )))
+    }
+}
+
+/// Recipients sharing an email
+struct Personalization<'a> {
+    to: Vec<&'a EmailRecipient>,
+    cc: Vec<&'a EmailRecipient>,
+    bcc: Vec<&'a EmailRecipient>,
+}
+
+impl Personalization<'_> {
+    fn emails(&self) -> Vec<String> {
+        self.to
+            .iter()
+            .chain(&self.cc)
+            .chain(&self.bcc)
+            .map(|recipient| recipient.address.email.clone())
+            .collect()
+    }
+}
+
+impl EmailWorker {
+    /// Splits the recipients into the emails to send
+    ///
+    /// Recipients share one email, unless several `to` recipients have their own
+    /// substitutions or unsubscribe links. Each of them then gets an individual email, and the
+    /// `cc` and `bcc` recipients receive the first one
+    fn personalizations(recipients: &EmailRecipients) -> Vec<Personalization<'_>> {
+        let shared = recipients.to.len() == 1
+            || recipients.to.iter().all(|recipient| {
+                recipient.substitutions.is_empty() && recipient.unsubscribe_url.is_none()
+            });
+
+        if shared {
+            return vec![Personalization {
+                to: recipients.to.iter().collect(),
+                cc: recipients.cc.iter().collect(),
+                bcc: recipients.bcc.iter().collect(),
+            }];
+        }
+
+        recipients
+            .to
+            .iter()
+            .enumerate()
+            .map(|(index, recipient)| Personalization {
+                to: vec![recipient],
+                cc: recipients.cc.iter().filter(|_| index == 0).collect(),
+                bcc: recipients.bcc.iter().filter(|_| index == 0).collect(),
+            })
+            .collect()
     }
 }
 
@@ -53,6 +226,7 @@ impl NotificationWorker for EmailWorker {
         &self,
         notification: &NotificationDeQueue,
         repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
     ) -> Result<(), NotiDeliverError> {
         // Parse the notification payload into `EmailPayload`
         let payload = serde_json::from_value::<EmailPayload>(notification.payload.clone())
@@ -61,15 +235,19 @@ impl NotificationWorker for EmailWorker {
                 NotiDeliverError::JsonParseError
             })?;
 
-        // Try parsing payload's variables
-        let mut attachments: Option<Vec<Attachments>> = None;
+        // Try parsing payload's variables, the notification service validated them so a
+        // malformed value fails the email instead of being dropped
+        let mut attachments: Vec<Attachments> = Vec::new();
         let mut reply: Option<ReplyTo> = None;
-        if let Some(value) = payload.optionals {
+        let mut custom_headers: Vec<(String, String)> = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        if let Some(value) = &payload.optionals {
             // Get attachment value if exists
             if let Some(v) = value.get("attachments") {
-                if let Ok(att) = serde_json::from_value::<Vec<Attachments>>(v.clone()) {
-                    attachments = Some(att);
-                };
+                attachments = serde_json::from_value(v.clone()).map_err(|e| {
+                    error!("Invalid attachments: {}", e);
+                    NotiDeliverError::PermanentFailure(format!("invalid attachments: {}", e))
+                })?;
             };
             // Get reply_to value if exists
             if let Some(v) = value.get("reply_to") {
@@ -77,57 +255,205 @@ impl NotificationWorker for EmailWorker {
                     reply = Some(rep);
                 };
             };
+            // Get custom headers and tags if exist
+            if let Some(v) = value.get("headers") {
+                let headers = serde_json::from_value::<BTreeMap<String, String>>(v.clone())
+                    .map_err(|e| {
+                        error!("Invalid headers: {}", e);
+                        NotiDeliverError::PermanentFailure(format!("invalid headers: {}", e))
+                    })?;
+                custom_headers = headers.into_iter().collect();
+            };
+            if let Some(v) = value.get("tags") {
+                tags = serde_json::from_value(v.clone()).map_err(|e| {
+                    error!("Invalid tags: {}", e);
+                    NotiDeliverError::PermanentFailure(format!("invalid tags: {}", e))
+                })?;
+            };
         };
+        self.load_attachments(&mut attachments).await?;
 
-        // Construct the request message
-        let mut message = serde_json::json!({
-            "personalizations": [{
-                "to": [{"email": notification.recipient}]
-            }],
-            "from": {
-                "email": notification.sender
-            },
-            "subject": payload.subject,
-            "content": [{
-                "type": payload.content_type,
-                "value": payload.content
-            }]
-        });
-
-        if let Some(atts) = attachments {
-            message["attachments"] = serde_json::json!(atts);
-        }
+        let sender = notification.sender.as_deref().ok_or_else(|| {
+            error!("Missing sender");
+            NotiDeliverError::JsonParseError
+        })?;
+        let sender = EmailAddress::from_mailbox(sender).map_err(|e| {
+            error!("Invalid sender: {}", e);
+            NotiDeliverError::PermanentFailure(e)
+        })?;
 
-        if let Some(rep) = reply {
-            message["reply_to"] = serde_json::json!(rep);
-        }
+        let subject = payload.subject.clone();
+        let (text, html) = payload.into_parts().ok_or_else(|| {
+            error!("Missing email body");
+            NotiDeliverError::JsonParseError
+        })?;
 
-        // return Ok(());
-
-        // Attempt to send the notification
-        match self.try_send(&message).await {
-            Ok(response) => {
-                // info!("SendGrid response: {:?}", response);
-                if response.status().is_success() {
-                    // Successfully sent notification, update database status
-                    let result = repo
-                        .update_notification_status(&notification.notification_id, "sent")
-                        .await
-                        .map_err(|e| {
-                            error!("Update error: {}", e);
-                            NotiDeliverError::DatabaseError(e)
-                        })?;
-                    info!("Update row affected: {}", result);
-                    return Ok(());
-                } else {
-                    // Failed to send notification
-                    return Err(NotiDeliverError::RequestFailed);
+        let recipients = notification
+            .recipients
+            .clone()
+            .unwrap_or_else(|| EmailRecipients {
+                to: vec![EmailRecipient {
+                    address: EmailAddress {
+                        email: notification.recipient.clone(),
+                        name: None,
+                    },
+                    substitutions: HashMap::new(),
+                    unsubscribe_url: None,
+                }],
+                cc: Vec::new(),
+                bcc: Vec::new(),
+            });
+
+        // A retried notification is only sent again to the recipients which failed with a
+        // retryable error
+        let completed = repo
+            .find_completed_recipients(&notification.notification_id)
+            .await
+            .map_err(|e| {
+                error!("Select error: {}", e);
+                NotiDeliverError::DatabaseError(e)
+            })?;
+        let mut delivered = completed
+            .iter()
+            .filter(|(_, status)| *status != RecipientStatus::Failed.to_string())
+            .count();
+        let completed: HashSet<String> = completed.into_iter().map(|(email, _)| email).collect();
+
+        // Every email is attempted, a recipient failing does not hold back the others
+        let last_attempt = notification.retry_count + 1 >= MAX_ATTEMPTS;
+        let mut retry_error: Option<NotiDeliverError> = None;
+        let mut deferred: Option<NotiDeliverError> = None;
+        let mut permanent_error: Option<NotiDeliverError> = None;
+
+        for personalization in Self::personalizations(&recipients) {
+            if personalization
+                .to
+                .iter()
+                .all(|recipient| completed.contains(&recipient.address.email))
+            {
+                continue;
+            }
+
+            // Substitutions and unsubscribe links only apply to emails with a single `to`
+            let (substitutions, unsubscribe_url) = match personalization.to.as_slice() {
+                [recipient] => (
+                    Some(&recipient.substitutions),
+                    recipient.unsubscribe_url.as_deref(),
+                ),
+                _ => (None, None),
+            };
+            let mut variables: Map<String, Value> = substitutions
+                .into_iter()
+                .flatten()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect();
+            // Marketing emails carry a one-click unsubscribe link (RFC 8058), which
+            // templates can also render with `{{unsubscribe_url}}`. The placeholder is
+            // blanked in emails without one
+            variables.insert(
+                UNSUBSCRIBE_URL_KEY.to_string(),
+                Value::String(unsubscribe_url.unwrap_or_default().to_string()),
+            );
+            let locale = notification.template_locale.as_deref();
+            let render = |content: &str| render_text(content, &variables, locale, false);
+
+            let mut headers = custom_headers.clone();
+            if let Some(url) = unsubscribe_url {
+                headers.push(("List-Unsubscribe".to_string(), format!("<{}>", url)));
+                headers.push((
+                    "List-Unsubscribe-Post".to_string(),
+                    "List-Unsubscribe=One-Click".to_string(),
+                ));
+            }
+
+            // Construct the provider independent email
+            let addresses = |recipients: &[&EmailRecipient]| {
+                recipients
+                    .iter()
+                    .map(|recipient| recipient.address.clone())
+                    .collect()
+            };
+            let email = OutgoingEmail {
+                from: sender.clone(),
+                to: addresses(&personalization.to),
+                cc: addresses(&personalization.cc),
+                bcc: addresses(&personalization.bcc),
+                subject: render(&subject),
+                text: render(&text),
+                html: html
+                    .as_deref()
+                    .map(|html| render_text(html, &variables, locale, true)),
+                attachments: attachments.clone(),
+                reply_to: reply.clone(),
+                headers,
+                tags: tags.clone(),
+            };
+
+            // Attempt to send the notification
+            let result = self
+                .send_with_failover(notification.user_id.as_deref(), &email, attempt)
+                .await;
+
+            let status = match &result {
+                Ok(()) => Some(RecipientStatus::Sent),
+                Err(NotiDeliverError::Deferred(_)) => None,
+                Err(NotiDeliverError::PermanentFailure(_)) => Some(RecipientStatus::Failed),
+                Err(_) if last_attempt => Some(RecipientStatus::Failed),
+                Err(_) => Some(RecipientStatus::Retrying),
+            };
+            if let Some(status) = status {
+                if let Err(e) = repo
+                    .update_recipient_status(
+                        &notification.notification_id,
+                        &personalization.emails(),
+                        status,
+                        attempt,
+                    )
+                    .await
+                {
+                    error!("Cannot update recipients: {}", e);
+                }
+            }
+
+            match result {
+                Ok(()) => delivered += 1,
+                Err(e @ NotiDeliverError::Deferred(_)) => {
+                    deferred.get_or_insert(e);
+                }
+                Err(e @ NotiDeliverError::PermanentFailure(_)) => {
+                    error!("Email cannot be sent: {}", e);
+                    permanent_error.get_or_insert(e);
+                }
+                Err(e) => {
+                    error!("Email failed: {}", e);
+                    retry_error.get_or_insert(e);
                 }
             }
-            Err(e) => {
-                error!("Can not send request: {}", e);
-                return Err(NotiDeliverError::RequestError(e));
+        }
+
+        // Retry the recipients which can still be reached, failing the notification only
+        // when none of its recipients got the email
+        if let Some(e) = retry_error.filter(|_| !last_attempt || delivered == 0) {
+            return Err(e);
+        }
+        if let Some(e) = deferred {
+            return Err(e);
+        }
+        if delivered == 0 {
+            if let Some(e) = permanent_error {
+                return Err(e);
             }
         }
+
+        // Successfully sent notification, update database status
+        let result = repo
+            .update_notification_status(&notification.notification_id, NotificationStatus::Sent)
+            .await
+            .map_err(|e| {
+                error!("Update error: {}", e);
+                NotiDeliverError::DatabaseError(e)
+            })?;
+        info!("Update row affected: {}", result);
+        Ok(())
     }
 }
@@ -5,23 +5,37 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use actix::{Actor, AsyncContext, Context, Message, Recipient, WrapFuture};
 use actix_web::rt::time::sleep;
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use redis::AsyncCommands;
 
 use crate::module::notification_delivery_module::{
     errors::NotiDeliverError,
-    models::notification::NotificationDeQueue,
+    models::notification::{NotificationDeQueue, NotificationStatus},
     repositories::{notification_repository::NotificationRepo, redis_repository::RedisRepository},
 };
 
+pub mod apns_worker;
+pub mod email_providers;
 pub mod email_worker;
 pub mod notification_worker_actor;
 pub mod push_worker;
+pub mod webpush_worker;
+
+// How often deferred jobs are checked for being due
+const REQUEUE_INTERVAL: Duration = Duration::from_secs(1);
+// Deferred jobs moved back to the queue per check
+const REQUEUE_BATCH_SIZE: usize = 100;
+
+/// Sorted set of the jobs of `queue_key` deferred until their score, a Unix timestamp in
+/// milliseconds
+pub fn deferred_key(queue_key: &str) -> String {
+    format!("{}_deferred", queue_key)
+}
 
 /// Represents a message containing a dequeued notification
 /// This message is sent to the appropriate worker for processing
@@ -112,7 +126,28 @@ impl QueueWorker {
             NotiDeliverError::MissingEnvError(e)
         })?;
 
+        let deferred_key = deferred_key(&queue_key);
+        let mut requeued_at: Option<Instant> = None;
+
         while running.load(Ordering::Relaxed) {
+            // Put the deferred jobs which are due back in the queue
+            if requeued_at.is_none_or(|at| at.elapsed() >= REQUEUE_INTERVAL) {
+                requeued_at = Some(Instant::now());
+                match redis_repo
+                    .requeue_due(
+                        &deferred_key,
+                        &queue_key,
+                        chrono::Utc::now().timestamp_millis(),
+                        REQUEUE_BATCH_SIZE,
+                    )
+                    .await
+                {
+                    Ok(0) => (),
+                    Ok(moved) => info!("{} deferred jobs put back to queue", moved),
+                    Err(e) => error!("Cannot requeue deferred jobs: {}", e),
+                }
+            }
+
             // Attempt to pop a job from the Redis queue
             let job: Option<String> = conn.rpop(&queue_key, None).await.map_err(|e| {
                 error!("Queue pop error: {}", e);
@@ -124,11 +159,27 @@ impl QueueWorker {
                 match serde_json::from_str::<NotificationDeQueue>(&job_data) {
                     Ok(notification) => {
                         // Dispatch the message to the appropriate worker
-                        if let Some(worker) = workers.get(&notification.channel) {
+                        let worker_key = notification.worker_key();
+                        if let Some(worker) = workers.get(&worker_key) {
                             worker.do_send(NotificationMessage(notification, queue_key.clone()));
                         } else {
-                            error!("No worker found for channel: {}", notification.channel);
-                            return Err(NotiDeliverError::NoneValue);
+                            // Nothing can deliver this job, keep it in the failed queue
+                            error!("No worker found for: {}", worker_key);
+                            let mut failed_key = queue_key.clone();
+                            failed_key.push_str("_failed");
+                            if let Err(e) = redis_repo.push_to_queue(&failed_key, &job_data).await {
+                                error!("Cannot push to failed queue: {}", e);
+                            };
+                            match noti_repo
+                                .update_notification_status(
+                                    &notification.notification_id,
+                                    NotificationStatus::Failed,
+                                )
+                                .await
+                            {
+                                Ok(result) => info!("Update row affected: {}", result),
+                                Err(e) => error!("Update error: {}", e),
+                            }
                         }
                     }
                     Err(e) => {
@@ -149,8 +200,9 @@ impl QueueWorker {
                             if let Some(id) = json.get("notification_id").and_then(|id| id.as_str())
                             {
                                 // Update status to "failed"
-                                if let Ok(result) =
-                                    noti_repo.update_notification_status(&id, "failed").await
+                                if let Ok(result) = noti_repo
+                                    .update_notification_status(id, NotificationStatus::Failed)
+                                    .await
                                 {
                                     info!("Update row affected: {}", result);
                                 } else {
@@ -165,8 +217,9 @@ impl QueueWorker {
                     }
                 }
             } else {
-                warn!("Queue is empty, retrying after 10 seconds...");
-                sleep(Duration::from_secs(10)).await;
+                // Wake up in time for the deferred jobs
+                debug!("Queue is empty, retrying after 1 second...");
+                sleep(REQUEUE_INTERVAL).await;
             }
         }
 
@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use actix::{Actor, AsyncContext, Context, Handler, WrapFuture};
 use async_trait::async_trait;
@@ -6,11 +9,15 @@ use log::{error, info, warn};
 
 use crate::module::notification_delivery_module::{
     errors::NotiDeliverError,
-    models::notification::NotificationDeQueue,
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        notification::{NotificationDeQueue, NotificationStatus},
+    },
     repositories::{notification_repository::NotificationRepo, redis_repository::RedisRepository},
+    utils::{circuit_breaker::CircuitBreakerRegistry, rate_limiter::RateLimiter},
 };
 
-use super::NotificationMessage;
+use super::{deferred_key, NotificationMessage};
 
 /// Trait defining a worker responsible for sending notifications asynchronously.
 #[async_trait]
@@ -20,9 +27,25 @@ pub trait NotificationWorker: Send + Sync {
         &self,
         notification: &NotificationDeQueue,
         repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
     ) -> Result<(), NotiDeliverError>;
+
+    /// Name of the provider guarded by a circuit breaker and rate limited around `send`
+    ///
+    /// Workers delivering through several providers return `None` and manage their breakers
+    /// and rate limits
+    fn provider(&self) -> Option<&'static str> {
+        None
+    }
 }
 
+// Delay before retrying a notification whose provider is short-circuited
+const DEFAULT_DEFER_DELAY: Duration = Duration::from_secs(5);
+// Attempts made to deliver a job before it is given up
+pub const MAX_ATTEMPTS: u8 = 3;
+// Deferrals requested by a provider before a job is given up
+const MAX_DEFERRALS: u8 = 20;
+
 /// Actor responsible for processing notification messages using a `NotificationWorker`
 ///
 /// Every worker MUST implement the `NotificationWorker` trait and be wrapped within a
@@ -31,6 +54,8 @@ pub struct NotificationWorkerActor {
     worker: Arc<dyn NotificationWorker>,
     noti_repo: Arc<NotificationRepo>,
     redis_repo: Arc<RedisRepository>,
+    breakers: Arc<CircuitBreakerRegistry>,
+    limiter: Arc<RateLimiter>,
 }
 
 impl NotificationWorkerActor {
@@ -39,11 +64,132 @@ impl NotificationWorkerActor {
         worker: Arc<dyn NotificationWorker>,
         noti_repo: Arc<NotificationRepo>,
         redis_repo: Arc<RedisRepository>,
+        breakers: Arc<CircuitBreakerRegistry>,
+        limiter: Arc<RateLimiter>,
     ) -> Self {
         Self {
             worker,
             noti_repo,
             redis_repo,
+            breakers,
+            limiter,
+        }
+    }
+
+    /// Sends the notification, going through the rate limit and the circuit breaker of the
+    /// worker's provider
+    async fn send_guarded(
+        worker: Arc<dyn NotificationWorker>,
+        breakers: Arc<CircuitBreakerRegistry>,
+        limiter: Arc<RateLimiter>,
+        notification: &NotificationDeQueue,
+        noti_repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        let Some(provider) = worker.provider() else {
+            return worker.send(notification, noti_repo, attempt).await;
+        };
+        attempt.provider = Some(provider.to_string());
+
+        let breaker = breakers.get(provider);
+        if !breaker.try_acquire() {
+            return Err(NotiDeliverError::Deferred(
+                breaker.retry_in().unwrap_or(DEFAULT_DEFER_DELAY),
+            ));
+        }
+
+        if let Err(wait) = limiter.try_acquire(&[provider]) {
+            breaker.release();
+            return Err(NotiDeliverError::Deferred(wait));
+        }
+
+        let result = worker.send(notification, noti_repo, attempt).await;
+        match &result {
+            Err(e) if e.is_provider_outage() => breaker.record_failure(),
+            Err(NotiDeliverError::Deferred(delay)) => {
+                // The provider is throttling us, hold every notification it would deliver.
+                // Being throttled counts against its circuit like a server error
+                limiter.pause(provider, *delay);
+                breaker.record_failure();
+            }
+            Ok(()) => breaker.record_success(),
+            // The provider answered, the notification itself was rejected
+            Err(_) if attempt.reached_provider() => breaker.record_success(),
+            // Failed before any request, e.g. an invalid payload, the provider was not probed
+            Err(_) => breaker.release(),
+        }
+        result
+    }
+
+    /// Moves a job to the failed queue and marks its notification failed
+    async fn fail_job(
+        redis_repo: &RedisRepository,
+        noti_repo: &NotificationRepo,
+        queue_key: &str,
+        notification: &NotificationDeQueue,
+    ) {
+        let value = serde_json::json!(notification);
+        let failed_key = format!("{}_failed", queue_key);
+
+        if let Err(e) = redis_repo
+            .push_to_queue(&failed_key, &value.to_string())
+            .await
+        {
+            error!("Cannot push to failed queue: {}", e);
+        };
+        // Update status to "failed"
+        match noti_repo
+            .update_notification_status(&notification.notification_id, NotificationStatus::Failed)
+            .await
+        {
+            Ok(result) => info!("Update row affected: {}", result),
+            Err(e) => error!("Update error: {}", e),
+        }
+    }
+
+    /// Stores the attempt in the timeline of the notification, and the provider message id of
+    /// a delivered notification
+    ///
+    /// Deferrals that never reached the provider (open breaker, local rate limit) are not
+    /// attempts and are skipped
+    async fn record_attempt(
+        noti_repo: &NotificationRepo,
+        notification_id: &str,
+        attempt: &DeliveryAttempt,
+        latency: Duration,
+        result: &Result<(), NotiDeliverError>,
+    ) {
+        if let (Ok(()), Some(message_id)) = (result, &attempt.provider_message_id) {
+            if let Err(e) = noti_repo
+                .update_provider_message_id(
+                    notification_id,
+                    attempt.provider.as_deref(),
+                    message_id,
+                )
+                .await
+            {
+                error!("Cannot store provider message id: {}", e);
+            }
+        }
+
+        let (outcome, error) = match result {
+            Ok(()) => ("sent", None),
+            Err(NotiDeliverError::Deferred(_)) if !attempt.reached_provider() => return,
+            Err(e @ NotiDeliverError::Deferred(_)) => ("deferred", Some(e)),
+            Err(e) => ("failed", Some(e)),
+        };
+
+        if let Err(e) = noti_repo
+            .insert_delivery_attempt(
+                notification_id,
+                attempt,
+                latency.as_millis() as i64,
+                outcome,
+                error.map(|e| (e.kind(), e.to_string())),
+            )
+            .await
+        {
+            error!("Cannot record delivery attempt: {}", e);
         }
     }
 }
@@ -63,24 +209,97 @@ impl Handler<NotificationMessage> for NotificationWorkerActor {
         let worker = self.worker.clone();
         let noti_repo = self.noti_repo.clone();
         let redis_repo = self.redis_repo.clone();
+        let breakers = self.breakers.clone();
+        let limiter = self.limiter.clone();
         let mut notification = msg.0;
         let queue_key = msg.1;
 
         // Spawn an asynchronous task within the actor's context to process the notification
         ctx.spawn(
             async move {
-                if let Err(e) = worker.send(&notification, noti_repo.clone()).await {
-                    // Retrying failed job
+                // Claim the notification, a notification already sent or cancelled is skipped
+                match noti_repo
+                    .update_notification_status(
+                        &notification.notification_id,
+                        NotificationStatus::Processing,
+                    )
+                    .await
+                {
+                    Ok(0) => {
+                        warn!(
+                            "Notification {} cannot be processed anymore, skipping",
+                            notification.notification_id
+                        );
+                        return;
+                    }
+                    Ok(_) => (),
+                    Err(e) => error!("Update error: {}", e),
+                }
+
+                let mut attempt = DeliveryAttempt::default();
+                let started_at = Instant::now();
+                let result = Self::send_guarded(
+                    worker,
+                    breakers,
+                    limiter,
+                    &notification,
+                    noti_repo.clone(),
+                    &mut attempt,
+                )
+                .await;
+                Self::record_attempt(
+                    &noti_repo,
+                    &notification.notification_id,
+                    &attempt,
+                    started_at.elapsed(),
+                    &result,
+                )
+                .await;
+
+                // A provider deferring the same job over and over is not going to take it
+                if matches!(result, Err(NotiDeliverError::Deferred(_)))
+                    && attempt.reached_provider()
+                {
+                    notification.deferral_count += 1;
+                }
+
+                if notification.deferral_count > MAX_DEFERRALS {
+                    error!(
+                        "Job deferred more than {} times, moving to failed queue...",
+                        MAX_DEFERRALS
+                    );
+                    Self::fail_job(&redis_repo, &noti_repo, &queue_key, &notification).await;
+                } else if let Err(NotiDeliverError::Deferred(delay)) = result {
+                    // The provider is unavailable, park the job in Redis until it is due
+                    // without counting an attempt
+                    warn!("Delivery deferred for {:?}, scheduling job...", delay);
+                    let delay = i64::try_from(delay.as_millis()).unwrap_or(i64::MAX);
+                    let due_at = chrono::Utc::now().timestamp_millis().saturating_add(delay);
+                    let value = serde_json::json!(&notification);
+                    if let Err(e) = redis_repo
+                        .schedule(&deferred_key(&queue_key), &value.to_string(), due_at)
+                        .await
+                    {
+                        error!("Cannot be scheduled: {}", e);
+                    };
+                } else if let Err(e) = result {
                     error!("Send request error: {}", e);
-                    warn!("Puting back to queue...");
 
                     notification.retry_count += 1;
-                    warn!("Retrying job (attempt {}/3)...", notification.retry_count);
 
                     // Construct string valus of NotificationDequeue
                     let value = serde_json::json!(&notification);
 
-                    if notification.retry_count < 3 {
+                    // Permanent failures (e.g. a recipient that no longer exists) are not retried
+                    let retryable = !matches!(e, NotiDeliverError::PermanentFailure(_));
+
+                    if retryable && notification.retry_count < MAX_ATTEMPTS {
+                        // Retrying failed job
+                        warn!("Puting back to queue...");
+                        warn!(
+                            "Retrying job (attempt {}/{})...",
+                            notification.retry_count, MAX_ATTEMPTS
+                        );
                         if let Err(e) = redis_repo
                             .push_to_queue(&queue_key, &value.to_string())
                             .await
@@ -88,26 +307,9 @@ impl Handler<NotificationMessage> for NotificationWorkerActor {
                             error!("Cannot be put back to queue: {}", e);
                         };
                     } else {
-                        // Move job to failed queue if failed more than 3 times
-                        error!("Job failed too many times, moving to failed queue...");
-                        let mut failed_key = queue_key.clone();
-                        failed_key.push_str("_failed");
-
-                        if let Err(e) = redis_repo
-                            .push_to_queue(&failed_key, &value.to_string())
-                            .await
-                        {
-                            error!("Cannot push to failed queue: {}", e);
-                        };
-                        // Update status to "failed"
-                        if let Ok(result) = noti_repo
-                            .update_notification_status(&notification.notification_id, "failed")
-                            .await
-                        {
-                            info!("Update row affected: {}", result);
-                        } else {
-                            error!("Update error: {}", e);
-                        }
+                        // Move job to failed queue once its attempts are exhausted
+                        error!("Job cannot be delivered, moving to failed queue...");
+                        Self::fail_job(&redis_repo, &noti_repo, &queue_key, &notification).await;
                     }
                 };
             }
@@ -6,9 +6,13 @@ use serde_json::json;
 
 use crate::module::notification_delivery_module::{
     errors::NotiDeliverError,
-    models::{notification::NotificationDeQueue, push_payload::PushPayload},
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        notification::{NotificationDeQueue, NotificationStatus},
+        push_payload::PushPayload,
+    },
     repositories::notification_repository::NotificationRepo,
-    utils::fcm_token_manager::TokenManager,
+    utils::{fcm_token_manager::TokenManager, rate_limiter::throttled_error},
 };
 
 use super::notification_worker_actor::NotificationWorker;
@@ -18,12 +22,12 @@ use super::notification_worker_actor::NotificationWorker;
 pub struct PushWorker {
     client: reqwest::Client,
     url: String,
-    token_manager: TokenManager,
+    token_manager: Arc<TokenManager>,
 }
 
 impl PushWorker {
     /// Creates a new instance of `PushWorker`
-    pub async fn new(token_manager: TokenManager) -> Self {
+    pub async fn new(token_manager: Arc<TokenManager>) -> Self {
         let project_id = env::var("PROJECT_ID").expect("PROJECT_ID must be set");
         Self {
             client: reqwest::Client::new(),
@@ -57,6 +61,7 @@ impl NotificationWorker for PushWorker {
         &self,
         notification: &NotificationDeQueue,
         repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
     ) -> Result<(), NotiDeliverError> {
         // Parse the notification payload into `PushPayload`
         let payload =
@@ -98,15 +103,25 @@ impl NotificationWorker for PushWorker {
             match self.try_send(token.as_str(), &message).await {
                 Ok(response) => {
                     // info!("FCM response: {:?}", response);
+                    attempt.record_response(response.status(), "");
                     if response.status() == reqwest::StatusCode::UNAUTHORIZED {
                         // Token expired, attempt to refresh
                         warn!("Token expired, refreshing token...");
                         self.token_manager.update_token().await;
                         continue;
                     } else if response.status().is_success() {
+                        // FCM names the accepted message `projects/*/messages/{message_id}`
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        attempt.record_response(status, &body);
+                        attempt.record_message_id(&body, "name");
+
                         // Successfully sent notification, update database status
                         let result = repo
-                            .update_notification_status(&notification.notification_id, "sent")
+                            .update_notification_status(
+                                &notification.notification_id,
+                                NotificationStatus::Sent,
+                            )
                             .await
                             .map_err(|e| {
                                 error!("Update error: {}", e);
@@ -114,8 +129,21 @@ impl NotificationWorker for PushWorker {
                             })?;
                         info!("Update row affected: {}", result);
                         return Ok(());
+                    } else if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        // Quota exceeded, wait for the time requested by FCM
+                        return Err(throttled_error("fcm", response.headers()));
                     } else {
                         // Failed to send notification
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        attempt.record_response(status, &body);
+                        error!("FCM rejected notification ({}): {}", status, body);
+                        if status.is_server_error() {
+                            return Err(NotiDeliverError::ProviderUnavailable(format!(
+                                "FCM returned {}",
+                                status
+                            )));
+                        }
                         return Err(NotiDeliverError::RequestFailed);
                     }
                 }
@@ -129,4 +157,8 @@ impl NotificationWorker for PushWorker {
         error!("Can not send request");
         Err(NotiDeliverError::RequestFailed)
     }
+
+    fn provider(&self) -> Option<&'static str> {
+        Some("fcm")
+    }
 }
@@ -0,0 +1,245 @@
+use std::{
+    env,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::{error, info, warn};
+use p256::ecdsa::SigningKey;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::module::notification_delivery_module::{
+    errors::NotiDeliverError,
+    models::{
+        delivery_attempt::DeliveryAttempt,
+        notification::{NotificationDeQueue, NotificationStatus},
+        webpush_payload::WebPushPayload,
+    },
+    repositories::{notification_repository::NotificationRepo, webpush_repository::WebPushRepo},
+    utils::{jwt::sign_es256, rate_limiter::throttled_error, webpush_crypto},
+};
+
+use super::notification_worker_actor::NotificationWorker;
+
+// VAPID tokens must not be valid for more than 24 hours
+const VAPID_TOKEN_LIFETIME_SECS: u64 = 12 * 60 * 60;
+
+/// `WebPushWorker` is responsible for sending notifications to browsers through their push
+/// service. Payloads are encrypted as described in RFC 8291 and requests are authenticated
+/// with VAPID (RFC 8292)
+pub struct WebPushWorker {
+    client: reqwest::Client,
+    webpush_repo: Arc<WebPushRepo>,
+    signing_key: SigningKey,
+    // Base64url encoded uncompressed public key, sent as the `k` parameter
+    public_key: String,
+    subject: String,
+    ttl: u32,
+}
+
+impl WebPushWorker {
+    /// Creates a new instance of `WebPushWorker`
+    pub fn new(webpush_repo: Arc<WebPushRepo>) -> Self {
+        // The raw 32 bytes private key, base64url encoded (as generated by `web-push` tools)
+        let private_key = env::var("VAPID_PRIVATE_KEY").expect("VAPID_PRIVATE_KEY must be set");
+        // Contact of the application server, a `mailto:` or `https:` URI
+        let subject = env::var("VAPID_SUBJECT").expect("VAPID_SUBJECT must be set");
+        let ttl = env::var("WEBPUSH_TTL")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(86400);
+
+        let private_key = URL_SAFE_NO_PAD
+            .decode(private_key.trim_end_matches('='))
+            .expect("VAPID_PRIVATE_KEY must be base64url encoded");
+        let signing_key = SigningKey::from_slice(&private_key).expect("Invalid VAPID_PRIVATE_KEY");
+        let public_key = URL_SAFE_NO_PAD.encode(
+            signing_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+
+        Self {
+            client: reqwest::Client::new(),
+            webpush_repo,
+            signing_key,
+            public_key,
+            subject,
+            ttl,
+        }
+    }
+
+    /// Signs a VAPID token for the origin of the push service endpoint
+    fn vapid_authorization(&self, endpoint: &str) -> Result<String, NotiDeliverError> {
+        let audience = reqwest::Url::parse(endpoint)
+            .map(|url| url.origin().ascii_serialization())
+            .map_err(|_| {
+                NotiDeliverError::PermanentFailure("Invalid subscription endpoint".to_string())
+            })?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| NotiDeliverError::NoneValue)?
+            .as_secs();
+
+        let header = json!({ "typ": "JWT", "alg": "ES256" });
+        let claims = json!({
+            "aud": audience,
+            "exp": now + VAPID_TOKEN_LIFETIME_SECS,
+            "sub": self.subject
+        });
+        let token = sign_es256(&header, &claims, &self.signing_key);
+
+        Ok(format!("vapid t={}, k={}", token, self.public_key))
+    }
+
+    /// Attempts to send an encrypted push message to the push service
+    async fn try_send(
+        &self,
+        endpoint: &str,
+        authorization: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .post(endpoint)
+            .header("Authorization", authorization)
+            .header("TTL", self.ttl.to_string())
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .body(body)
+            .send()
+            .await
+    }
+}
+
+#[async_trait]
+impl NotificationWorker for WebPushWorker {
+    /// Sends a notification using the Web Push protocol
+    async fn send(
+        &self,
+        notification: &NotificationDeQueue,
+        repo: Arc<NotificationRepo>,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<(), NotiDeliverError> {
+        // Parse the notification payload into `WebPushPayload`
+        let payload = serde_json::from_value::<WebPushPayload>(notification.payload.clone())
+            .map_err(|e| {
+                error!("Invalid data type: {}", e);
+                NotiDeliverError::JsonParseError
+            })?;
+
+        // The recipient of a web push notification is the id of a stored subscription
+        let subscription_id = Uuid::parse_str(&notification.recipient).map_err(|_| {
+            NotiDeliverError::PermanentFailure("Invalid subscription id".to_string())
+        })?;
+
+        let subscription = self
+            .webpush_repo
+            .find_subscription(&subscription_id)
+            .await
+            .map_err(|e| {
+                error!("Select error: {}", e);
+                NotiDeliverError::DatabaseError(e)
+            })?
+            .ok_or_else(|| {
+                NotiDeliverError::PermanentFailure("Subscription no longer exists".to_string())
+            })?;
+
+        let (ua_public, auth_secret) = match (
+            URL_SAFE_NO_PAD.decode(subscription.p256dh.trim_end_matches('=')),
+            URL_SAFE_NO_PAD.decode(subscription.auth.trim_end_matches('=')),
+        ) {
+            (Ok(ua_public), Ok(auth_secret)) => (ua_public, auth_secret),
+            _ => {
+                return Err(NotiDeliverError::PermanentFailure(
+                    "Invalid subscription keys".to_string(),
+                ))
+            }
+        };
+
+        // Encrypt the message for this subscription
+        let message = json!(payload).to_string();
+        let body =
+            webpush_crypto::encrypt(message.as_bytes(), &ua_public, &auth_secret).map_err(|e| {
+                error!("Cannot encrypt web push payload: {}", e);
+                NotiDeliverError::PermanentFailure(e)
+            })?;
+
+        let authorization = self.vapid_authorization(&subscription.endpoint)?;
+
+        // Attempt to send the notification
+        match self
+            .try_send(&subscription.endpoint, &authorization, body)
+            .await
+        {
+            Ok(response) => {
+                let status = response.status();
+                attempt.record_response(status, "");
+                if status.is_success() {
+                    // The push service returns the URL of the created push message resource
+                    attempt.provider_message_id = response
+                        .headers()
+                        .get("Location")
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+
+                    // Successfully sent notification, update database status
+                    let result = repo
+                        .update_notification_status(
+                            &notification.notification_id,
+                            NotificationStatus::Sent,
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Update error: {}", e);
+                            NotiDeliverError::DatabaseError(e)
+                        })?;
+                    info!("Update row affected: {}", result);
+                    Ok(())
+                } else if status == reqwest::StatusCode::NOT_FOUND
+                    || status == reqwest::StatusCode::GONE
+                {
+                    // The subscription expired or was revoked by the user, forget it
+                    warn!("Subscription {} is gone, removing it", subscription_id);
+                    if let Err(e) = self
+                        .webpush_repo
+                        .delete_subscription(&subscription_id)
+                        .await
+                    {
+                        error!("Delete error: {}", e);
+                    }
+                    Err(NotiDeliverError::PermanentFailure(format!(
+                        "Push service returned {}",
+                        status
+                    )))
+                } else if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    Err(throttled_error("webpush", response.headers()))
+                } else {
+                    // Failed to send notification
+                    let body = response.text().await.unwrap_or_default();
+                    attempt.record_response(status, &body);
+                    error!("Push service rejected notification: {}", status);
+                    if status.is_server_error() {
+                        return Err(NotiDeliverError::ProviderUnavailable(format!(
+                            "Push service returned {}",
+                            status
+                        )));
+                    }
+                    Err(NotiDeliverError::RequestFailed)
+                }
+            }
+            Err(e) => {
+                error!("Can not send request: {}", e);
+                Err(NotiDeliverError::RequestError(e))
+            }
+        }
+    }
+
+    fn provider(&self) -> Option<&'static str> {
+        Some("webpush")
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use actix_web::{
+    http::header::CONTENT_TYPE,
+    web::{self, Bytes, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::attachment::{AttachmentQuery, AttachmentUploadQuery},
+    services::attachment_service::AttachmentService,
+    utils::attachment_policy::AttachmentPolicy,
+};
+
+pub struct AttachmentController {
+    attachment_service: Arc<AttachmentService>,
+}
+
+impl AttachmentController {
+    pub fn new(attachment_service: Arc<AttachmentService>) -> Self {
+        Self { attachment_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig, policy: &AttachmentPolicy) {
+        cfg.service(
+            web::scope("/attachment")
+                // Uploads are raw files, larger than the default payload limit
+                .app_data(web::PayloadConfig::new(policy.max_file_size))
+                .route("", web::post().to(Self::upload))
+                .route("/{id}", web::get().to(Self::get))
+                .route("/{id}", web::delete().to(Self::delete)),
+        );
+    }
+
+    /// Stores the raw body as an attachment, typed with the `Content-Type` header
+    async fn upload(
+        self_controller: web::Data<Arc<AttachmentController>>,
+        request: HttpRequest,
+        query: Query<AttachmentUploadQuery>,
+        body: Bytes,
+    ) -> impl Responder {
+        let content_type = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream");
+
+        match self_controller
+            .attachment_service
+            .upload(&query.user_id, &query.filename, content_type, &body)
+            .await
+        {
+            Ok(attachment) => HttpResponse::Created().json(attachment),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn get(
+        self_controller: web::Data<Arc<AttachmentController>>,
+        id: Path<String>,
+        query: Query<AttachmentQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .attachment_service
+            .find(&query.user_id, &id)
+            .await
+        {
+            Ok(Some(attachment)) => HttpResponse::Ok().json(attachment),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn delete(
+        self_controller: web::Data<Arc<AttachmentController>>,
+        id: Path<String>,
+        query: Query<AttachmentQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .attachment_service
+            .delete(&query.user_id, &id)
+            .await
+        {
+            Ok(0) => HttpResponse::NotFound().finish(),
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use log::error;
+
+use crate::module::notification_service_module::{
+    models::email_event::SendGridEvent, services::event_service::EventService,
+};
+
+pub struct EventWebhookController {
+    event_service: Arc<EventService>,
+}
+
+impl EventWebhookController {
+    pub fn new(event_service: Arc<EventService>) -> Self {
+        Self { event_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::scope("/webhook").route("/sendgrid", web::post().to(Self::sendgrid)));
+    }
+
+    /// Receives the SendGrid signed Event Webhook
+    ///
+    /// The signature covers the raw body, so the body is only parsed once verified
+    async fn sendgrid(
+        self_controller: web::Data<Arc<EventWebhookController>>,
+        request: HttpRequest,
+        body: web::Bytes,
+    ) -> impl Responder {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+        };
+        let signature = header("X-Twilio-Email-Event-Webhook-Signature");
+        let timestamp = header("X-Twilio-Email-Event-Webhook-Timestamp");
+
+        if let Err(e) = self_controller
+            .event_service
+            .verify_sendgrid(signature, timestamp, &body)
+        {
+            return HttpResponse::from_error(e);
+        }
+
+        let events = match serde_json::from_slice::<Vec<SendGridEvent>>(&body) {
+            Ok(events) => events,
+            Err(e) => {
+                error!("Invalid SendGrid events: {}", e);
+                return HttpResponse::BadRequest().finish();
+            }
+        };
+
+        match self_controller.event_service.ingest_sendgrid(events).await {
+            Ok(()) => HttpResponse::Ok().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
@@ -1 +1,9 @@
+pub mod attachment_controller;
+pub mod event_webhook_controller;
 pub mod notification_controller;
+pub mod preference_controller;
+pub mod sender_controller;
+pub mod suppression_controller;
+pub mod template_controller;
+pub mod tracking_controller;
+pub mod webpush_controller;
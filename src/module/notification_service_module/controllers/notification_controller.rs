@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
 use actix_web::{
-    web::{self, Json},
+    web::{self, Json, Path},
     HttpResponse, Responder,
 };
 
 use crate::module::notification_service_module::{
     models::notification::NotificationRequest, services::notification_service::NotificationService,
+    utils::attachment_policy::AttachmentPolicy,
 };
 
 pub struct NotificationController {
@@ -18,8 +19,14 @@ impl NotificationController {
         Self { noti_service }
     }
 
-    pub fn routes(cfg: &mut web::ServiceConfig) {
-        cfg.service(web::scope("/notification").route("/send", web::post().to(Self::send)));
+    pub fn routes(cfg: &mut web::ServiceConfig, policy: &AttachmentPolicy) {
+        cfg.service(
+            web::scope("/notification")
+                // Emails carry their inline attachments in the request
+                .app_data(web::JsonConfig::default().limit(policy.max_request_size()))
+                .route("/send", web::post().to(Self::send))
+                .route("/{id}/timeline", web::get().to(Self::timeline)),
+        );
     }
 
     async fn send(
@@ -31,8 +38,19 @@ impl NotificationController {
             .send(notification_request.0)
             .await
         {
-            Ok(response) => return HttpResponse::Ok().json(response),
-            Err(e) => return HttpResponse::from_error(e),
-        };
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn timeline(
+        self_controller: web::Data<Arc<NotificationController>>,
+        id: Path<String>,
+    ) -> impl Responder {
+        match self_controller.noti_service.timeline(&id).await {
+            Ok(Some(timeline)) => HttpResponse::Ok().json(timeline),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
     }
 }
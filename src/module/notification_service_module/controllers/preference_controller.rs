@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use actix_web::{
+    http::header::ContentType,
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::preference::{PreferenceQuery, PreferenceRequest},
+    services::preference_service::PreferenceService,
+};
+
+pub struct PreferenceController {
+    preference_service: Arc<PreferenceService>,
+}
+
+impl PreferenceController {
+    pub fn new(preference_service: Arc<PreferenceService>) -> Self {
+        Self { preference_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/preferences")
+                .route("", web::get().to(Self::get))
+                .route("", web::put().to(Self::update)),
+        );
+        // Public links found in emails
+        cfg.service(
+            web::resource("/unsubscribe/{token}")
+                .route(web::get().to(Self::confirm_unsubscribe))
+                .route(web::post().to(Self::unsubscribe)),
+        );
+    }
+
+    async fn get(
+        self_controller: web::Data<Arc<PreferenceController>>,
+        query: Query<PreferenceQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .preference_service
+            .get(&query.user_id, &query.recipient)
+            .await
+        {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn update(
+        self_controller: web::Data<Arc<PreferenceController>>,
+        request: Json<PreferenceRequest>,
+    ) -> impl Responder {
+        match self_controller.preference_service.update(request.0).await {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    /// Page behind the `List-Unsubscribe` link, opting out only once confirmed so link
+    /// scanners cannot unsubscribe recipients
+    async fn confirm_unsubscribe(
+        self_controller: web::Data<Arc<PreferenceController>>,
+        token: Path<String>,
+    ) -> impl Responder {
+        // Only verified tokens reach the page, they are base64url and safe in the markup
+        let Some(action) = self_controller
+            .preference_service
+            .confirm_unsubscribe_url(&token)
+        else {
+            return HttpResponse::BadRequest().body("Invalid unsubscribe link");
+        };
+
+        HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(format!(
+                "<!DOCTYPE html><html><body>\
+             <form method=\"post\" action=\"{}\">\
+             <p>Stop receiving these emails?</p>\
+             <button type=\"submit\">Unsubscribe</button>\
+             </form></body></html>",
+                action
+            ))
+    }
+
+    /// One-click unsubscribe (RFC 8058), also posted by the confirmation page
+    async fn unsubscribe(
+        self_controller: web::Data<Arc<PreferenceController>>,
+        token: Path<String>,
+    ) -> impl Responder {
+        match self_controller.preference_service.unsubscribe(&token).await {
+            Ok(Some(_)) => HttpResponse::Ok().content_type(ContentType::html()).body(
+                "<!DOCTYPE html><html><body><p>You have been unsubscribed.</p></body></html>",
+            ),
+            Ok(None) => HttpResponse::BadRequest().body("Invalid unsubscribe link"),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use actix_web::{
+    http::header::ContentType,
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::sender_identity::{SenderIdentityQuery, SenderIdentityRequest},
+    services::sender_service::SenderService,
+};
+
+pub struct SenderController {
+    sender_service: Arc<SenderService>,
+}
+
+impl SenderController {
+    pub fn new(sender_service: Arc<SenderService>) -> Self {
+        Self { sender_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/sender")
+                .route("", web::post().to(Self::create))
+                .route("", web::get().to(Self::list))
+                // Public link found in verification emails
+                .route("/verify/{token}", web::get().to(Self::confirm))
+                .route("/{id}", web::delete().to(Self::delete))
+                .route("/{id}/verify", web::post().to(Self::verify))
+                .route("/{id}/default", web::put().to(Self::set_default)),
+        );
+    }
+
+    async fn create(
+        self_controller: web::Data<Arc<SenderController>>,
+        request: Json<SenderIdentityRequest>,
+    ) -> impl Responder {
+        match self_controller.sender_service.create(request.0).await {
+            Ok(sender) => HttpResponse::Created().json(sender),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn list(
+        self_controller: web::Data<Arc<SenderController>>,
+        query: Query<SenderIdentityQuery>,
+    ) -> impl Responder {
+        match self_controller.sender_service.list(&query.user_id).await {
+            Ok(senders) => HttpResponse::Ok().json(senders),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    /// Checks the TXT record of a domain, or sends the verification email of an address again
+    async fn verify(
+        self_controller: web::Data<Arc<SenderController>>,
+        id: Path<String>,
+        query: Query<SenderIdentityQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .sender_service
+            .verify(&query.user_id, &id)
+            .await
+        {
+            Ok(Some(sender)) => HttpResponse::Ok().json(sender),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn confirm(
+        self_controller: web::Data<Arc<SenderController>>,
+        token: Path<String>,
+    ) -> impl Responder {
+        match self_controller.sender_service.confirm(&token).await {
+            Ok(Some(_)) => HttpResponse::Ok().content_type(ContentType::html()).body(
+                "<!DOCTYPE html><html><body><p>Your sender address is verified.</p></body></html>",
+            ),
+            Ok(None) => HttpResponse::BadRequest().body("Invalid verification link"),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn set_default(
+        self_controller: web::Data<Arc<SenderController>>,
+        id: Path<String>,
+        query: Query<SenderIdentityQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .sender_service
+            .set_default(&query.user_id, &id)
+            .await
+        {
+            Ok(0) => HttpResponse::NotFound().finish(),
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn delete(
+        self_controller: web::Data<Arc<SenderController>>,
+        id: Path<String>,
+        query: Query<SenderIdentityQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .sender_service
+            .delete(&query.user_id, &id)
+            .await
+        {
+            Ok(0) => HttpResponse::NotFound().finish(),
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use actix_web::{
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::suppression::{SuppressionQuery, SuppressionRequest},
+    services::suppression_service::SuppressionService,
+};
+
+pub struct SuppressionController {
+    suppression_service: Arc<SuppressionService>,
+}
+
+impl SuppressionController {
+    pub fn new(suppression_service: Arc<SuppressionService>) -> Self {
+        Self {
+            suppression_service,
+        }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/suppression")
+                .route("", web::post().to(Self::suppress))
+                .route("", web::get().to(Self::list))
+                .route("/{email}", web::delete().to(Self::unsuppress)),
+        );
+    }
+
+    async fn suppress(
+        self_controller: web::Data<Arc<SuppressionController>>,
+        request: Json<SuppressionRequest>,
+    ) -> impl Responder {
+        match self_controller
+            .suppression_service
+            .suppress(request.0)
+            .await
+        {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn list(
+        self_controller: web::Data<Arc<SuppressionController>>,
+        query: Query<SuppressionQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .suppression_service
+            .list(&query.user_id)
+            .await
+        {
+            Ok(suppressions) => HttpResponse::Ok().json(suppressions),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn unsuppress(
+        self_controller: web::Data<Arc<SuppressionController>>,
+        email: Path<String>,
+        query: Query<SuppressionQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .suppression_service
+            .unsuppress(&query.user_id, &email)
+            .await
+        {
+            Ok(0) => HttpResponse::NotFound().finish(),
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
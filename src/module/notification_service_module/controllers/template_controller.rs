@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use actix_web::{
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::template::{
+        TemplateDraftRequest, TemplatePreviewRequest, TemplateQuery, TemplateRequest,
+        TemplateTestRequest, UserLocale,
+    },
+    services::{notification_service::NotificationService, template_service::TemplateService},
+};
+
+pub struct TemplateController {
+    template_service: Arc<TemplateService>,
+    // Test sends are regular notifications
+    noti_service: Arc<NotificationService>,
+}
+
+impl TemplateController {
+    pub fn new(
+        template_service: Arc<TemplateService>,
+        noti_service: Arc<NotificationService>,
+    ) -> Self {
+        Self {
+            template_service,
+            noti_service,
+        }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/template")
+                .route("", web::post().to(Self::create))
+                .route("", web::get().to(Self::list))
+                .route("/{id}", web::get().to(Self::get))
+                .route("/{id}/draft", web::put().to(Self::update_draft))
+                .route(
+                    "/{id}/versions/{version}/publish",
+                    web::post().to(Self::publish),
+                )
+                .route("/{id}/rollback", web::post().to(Self::rollback))
+                .route("/{id}/preview", web::post().to(Self::preview))
+                .route("/{id}/test-send", web::post().to(Self::test_send)),
+        );
+        // Locale of the templates of a user when a notification gives none
+        cfg.service(web::resource("/users/{id}/locale").route(web::put().to(Self::set_locale)));
+    }
+
+    async fn create(
+        self_controller: web::Data<Arc<TemplateController>>,
+        request: Json<TemplateRequest>,
+    ) -> impl Responder {
+        match self_controller.template_service.create(request.0).await {
+            Ok(template) => HttpResponse::Created().json(template),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn list(
+        self_controller: web::Data<Arc<TemplateController>>,
+        query: Query<TemplateQuery>,
+    ) -> impl Responder {
+        match self_controller.template_service.list(&query.user_id).await {
+            Ok(templates) => HttpResponse::Ok().json(templates),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn get(
+        self_controller: web::Data<Arc<TemplateController>>,
+        id: Path<String>,
+        query: Query<TemplateQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .template_service
+            .find(&query.user_id, &id)
+            .await
+        {
+            Ok(Some(template)) => HttpResponse::Ok().json(template),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn update_draft(
+        self_controller: web::Data<Arc<TemplateController>>,
+        id: Path<String>,
+        query: Query<TemplateQuery>,
+        request: Json<TemplateDraftRequest>,
+    ) -> impl Responder {
+        match self_controller
+            .template_service
+            .update_draft(&query.user_id, &id, &request)
+            .await
+        {
+            Ok(Some(draft)) => HttpResponse::Ok().json(draft),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn publish(
+        self_controller: web::Data<Arc<TemplateController>>,
+        path: Path<(String, i32)>,
+        query: Query<TemplateQuery>,
+    ) -> impl Responder {
+        let (id, version) = path.into_inner();
+        match self_controller
+            .template_service
+            .publish(&query.user_id, &id, version)
+            .await
+        {
+            Ok(Some(published)) => HttpResponse::Ok().json(published),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    /// Publishes again the version published before the current one
+    async fn rollback(
+        self_controller: web::Data<Arc<TemplateController>>,
+        id: Path<String>,
+        query: Query<TemplateQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .template_service
+            .rollback(&query.user_id, &id)
+            .await
+        {
+            Ok(Some(published)) => HttpResponse::Ok().json(published),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    /// Renders the draft, or the version given, with sample variables
+    async fn preview(
+        self_controller: web::Data<Arc<TemplateController>>,
+        id: Path<String>,
+        query: Query<TemplateQuery>,
+        request: Json<TemplatePreviewRequest>,
+    ) -> impl Responder {
+        match self_controller
+            .template_service
+            .preview(&query.user_id, &id, &request)
+            .await
+        {
+            Ok(Some(preview)) => HttpResponse::Ok().json(preview),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn test_send(
+        self_controller: web::Data<Arc<TemplateController>>,
+        id: Path<String>,
+        query: Query<TemplateQuery>,
+        request: Json<TemplateTestRequest>,
+    ) -> impl Responder {
+        match self_controller
+            .noti_service
+            .send_test(&query.user_id, &id, request.0)
+            .await
+        {
+            Ok(Some(response)) => HttpResponse::Ok().json(response),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn set_locale(
+        self_controller: web::Data<Arc<TemplateController>>,
+        user_id: Path<String>,
+        request: Json<UserLocale>,
+    ) -> impl Responder {
+        match self_controller
+            .template_service
+            .set_user_locale(&user_id, &request)
+            .await
+        {
+            Ok(Some(locale)) => HttpResponse::Ok().json(locale),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
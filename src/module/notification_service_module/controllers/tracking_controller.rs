@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use actix_web::{
+    http::header::{self, CacheControl, CacheDirective},
+    web::{self, Path},
+    HttpResponse, Responder,
+};
+use log::error;
+
+use crate::module::notification_service_module::services::tracking_service::TrackingService;
+
+// Transparent 1x1 GIF served by the open pixel
+const PIXEL_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+pub struct TrackingController {
+    tracking_service: Arc<TrackingService>,
+}
+
+impl TrackingController {
+    pub fn new(tracking_service: Arc<TrackingService>) -> Self {
+        Self { tracking_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/tracking")
+                // Public links found in emails
+                .route("/open/{token}", web::get().to(Self::open))
+                .route("/click/{token}", web::get().to(Self::click))
+                .route("/templates/{template_id}", web::get().to(Self::engagement)),
+        );
+    }
+
+    /// Serves the open pixel, the image is returned even when the open cannot be recorded
+    async fn open(
+        self_controller: web::Data<Arc<TrackingController>>,
+        path: Path<String>,
+    ) -> impl Responder {
+        if let Err(e) = self_controller.tracking_service.record_open(&path).await {
+            error!("Cannot record open: {}", e);
+        }
+
+        HttpResponse::Ok()
+            .content_type("image/gif")
+            // Every open must reach the gateway
+            .insert_header(CacheControl(vec![
+                CacheDirective::NoCache,
+                CacheDirective::NoStore,
+                CacheDirective::MustRevalidate,
+            ]))
+            .body(PIXEL_GIF)
+    }
+
+    /// Redirects to the target of a tracked link
+    ///
+    /// Only targets signed by the gateway are followed, so the endpoint is not an open redirect
+    async fn click(
+        self_controller: web::Data<Arc<TrackingController>>,
+        path: Path<String>,
+    ) -> impl Responder {
+        match self_controller.tracking_service.record_click(&path).await {
+            Ok(Some(url)) => HttpResponse::Found()
+                .insert_header((header::LOCATION, url))
+                .finish(),
+            Ok(None) => HttpResponse::NotFound().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn engagement(
+        self_controller: web::Data<Arc<TrackingController>>,
+        path: Path<String>,
+    ) -> impl Responder {
+        match self_controller
+            .tracking_service
+            .template_engagement(&path)
+            .await
+        {
+            Ok(engagement) => HttpResponse::Ok().json(engagement),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
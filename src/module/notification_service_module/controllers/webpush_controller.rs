@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use actix_web::{
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+
+use crate::module::notification_service_module::{
+    models::webpush::{WebPushSubscriptionQuery, WebPushSubscriptionRequest},
+    services::webpush_service::WebPushService,
+};
+
+pub struct WebPushController {
+    webpush_service: Arc<WebPushService>,
+}
+
+impl WebPushController {
+    pub fn new(webpush_service: Arc<WebPushService>) -> Self {
+        Self { webpush_service }
+    }
+
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::scope("/webpush")
+                .route("/subscription", web::post().to(Self::subscribe))
+                .route("/subscription/{id}", web::delete().to(Self::unsubscribe)),
+        );
+    }
+
+    async fn subscribe(
+        self_controller: web::Data<Arc<WebPushController>>,
+        request: Json<WebPushSubscriptionRequest>,
+    ) -> impl Responder {
+        match self_controller.webpush_service.subscribe(request.0).await {
+            Ok(response) => HttpResponse::Ok().json(response),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+
+    async fn unsubscribe(
+        self_controller: web::Data<Arc<WebPushController>>,
+        id: Path<String>,
+        query: Query<WebPushSubscriptionQuery>,
+    ) -> impl Responder {
+        match self_controller
+            .webpush_service
+            .unsubscribe(&id, &query.user_id)
+            .await
+        {
+            Ok(0) => HttpResponse::NotFound().finish(),
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
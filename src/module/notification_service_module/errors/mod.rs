@@ -15,8 +15,22 @@ pub enum NotiSrvError {
     #[display("Redis push failed")]
     RedisQueuePushError(PoolError),
 
+    /// Invalid input of the caller
     #[display("Invalid data field")]
     InvalidDataField(Box<dyn std::error::Error>),
+
+    #[display("Invalid condition: {_0}")]
+    InvalidCondition(#[error(not(source))] String),
+
+    #[display("Invalid webhook signature")]
+    InvalidSignature,
+
+    #[display("Sender not allowed: {_0}")]
+    SenderNotAllowed(#[error(not(source))] String),
+
+    /// The variables of a notification do not match its template, one message per mismatch
+    #[display("Invalid template variables")]
+    InvalidVariables(#[error(not(source))] Vec<String>),
 }
 
 impl ResponseError for NotiSrvError {
@@ -30,10 +44,26 @@ impl ResponseError for NotiSrvError {
                 .json(serde_json::json!({"messages": self.to_string()}))
                 .map_into_boxed_body(),
 
-            NotiSrvError::InvalidDataField(e) => HttpResponse::InternalServerError()
+            NotiSrvError::InvalidDataField(e) => HttpResponse::BadRequest()
                 .json(serde_json::json!({"messages": e.to_string()}))
                 .map_into_boxed_body(),
 
+            NotiSrvError::InvalidCondition(_) => HttpResponse::BadRequest()
+                .json(serde_json::json!({"messages": self.to_string()}))
+                .map_into_boxed_body(),
+
+            NotiSrvError::InvalidSignature => HttpResponse::Unauthorized()
+                .json(serde_json::json!({"messages": self.to_string()}))
+                .map_into_boxed_body(),
+
+            NotiSrvError::SenderNotAllowed(_) => HttpResponse::Forbidden()
+                .json(serde_json::json!({"messages": self.to_string()}))
+                .map_into_boxed_body(),
+
+            NotiSrvError::InvalidVariables(errors) => HttpResponse::UnprocessableEntity()
+                .json(serde_json::json!({"messages": self.to_string(), "errors": errors}))
+                .map_into_boxed_body(),
+
             _ => HttpResponse::InternalServerError().finish(),
         }
     }
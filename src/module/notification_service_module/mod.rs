@@ -1,44 +1,136 @@
 use std::sync::Arc;
 
 use actix_web::web;
-use controllers::notification_controller::NotificationController;
+use controllers::{
+    attachment_controller::AttachmentController, event_webhook_controller::EventWebhookController,
+    notification_controller::NotificationController, preference_controller::PreferenceController,
+    sender_controller::SenderController, suppression_controller::SuppressionController,
+    template_controller::TemplateController, tracking_controller::TrackingController,
+    webpush_controller::WebPushController,
+};
 use deadpool_redis::Pool;
-use repository::{notification_repository::NotificationRepo, redis_repository::RedisRepository};
-use services::notification_service::NotificationService;
+use repository::{
+    attachment_repository::AttachmentRepo, event_repository::EventRepo,
+    notification_repository::NotificationRepo, preference_repository::PreferenceRepo,
+    redis_repository::RedisRepository, sender_repository::SenderRepo,
+    suppression_repository::SuppressionRepo, template_repository::TemplateRepo,
+    webpush_repository::WebPushRepo,
+};
+use services::{
+    attachment_service::AttachmentService, event_service::EventService,
+    notification_service::NotificationService, preference_service::PreferenceService,
+    sender_service::SenderService, suppression_service::SuppressionService,
+    template_service::TemplateService, tracking_service::TrackingService,
+    webpush_service::WebPushService,
+};
 use sqlx::PgPool;
+use utils::attachment_policy::AttachmentPolicy;
 
 pub mod controllers;
 pub mod errors;
 pub mod models;
 pub mod repository;
 pub mod services;
+pub mod utils;
 
+#[derive(Clone)]
 pub struct NotiServiceModule {
     pub noti_controller: Arc<NotificationController>,
+    pub webpush_controller: Arc<WebPushController>,
+    pub event_webhook_controller: Arc<EventWebhookController>,
+    pub suppression_controller: Arc<SuppressionController>,
+    pub preference_controller: Arc<PreferenceController>,
+    pub tracking_controller: Arc<TrackingController>,
+    pub attachment_controller: Arc<AttachmentController>,
+    pub sender_controller: Arc<SenderController>,
+    pub template_controller: Arc<TemplateController>,
+    pub attachment_policy: Arc<AttachmentPolicy>,
 }
 
 impl NotiServiceModule {
-    pub fn new(pg_pool: Arc<PgPool>, redis_pool: Arc<Pool>) -> Self {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        redis_pool: Arc<Pool>,
+        attachment_policy: Arc<AttachmentPolicy>,
+    ) -> Self {
         // init repositories
-        let noti_repo = Arc::new(NotificationRepo::new(pg_pool));
+        let noti_repo = Arc::new(NotificationRepo::new(pg_pool.clone()));
         let redis_repo = Arc::new(RedisRepository::new(redis_pool));
+        let webpush_repo = Arc::new(WebPushRepo::new(pg_pool.clone()));
+        let event_repo = Arc::new(EventRepo::new(pg_pool.clone()));
+        let suppression_repo = Arc::new(SuppressionRepo::new(pg_pool.clone()));
+        let preference_repo = Arc::new(PreferenceRepo::new(pg_pool.clone()));
+        let attachment_repo = Arc::new(AttachmentRepo::new(pg_pool.clone()));
+        let sender_repo = Arc::new(SenderRepo::new(pg_pool.clone()));
+        let template_repo = Arc::new(TemplateRepo::new(pg_pool));
 
         // init services
+        let preference_service = Arc::new(PreferenceService::new(preference_repo.clone()));
+        let tracking_service = Arc::new(TrackingService::new(event_repo.clone()));
+        let attachment_service = Arc::new(AttachmentService::new(
+            attachment_repo.clone(),
+            attachment_policy.clone(),
+        ));
+        let sender_service = Arc::new(SenderService::new(
+            sender_repo.clone(),
+            noti_repo.clone(),
+            redis_repo.clone(),
+        ));
+        let template_service = Arc::new(TemplateService::new(template_repo.clone()));
         let noti_service = Arc::new(NotificationService::new(
             noti_repo.clone(),
             redis_repo.clone(),
+            webpush_repo.clone(),
+            suppression_repo.clone(),
+            preference_service.clone(),
+            tracking_service.clone(),
+            attachment_service.clone(),
+            sender_service.clone(),
+            template_service.clone(),
+        ));
+        let webpush_service = Arc::new(WebPushService::new(webpush_repo.clone()));
+        let event_service = Arc::new(EventService::new(
+            event_repo.clone(),
+            suppression_repo.clone(),
         ));
+        let suppression_service = Arc::new(SuppressionService::new(suppression_repo.clone()));
 
         // init controllers
         let noti_controller = NotificationController::new(noti_service.clone());
+        let webpush_controller = WebPushController::new(webpush_service.clone());
+        let event_webhook_controller = EventWebhookController::new(event_service.clone());
+        let suppression_controller = SuppressionController::new(suppression_service.clone());
+        let preference_controller = PreferenceController::new(preference_service.clone());
+        let tracking_controller = TrackingController::new(tracking_service.clone());
+        let attachment_controller = AttachmentController::new(attachment_service.clone());
+        let sender_controller = SenderController::new(sender_service.clone());
+        let template_controller =
+            TemplateController::new(template_service.clone(), noti_service.clone());
 
         // generate module
         Self {
             noti_controller: Arc::new(noti_controller),
+            webpush_controller: Arc::new(webpush_controller),
+            event_webhook_controller: Arc::new(event_webhook_controller),
+            suppression_controller: Arc::new(suppression_controller),
+            preference_controller: Arc::new(preference_controller),
+            tracking_controller: Arc::new(tracking_controller),
+            attachment_controller: Arc::new(attachment_controller),
+            sender_controller: Arc::new(sender_controller),
+            template_controller: Arc::new(template_controller),
+            attachment_policy,
         }
     }
 
-    pub fn routes_config(cfg: &mut web::ServiceConfig) {
-        NotificationController::routes(cfg);
+    pub fn routes_config(&self, cfg: &mut web::ServiceConfig) {
+        NotificationController::routes(cfg, &self.attachment_policy);
+        WebPushController::routes(cfg);
+        EventWebhookController::routes(cfg);
+        SuppressionController::routes(cfg);
+        PreferenceController::routes(cfg);
+        TrackingController::routes(cfg);
+        AttachmentController::routes(cfg, &self.attachment_policy);
+        SenderController::routes(cfg);
+        TemplateController::routes(cfg);
     }
 }
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentUploadQuery {
+    pub user_id: String,
+    pub filename: String,
+}
+
+/// An uploaded attachment, referenced by id in the payload of emails
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AttachmentRecord {
+    pub id: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// An attachment of an email payload (`optionals.attachments`)
+///
+/// Either inlines its base64 `content`, or references an uploaded attachment by `id`. Inline
+/// attachments have a `content_id`, referenced as `cid:<content_id>` in the HTML body
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PayloadAttachment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub filename: Option<String>,
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+    pub disposition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+}
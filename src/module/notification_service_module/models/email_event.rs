@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::{notification::NotificationStatus, suppression::SuppressionReason};
+
+/// An event posted by the SendGrid Event Webhook
+///
+/// Only the fields used to track notifications are read, see
+/// https://www.twilio.com/docs/sendgrid/for-developers/tracking-events/event
+#[derive(Debug, Deserialize)]
+pub struct SendGridEvent {
+    pub event: String,
+    /// Recipient the event is about, one of the recipients of the email
+    pub email: Option<String>,
+    /// `<X-Message-Id>.<filter suffix>`, absent on some engagement events
+    pub sg_message_id: Option<String>,
+    /// Unique id of the event, used to ignore redelivered events
+    pub sg_event_id: Option<String>,
+    /// Unix timestamp of the event
+    pub timestamp: i64,
+    pub reason: Option<String>,
+    pub response: Option<String>,
+    /// Bounce classification (`bounce` or `blocked`)
+    #[serde(rename = "type")]
+    pub bounce_type: Option<String>,
+    /// Link of a `click` event
+    pub url: Option<String>,
+}
+
+impl SendGridEvent {
+    /// The `X-Message-Id` returned when the email was sent
+    pub fn message_id(&self) -> Option<&str> {
+        self.sg_message_id
+            .as_deref()
+            .map(|id| id.split_once('.').map_or(id, |(prefix, _)| prefix))
+    }
+
+    /// Details kept with the event, the first of reason, response and url
+    pub fn detail(&self) -> Option<String> {
+        match (&self.reason, &self.response, &self.url) {
+            (Some(reason), _, _) => match &self.bounce_type {
+                Some(bounce_type) => Some(format!("{}: {}", bounce_type, reason)),
+                None => Some(reason.clone()),
+            },
+            (None, Some(response), _) => Some(response.clone()),
+            (None, None, Some(url)) => Some(url.clone()),
+            _ => None,
+        }
+    }
+
+    /// Status of the notification after the event
+    ///
+    /// Engagement events (`open`, `click`, ...) are recorded without changing the status
+    pub fn status(&self) -> Option<NotificationStatus> {
+        match self.event.as_str() {
+            "delivered" => Some(NotificationStatus::Delivered),
+            // SendGrid accepted a dropped email but will never attempt it
+            "bounce" | "dropped" => Some(NotificationStatus::Bounced),
+            _ => None,
+        }
+    }
+
+    /// Reason to stop emailing the recipient after the event
+    ///
+    /// Only hard bounces are suppressed, `blocked` bounces are usually temporary
+    pub fn suppression_reason(&self) -> Option<SuppressionReason> {
+        match (self.event.as_str(), self.bounce_type.as_deref()) {
+            ("bounce", Some("bounce")) => Some(SuppressionReason::Bounce),
+            ("spamreport", _) => Some(SuppressionReason::Complaint),
+            _ => None,
+        }
+    }
+}
+
+/// The notification a provider event is about
+#[derive(Debug, sqlx::FromRow)]
+pub struct EventNotification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub recipient: String,
+}
+
+/// A provider event about a notification, stored in `NotificationEvent`
+#[derive(Debug)]
+pub struct ProviderEvent {
+    pub notification_id: Uuid,
+    pub provider: String,
+    pub provider_event_id: Option<String>,
+    pub event: String,
+    pub detail: Option<String>,
+    pub occurred_at: NaiveDateTime,
+}
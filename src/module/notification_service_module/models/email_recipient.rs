@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+/// Header an email recipient is addressed in
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipientKind {
+    #[display("to")]
+    To,
+    #[display("cc")]
+    Cc,
+    #[display("bcc")]
+    Bcc,
+}
+
+/// Status of an email recipient when the notification is submitted, stored in
+/// `NotificationRecipient.status`
+///
+/// The delivery workers and the provider events move queued recipients forward
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum RecipientStatus {
+    #[display("queued")]
+    Queued,
+    /// Not sent because the address is on the suppression list
+    #[display("suppressed")]
+    Suppressed,
+    /// Not sent because the recipient opted out of the channel or the category
+    #[display("skipped")]
+    Skipped,
+}
+
+/// An address of an email, with its display name and its personalization
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EmailRecipient {
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Values replacing `{{key}}` in the subject and the body sent to this `to` recipient
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub substitutions: HashMap<String, String>,
+    /// One-click unsubscribe link of the recipient, set by the gateway for marketing emails
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub unsubscribe_url: Option<String>,
+}
+
+impl EmailRecipient {
+    pub fn new(email: &str) -> Self {
+        Self {
+            email: email.to_string(),
+            name: None,
+            substitutions: HashMap::new(),
+            unsubscribe_url: None,
+        }
+    }
+}
+
+/// A recipient of an email notification and whether it is sent to
+#[derive(Debug)]
+pub struct NotificationRecipient {
+    pub kind: RecipientKind,
+    pub recipient: EmailRecipient,
+    pub status: RecipientStatus,
+}
+
+/// Recipients an email is delivered to, queued with the notification
+#[derive(Debug, Serialize, Default)]
+pub struct EmailRecipients {
+    pub to: Vec<EmailRecipient>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cc: Vec<EmailRecipient>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bcc: Vec<EmailRecipient>,
+}
+
+/// The columns of `NotificationRecipient` shown in the timeline of a notification
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RecipientRecord {
+    pub email: String,
+    pub name: Option<String>,
+    pub kind: String,
+    pub status: String,
+    pub provider_message_id: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// Engagement tracking requested for an HTML email
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct TrackingOptions {
+    /// Adds a 1x1 pixel reporting when the email is opened
+    #[serde(default)]
+    pub opens: bool,
+    /// Rewrites the links to go through the click redirect endpoint
+    #[serde(default)]
+    pub clicks: bool,
+}
+
+/// Engagement counters of the notifications sent with a template
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EngagementCounters {
+    /// Notifications accepted by a provider
+    pub sent: i64,
+    /// Notifications opened at least once
+    pub opened: i64,
+    /// Notifications with at least one clicked link
+    pub clicked: i64,
+    pub opens: i64,
+    pub clicks: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateEngagement {
+    pub template_id: String,
+    #[serde(flatten)]
+    pub counters: EngagementCounters,
+    /// Share of the sent notifications opened, `0` when nothing was sent
+    pub open_rate: f64,
+    /// Share of the sent notifications clicked, `0` when nothing was sent
+    pub click_rate: f64,
+}
@@ -1,2 +1,12 @@
+pub mod attachment;
+pub mod email_event;
+pub mod email_recipient;
+pub mod engagement;
 pub mod notification;
 pub mod payload;
+pub mod preference;
+pub mod sender_identity;
+pub mod suppression;
+pub mod template;
+pub mod timeline;
+pub mod webpush;
@@ -1,6 +1,11 @@
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
+use super::{
+    email_recipient::{EmailRecipient, EmailRecipients},
+    engagement::TrackingOptions,
+};
+
 // pub struct Notification {
 //     pub id: String,
 //     pub user_id: String,
@@ -21,9 +26,11 @@ pub enum NotificationChannel {
     Email,
     #[display("sms")]
     Sms,
+    #[display("webpush")]
+    WebPush,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Display)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Display)]
 #[serde(rename_all = "lowercase")]
 pub enum PushRecipientType {
     #[display("token")]
@@ -34,14 +41,94 @@ pub enum PushRecipientType {
     Condition,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum PushProvider {
+    #[display("fcm")]
+    Fcm,
+    #[display("apns")]
+    Apns,
+}
+
+/// Lifecycle of a notification, stored in `Notification.status`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationStatus {
+    #[display("queued")]
+    Queued,
+    #[display("scheduled")]
+    Scheduled,
+    #[display("processing")]
+    Processing,
+    #[display("sent")]
+    Sent,
+    #[display("delivered")]
+    Delivered,
+    #[display("bounced")]
+    Bounced,
+    #[display("failed")]
+    Failed,
+    #[display("cancelled")]
+    Cancelled,
+    #[display("expired")]
+    Expired,
+    /// Not sent because the recipient is on the suppression list
+    #[display("suppressed")]
+    Suppressed,
+    /// Not sent because the recipient opted out of the channel or the category
+    #[display("skipped")]
+    Skipped,
+}
+
+impl NotificationStatus {
+    /// Statuses a notification may be in to move to this status, enforced by the repositories
+    pub fn allowed_from(&self) -> &'static [NotificationStatus] {
+        use NotificationStatus::*;
+
+        match self {
+            Queued => &[Scheduled],
+            Scheduled => &[],
+            Processing => &[Queued, Processing],
+            Sent => &[Queued, Processing],
+            Delivered => &[Sent],
+            Bounced => &[Sent, Delivered],
+            Failed => &[Queued, Scheduled, Processing],
+            Cancelled => &[Queued, Scheduled],
+            Expired => &[Queued, Scheduled, Processing],
+            // Recorded when the notification is submitted
+            Suppressed | Skipped => &[],
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct NotificationRequest {
     pub user_id: String,
+    /// Recipient of the notification, emails may list their recipients in `to` instead
+    #[serde(default)]
     pub recipient: String,
+    /// Recipients of an email, with display names and personalization
+    pub to: Option<Vec<EmailRecipient>>,
+    pub cc: Option<Vec<EmailRecipient>>,
+    pub bcc: Option<Vec<EmailRecipient>>,
     pub recipient_type: Option<PushRecipientType>,
     pub sender: Option<String>,
     pub channel: NotificationChannel,
+    pub provider: Option<PushProvider>,
+    /// Kind of notification (`marketing`, `security`, ...) recipients can opt out of
+    pub category: Option<String>,
+    /// Template rendered into the payload, `template_id` or `template_id@version`
     pub template_id: Option<String>,
+    /// Version of the template rendered, set once the template is rendered
+    #[serde(skip)]
+    pub template_version: Option<i32>,
+    /// Locale of the template variant rendered, `None` for the default variant
+    #[serde(skip)]
+    pub template_locale: Option<String>,
+    /// Locale templates are rendered for, the locale of the user profile if unset
+    pub locale: Option<String>,
+    /// Open and click tracking of HTML emails
+    pub tracking: Option<TrackingOptions>,
     pub payload: serde_json::Value,
 }
 
@@ -54,10 +141,17 @@ pub struct NotificationResponse {
 #[derive(Debug, Serialize)]
 pub struct NotificationEnQueue {
     pub notification_id: String,
+    pub user_id: String,
     pub recipient: String,
     pub recipient_type: Option<String>,
     pub sender: Option<String>,
     pub channel: String,
+    pub provider: Option<String>,
+    pub category: Option<String>,
+    /// Recipients an email is delivered to, with their unsubscribe links
+    pub recipients: Option<EmailRecipients>,
     pub template_id: Option<String>,
+    /// Locale the template was rendered with, recipient substitutions are formatted for it
+    pub template_locale: Option<String>,
     pub payload: serde_json::Value,
 }
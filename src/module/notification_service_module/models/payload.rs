@@ -4,8 +4,8 @@ pub struct PushPayload;
 
 impl Payload for PushPayload {
     fn validate_payload(payload: &Value) -> bool {
-        payload.get("title").map_or(false, |v| v.is_string())
-            && payload.get("body").map_or(false, |v| v.is_string())
+        payload.get("title").is_some_and(|v| v.is_string())
+            && payload.get("body").is_some_and(|v| v.is_string())
     }
 }
 
@@ -13,10 +13,28 @@ pub struct EmailPayload;
 
 impl Payload for EmailPayload {
     fn validate_payload(payload: &Value) -> bool {
-        payload.get("subject").map_or(false, |v| v.is_string())
-            && payload.get("content").map_or(false, |v| v.is_string())
-            && payload.get("content_type").map_or(true, |v| v.is_string())
-            && payload.get("variables").map_or(true, |v| v.is_object())
+        // The body is given as `html` and/or `text` parts, or as a single `content`
+        let has_body = ["content", "html", "text"]
+            .iter()
+            .any(|part| payload.get(part).is_some());
+
+        payload.get("subject").is_some_and(|v| v.is_string())
+            && has_body
+            && payload.get("content").is_none_or(|v| v.is_string())
+            && payload.get("html").is_none_or(|v| v.is_string())
+            && payload.get("text").is_none_or(|v| v.is_string())
+            && payload.get("content_type").is_none_or(|v| v.is_string())
+            && payload.get("variables").is_none_or(|v| v.is_object())
+    }
+}
+
+pub struct WebPushPayload;
+
+impl Payload for WebPushPayload {
+    fn validate_payload(payload: &Value) -> bool {
+        payload.get("title").is_some_and(|v| v.is_string())
+            && payload.get("body").is_some_and(|v| v.is_string())
+            && payload.get("data").is_none_or(|v| v.is_object())
     }
 }
 
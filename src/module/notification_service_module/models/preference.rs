@@ -0,0 +1,60 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Value of `channel` or `category` matching every channel or category
+pub const ANY: &str = "*";
+
+/// Whether an end-recipient accepts notifications of a channel and a category
+#[derive(Debug, Deserialize, Serialize, Clone, sqlx::FromRow)]
+pub struct Preference {
+    #[serde(default = "any")]
+    pub channel: String,
+    #[serde(default = "any")]
+    pub category: String,
+    pub enabled: bool,
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+fn any() -> String {
+    ANY.to_string()
+}
+
+impl Preference {
+    /// Ranks how closely the preference matches a notification, `None` if it does not apply
+    ///
+    /// A preference for the exact channel and category wins over a channel-wide one, which
+    /// wins over a category-wide one, which wins over a global one
+    pub fn specificity(&self, channel: &str, category: Option<&str>) -> Option<u8> {
+        let channel_score = match self.channel.as_str() {
+            ANY => 0,
+            value if value == channel => 2,
+            _ => return None,
+        };
+        let category_score = match (self.category.as_str(), category) {
+            (ANY, _) => 0,
+            (value, Some(category)) if value == category => 1,
+            _ => return None,
+        };
+        Some(channel_score + category_score)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreferenceQuery {
+    pub user_id: String,
+    pub recipient: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreferenceRequest {
+    pub user_id: String,
+    pub recipient: String,
+    pub preferences: Vec<Preference>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreferenceResponse {
+    pub recipient: String,
+    pub preferences: Vec<Preference>,
+}
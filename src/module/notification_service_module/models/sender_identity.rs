@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+/// What a sender identity covers: a single address, or every address of a domain
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum SenderIdentityKind {
+    #[display("email")]
+    Email,
+    #[display("domain")]
+    Domain,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SenderIdentityQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SenderIdentityRequest {
+    pub user_id: String,
+    /// Email address or domain, e.g. `news@example.com` or `example.com`
+    pub identity: String,
+    pub display_name: Option<String>,
+    pub reply_to_email: Option<String>,
+    pub reply_to_name: Option<String>,
+    /// Rejected: an identity becomes the default sender once verified, through
+    /// `PUT /sender/{id}/default`
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// An address or a domain a user may send emails from once verified
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct SenderIdentity {
+    pub id: String,
+    pub kind: String,
+    pub identity: String,
+    pub display_name: Option<String>,
+    pub reply_to_email: Option<String>,
+    pub reply_to_name: Option<String>,
+    pub is_default: bool,
+    #[serde(skip_serializing)]
+    pub verification_token: String,
+    pub verified_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// TXT record proving the ownership of a domain
+#[derive(Debug, Serialize)]
+pub struct DnsRecord {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SenderIdentityResponse {
+    #[serde(flatten)]
+    pub identity: SenderIdentity,
+    /// Record to publish for the verification of a domain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_record: Option<DnsRecord>,
+}
@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum SuppressionReason {
+    /// The address hard-bounced
+    #[display("bounce")]
+    Bounce,
+    /// The recipient marked an email as spam
+    #[display("complaint")]
+    Complaint,
+    /// Added through the API
+    #[display("manual")]
+    Manual,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuppressionRequest {
+    pub user_id: String,
+    pub email: String,
+    pub reason: Option<SuppressionReason>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuppressionQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuppressionResponse {
+    pub id: String,
+}
+
+/// An address the emails of a user are no longer sent to
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Suppression {
+    pub email: String,
+    pub reason: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
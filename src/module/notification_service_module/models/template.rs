@@ -0,0 +1,181 @@
+use chrono::NaiveDateTime;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Stage of a template version, stored in `TemplateVersion.status`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateVersionStatus {
+    /// Editable, never rendered by a send
+    #[display("draft")]
+    Draft,
+    /// Rendered by the sends which do not pin a version
+    #[display("published")]
+    Published,
+    /// Previously published, still rendered by the sends pinning it
+    #[display("retired")]
+    Retired,
+}
+
+/// Locale the templates of a user are rendered in when a notification gives none
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserLocale {
+    /// Locale tag such as `pt-BR`, `null` renders the default content
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateRequest {
+    pub user_id: String,
+    pub name: String,
+    /// Channel the template renders payloads of
+    #[serde(rename = "type")]
+    pub r#type: String,
+    /// Payload fields rendered with the `variables` of a notification, e.g. `subject`, `html`
+    /// and `text` of an email
+    pub content: Value,
+    /// Variants of `content` per locale, e.g. `pt-BR` or `pt`
+    #[serde(default)]
+    pub locales: Map<String, Value>,
+    /// JSON Schema the `variables` of a notification must match
+    pub variables_schema: Option<Value>,
+    /// Fails rendering on placeholders without a variable instead of keeping them
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateDraftRequest {
+    pub content: Value,
+    #[serde(default)]
+    pub locales: Map<String, Value>,
+    pub variables_schema: Option<Value>,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// The columns of a draft as stored, JSON values serialized
+#[derive(Debug)]
+pub struct TemplateDraft {
+    pub content: String,
+    pub locales: String,
+    pub variables_schema: Option<String>,
+    pub strict: bool,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+    /// Version rendered when a notification does not pin one
+    pub published_version: Option<i32>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TemplateVersionRecord {
+    pub version: i32,
+    pub status: String,
+    /// JSON object of the payload fields
+    pub content: String,
+    /// JSON object of the payload fields per locale
+    pub locales: String,
+    /// JSON Schema of the variables
+    pub variables_schema: Option<String>,
+    pub strict: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateVersion {
+    pub version: i32,
+    pub status: String,
+    pub content: Value,
+    pub locales: Value,
+    pub variables_schema: Option<Value>,
+    pub strict: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+impl From<TemplateVersionRecord> for TemplateVersion {
+    fn from(record: TemplateVersionRecord) -> Self {
+        Self {
+            version: record.version,
+            status: record.status,
+            content: serde_json::from_str(&record.content).unwrap_or(Value::Null),
+            locales: serde_json::from_str(&record.locales).unwrap_or(Value::Null),
+            variables_schema: record
+                .variables_schema
+                .and_then(|schema| serde_json::from_str(&schema).ok()),
+            strict: record.strict,
+            created_at: record.created_at,
+            published_at: record.published_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateResponse {
+    #[serde(flatten)]
+    pub template: Template,
+    pub versions: Vec<TemplateVersion>,
+}
+
+/// What a notification was rendered with
+#[derive(Debug)]
+pub struct RenderedTemplate {
+    pub template_id: String,
+    pub version: i32,
+    /// Locale of the variant rendered, `None` for the default content
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplatePreviewRequest {
+    /// Version to render, the draft or else the published version if unset
+    pub version: Option<i32>,
+    pub locale: Option<String>,
+    /// Sample values of the placeholders
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+}
+
+/// A template rendered with sample variables
+#[derive(Debug, Serialize)]
+pub struct TemplatePreview {
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+    pub version: i32,
+    pub status: String,
+    /// Locale of the variant rendered, `None` for the default content
+    pub locale: Option<String>,
+    /// Payload fields rendered, e.g. `subject`, `html` and `text` of an email
+    pub content: Value,
+    /// Whether the version requires a non-empty variable for each placeholder
+    pub strict: bool,
+    /// Placeholders without a sample variable, left as is in `content`, or with a null or
+    /// empty one
+    pub missing_variables: Vec<String>,
+    /// Mismatches of the sample variables with the schema of the version
+    pub invalid_variables: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateTestRequest {
+    /// Address the test email is sent to, which must be allow-listed
+    pub recipient: String,
+    pub sender: Option<String>,
+    #[serde(flatten)]
+    pub preview: TemplatePreviewRequest,
+}
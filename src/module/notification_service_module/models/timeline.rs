@@ -0,0 +1,85 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::email_recipient::RecipientRecord;
+
+/// The columns of `Notification` shown in its timeline
+#[derive(Debug, sqlx::FromRow)]
+pub struct NotificationRecord {
+    pub id: Uuid,
+    pub channel: Option<String>,
+    pub status: Option<String>,
+    pub provider: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub template_id: Option<Uuid>,
+    pub template_version: Option<i32>,
+    pub template_locale: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// A try to deliver a notification, recorded by the delivery workers in `DeliveryAttempt`
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DeliveryAttemptRecord {
+    pub attempt_number: i32,
+    pub outcome: Option<String>,
+    pub provider: Option<String>,
+    pub http_status: Option<i32>,
+    pub response_excerpt: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub latency_ms: i64,
+    pub error_kind: Option<String>,
+    pub error: Option<String>,
+    #[serde(rename = "at")]
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// An event reported by the provider after accepting the notification (delivered, open, ...)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProviderEventRecord {
+    pub provider: String,
+    #[serde(rename = "type")]
+    pub event: String,
+    pub detail: Option<String>,
+    #[serde(rename = "at")]
+    pub occurred_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum TimelineEvent {
+    Queued { at: Option<NaiveDateTime> },
+    Attempt(DeliveryAttemptRecord),
+    Provider(ProviderEventRecord),
+}
+
+impl TimelineEvent {
+    pub fn at(&self) -> Option<NaiveDateTime> {
+        match self {
+            TimelineEvent::Queued { at } => *at,
+            TimelineEvent::Attempt(attempt) => attempt.created_at,
+            TimelineEvent::Provider(event) => Some(event.occurred_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationTimeline {
+    pub id: String,
+    pub channel: Option<String>,
+    pub status: Option<String>,
+    /// Provider which accepted the notification and the id it gave to the message
+    pub provider: Option<String>,
+    pub provider_message_id: Option<String>,
+    /// Template and version the payload was rendered with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_version: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_locale: Option<String>,
+    /// Recipients of an email and how far each was delivered
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<RecipientRecord>,
+    pub events: Vec<TimelineEvent>,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Keys of a browser `PushSubscription`, as returned by `PushSubscription.toJSON()`
+#[derive(Debug, Deserialize)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebPushSubscriptionRequest {
+    pub user_id: String,
+    pub subscription: PushSubscription,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebPushSubscriptionQuery {
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebPushSubscriptionResponse {
+    pub id: String,
+}
@@ -0,0 +1,3 @@
+UPDATE SenderIdentity
+SET is_default = FALSE
+WHERE user_id = $1 AND is_default;
@@ -0,0 +1 @@
+DELETE FROM Attachment WHERE id = $1 AND user_id = $2;
@@ -0,0 +1 @@
+DELETE FROM Suppression WHERE user_id = $1 AND email = $2;
@@ -0,0 +1 @@
+DELETE FROM WebPushSubscription WHERE id = $1 AND user_id = $2;
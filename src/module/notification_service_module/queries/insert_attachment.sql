@@ -0,0 +1,3 @@
+INSERT INTO Attachment (id, user_id, filename, content_type, size, content)
+VALUES ($1, $2, $3, $4, $5, $6)
+RETURNING id::text, filename, content_type, size, created_at;
@@ -1,2 +1,2 @@
-INSERT INTO notification(id, user_id, recipient, channel, template_id, status) 
-VALUES ($1, $2, $3, $4, $5, $6);
\ No newline at end of file
+INSERT INTO notification(id, user_id, recipient, channel, template_id, template_version, template_locale, status, category) 
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9);
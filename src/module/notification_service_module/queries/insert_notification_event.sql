@@ -0,0 +1,3 @@
+INSERT INTO NotificationEvent (id, notification_id, provider, provider_event_id, event, detail, occurred_at)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (provider, provider_event_id) DO NOTHING;
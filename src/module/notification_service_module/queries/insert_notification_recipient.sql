@@ -0,0 +1,2 @@
+INSERT INTO NotificationRecipient (id, notification_id, email, name, kind, status)
+VALUES ($1, $2, $3, $4, $5, $6);
@@ -0,0 +1,3 @@
+INSERT INTO SenderIdentity (id, user_id, kind, identity, display_name, reply_to_email, reply_to_name, verification_token)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+RETURNING id::text, kind, identity, display_name, reply_to_email, reply_to_name, is_default, verification_token, verified_at, created_at;
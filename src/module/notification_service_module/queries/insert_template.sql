@@ -0,0 +1,3 @@
+INSERT INTO Template (id, user_id, name, type)
+VALUES ($1, $2, $3, $4)
+RETURNING id::text, name, type, published_version, created_at, updated_at;
@@ -0,0 +1 @@
+SELECT id::text, filename, content_type, size, created_at FROM Attachment WHERE id = $1 AND user_id = $2;
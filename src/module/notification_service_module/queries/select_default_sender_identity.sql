@@ -0,0 +1,3 @@
+SELECT id::text, kind, identity, display_name, reply_to_email, reply_to_name, is_default, verification_token, verified_at, created_at
+FROM SenderIdentity
+WHERE user_id = $1 AND is_default AND verified_at IS NOT NULL;
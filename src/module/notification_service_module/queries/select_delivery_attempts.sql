@@ -0,0 +1,2 @@
+SELECT attempt_number, outcome, provider, http_status, response_excerpt, provider_message_id, latency_ms, error_kind, error, created_at
+FROM DeliveryAttempt WHERE notification_id = $1 ORDER BY attempt_number;
@@ -0,0 +1 @@
+SELECT id, channel, status, provider, provider_message_id, template_id, template_version, template_locale, created_at FROM Notification WHERE id = $1;
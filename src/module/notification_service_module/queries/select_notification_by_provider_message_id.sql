@@ -0,0 +1,7 @@
+SELECT id, user_id, recipient FROM Notification WHERE provider = $1 AND provider_message_id = $2
+UNION
+-- Emails sent to each recipient separately get a message id per recipient
+SELECT n.id, n.user_id, n.recipient FROM NotificationRecipient r
+JOIN Notification n ON n.id = r.notification_id
+WHERE r.provider = $1 AND r.provider_message_id = $2
+LIMIT 1;
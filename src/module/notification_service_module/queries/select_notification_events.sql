@@ -0,0 +1,2 @@
+SELECT provider, event, detail, occurred_at FROM NotificationEvent
+WHERE notification_id = $1 ORDER BY occurred_at;
@@ -0,0 +1,2 @@
+SELECT email, name, kind, status, provider_message_id, updated_at
+FROM NotificationRecipient WHERE notification_id = $1 ORDER BY created_at, kind, email;
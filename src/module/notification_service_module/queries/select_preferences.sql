@@ -0,0 +1,2 @@
+SELECT channel, category, enabled, updated_at FROM Preference
+WHERE user_id = $1 AND recipient = $2 ORDER BY channel, category;
@@ -0,0 +1,2 @@
+SELECT status FROM NotificationRecipient
+WHERE notification_id = $1 AND status NOT IN ('suppressed', 'skipped');
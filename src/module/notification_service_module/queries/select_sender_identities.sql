@@ -0,0 +1,4 @@
+SELECT id::text, kind, identity, display_name, reply_to_email, reply_to_name, is_default, verification_token, verified_at, created_at
+FROM SenderIdentity
+WHERE user_id = $1
+ORDER BY created_at;
@@ -0,0 +1,8 @@
+-- An identity of the address wins over an identity of its domain
+SELECT id::text, kind, identity, display_name, reply_to_email, reply_to_name, is_default, verification_token, verified_at, created_at
+FROM SenderIdentity
+WHERE user_id = $1
+  AND verified_at IS NOT NULL
+  AND ((kind = 'email' AND identity = $2) OR (kind = 'domain' AND identity = $3))
+ORDER BY kind = 'email' DESC
+LIMIT 1;
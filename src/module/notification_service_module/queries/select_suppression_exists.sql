@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM Suppression WHERE user_id = $1 AND email = $2);
@@ -0,0 +1 @@
+SELECT email, reason, detail, created_at FROM Suppression WHERE user_id = $1 ORDER BY created_at DESC;
@@ -0,0 +1,3 @@
+SELECT id::text, name, type, published_version, created_at, updated_at
+FROM Template
+WHERE id = $1 AND user_id = $2;
@@ -0,0 +1,9 @@
+SELECT
+    COUNT(DISTINCT n.id) FILTER (WHERE n.status IN ('sent', 'delivered', 'bounced')) AS sent,
+    COUNT(DISTINCT e.notification_id) FILTER (WHERE e.event = 'open') AS opened,
+    COUNT(DISTINCT e.notification_id) FILTER (WHERE e.event = 'click') AS clicked,
+    COUNT(e.id) FILTER (WHERE e.event = 'open') AS opens,
+    COUNT(e.id) FILTER (WHERE e.event = 'click') AS clicks
+FROM Notification n
+LEFT JOIN NotificationEvent e ON e.notification_id = n.id
+WHERE n.template_id = $1;
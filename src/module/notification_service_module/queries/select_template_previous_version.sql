@@ -0,0 +1,6 @@
+-- Version published before the current one
+SELECT version
+FROM TemplateVersion
+WHERE template_id = $1 AND status = 'retired'
+ORDER BY published_at DESC
+LIMIT 1;
@@ -0,0 +1,3 @@
+SELECT version, status, content, locales, variables_schema, strict, created_at, published_at
+FROM TemplateVersion
+WHERE template_id = $1 AND version = $2;
@@ -0,0 +1,4 @@
+SELECT version, status, content, locales, variables_schema, strict, created_at, published_at
+FROM TemplateVersion
+WHERE template_id = $1
+ORDER BY version DESC;
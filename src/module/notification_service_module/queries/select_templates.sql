@@ -0,0 +1,4 @@
+SELECT id::text, name, type, published_version, created_at, updated_at
+FROM Template
+WHERE user_id = $1
+ORDER BY created_at;
@@ -0,0 +1 @@
+SELECT locale FROM Users WHERE id = $1;
@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM WebPushSubscription WHERE id = $1 AND user_id = $2);
@@ -0,0 +1,3 @@
+UPDATE SenderIdentity
+SET is_default = TRUE
+WHERE id = $1 AND user_id = $2 AND kind = 'email' AND verified_at IS NOT NULL;
@@ -0,0 +1 @@
+UPDATE Notification SET status = $1, updated_at = NOW() WHERE id = $2 AND status = ANY($3);
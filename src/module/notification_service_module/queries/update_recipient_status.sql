@@ -0,0 +1,2 @@
+UPDATE NotificationRecipient SET status = $1, updated_at = NOW()
+WHERE notification_id = $2 AND email = $3 AND status = ANY($4);
@@ -0,0 +1,3 @@
+UPDATE SenderIdentity
+SET verified_at = NOW()
+WHERE id = $1 AND verification_token = $2 AND verified_at IS NULL;
@@ -0,0 +1,3 @@
+UPDATE Template
+SET published_version = $2, updated_at = NOW()
+WHERE id = $1;
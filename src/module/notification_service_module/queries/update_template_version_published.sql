@@ -0,0 +1,4 @@
+UPDATE TemplateVersion
+SET status = 'published', published_at = NOW()
+WHERE template_id = $1 AND version = $2
+RETURNING version, status, content, locales, variables_schema, strict, created_at, published_at;
@@ -0,0 +1,3 @@
+UPDATE TemplateVersion
+SET status = 'retired'
+WHERE template_id = $1 AND status = 'published' AND version <> $2;
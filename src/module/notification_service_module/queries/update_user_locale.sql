@@ -0,0 +1 @@
+UPDATE Users SET locale = $2 WHERE id = $1;
@@ -0,0 +1,4 @@
+INSERT INTO Preference(id, user_id, recipient, channel, category, enabled)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (user_id, recipient, channel, category)
+DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW();
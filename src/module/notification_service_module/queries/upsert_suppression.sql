@@ -0,0 +1,5 @@
+INSERT INTO Suppression(id, user_id, email, reason, detail)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (user_id, email)
+DO UPDATE SET reason = EXCLUDED.reason, detail = EXCLUDED.detail
+RETURNING id;
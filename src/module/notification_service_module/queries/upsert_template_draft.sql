@@ -0,0 +1,9 @@
+-- A template has at most one draft, following its latest version
+INSERT INTO TemplateVersion (id, template_id, version, status, content, locales, variables_schema, strict)
+SELECT $1, $2, COALESCE(MAX(version), 0) + 1, 'draft', $3, $4, $5, $6
+FROM TemplateVersion
+WHERE template_id = $2
+ON CONFLICT (template_id) WHERE status = 'draft'
+DO UPDATE SET content = EXCLUDED.content, locales = EXCLUDED.locales,
+    variables_schema = EXCLUDED.variables_schema, strict = EXCLUDED.strict
+RETURNING version, status, content, locales, variables_schema, strict, created_at, published_at;
@@ -0,0 +1,5 @@
+INSERT INTO WebPushSubscription(id, user_id, endpoint, p256dh, auth)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (user_id, endpoint)
+DO UPDATE SET p256dh = EXCLUDED.p256dh, auth = EXCLUDED.auth
+RETURNING id;
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::attachment::AttachmentRecord;
+
+pub struct AttachmentRepo {
+    pool: Arc<PgPool>,
+}
+
+impl AttachmentRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl AttachmentRepo {
+    pub async fn insert(
+        &self,
+        user_id: &Uuid,
+        filename: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<AttachmentRecord, sqlx::Error> {
+        let stm = include_str!("../queries/insert_attachment.sql");
+
+        sqlx::query_as(stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(filename)
+            .bind(content_type)
+            .bind(content.len() as i64)
+            .bind(content)
+            .fetch_one(&*self.pool)
+            .await
+    }
+
+    /// Finds an attachment uploaded by the user, without its content
+    pub async fn find(
+        &self,
+        user_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<AttachmentRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_attachment.sql");
+
+        sqlx::query_as(stm)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    pub async fn delete(&self, user_id: &Uuid, id: &Uuid) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/delete_attachment.sql");
+
+        let result = sqlx::query(stm)
+            .bind(id)
+            .bind(user_id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::{
+    email_event::{EventNotification, ProviderEvent},
+    engagement::EngagementCounters,
+    notification::NotificationStatus,
+};
+
+pub struct EventRepo {
+    pool: Arc<PgPool>,
+}
+
+impl EventRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl EventRepo {
+    /// Finds the notification a provider accepted under the given message id
+    pub async fn find_notification(
+        &self,
+        provider: &str,
+        provider_message_id: &str,
+    ) -> Result<Option<EventNotification>, sqlx::Error> {
+        let stm = include_str!("../queries/select_notification_by_provider_message_id.sql");
+
+        sqlx::query_as(stm)
+            .bind(provider)
+            .bind(provider_message_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Stores a provider event, ignoring events already received
+    pub async fn insert_event(&self, event: &ProviderEvent) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/insert_notification_event.sql");
+
+        let result = sqlx::query(stm)
+            .bind(Uuid::new_v4())
+            .bind(event.notification_id)
+            .bind(&event.provider)
+            .bind(&event.provider_event_id)
+            .bind(&event.event)
+            .bind(&event.detail)
+            .bind(event.occurred_at)
+            .execute(&*self.pool)
+            .await?;
+
+        info!("Query insert result: {}", result.rows_affected());
+
+        Ok(result.rows_affected())
+    }
+
+    /// Moves a notification to `status`, if its current status allows the transition
+    pub async fn update_status(
+        &self,
+        id: &Uuid,
+        status: NotificationStatus,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_notification_status.sql");
+
+        let allowed_from: Vec<String> = status
+            .allowed_from()
+            .iter()
+            .map(|status| status.to_string())
+            .collect();
+
+        let result = sqlx::query(stm)
+            .bind(status.to_string())
+            .bind(id)
+            .bind(allowed_from)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Moves a recipient of a notification to `status`, if its current status allows the
+    /// transition
+    pub async fn update_recipient_status(
+        &self,
+        notification_id: &Uuid,
+        email: &str,
+        status: NotificationStatus,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_recipient_status.sql");
+
+        let allowed_from: Vec<String> = status
+            .allowed_from()
+            .iter()
+            .map(|status| status.to_string())
+            .collect();
+
+        let result = sqlx::query(stm)
+            .bind(status.to_string())
+            .bind(notification_id)
+            .bind(email)
+            .bind(allowed_from)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns the statuses of the recipients an email notification was sent to
+    pub async fn find_recipient_statuses(
+        &self,
+        notification_id: &Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let stm = include_str!("../queries/select_recipient_statuses.sql");
+
+        sqlx::query_scalar(stm)
+            .bind(notification_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    /// Counts the opens and clicks of the notifications sent with a template
+    pub async fn template_engagement(
+        &self,
+        template_id: &Uuid,
+    ) -> Result<EngagementCounters, sqlx::Error> {
+        let stm = include_str!("../queries/select_template_engagement.sql");
+
+        sqlx::query_as(stm)
+            .bind(template_id)
+            .fetch_one(&*self.pool)
+            .await
+    }
+}
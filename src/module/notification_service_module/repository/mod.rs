@@ -1,2 +1,9 @@
+pub mod attachment_repository;
+pub mod event_repository;
 pub mod notification_repository;
+pub mod preference_repository;
 pub mod redis_repository;
+pub mod sender_repository;
+pub mod suppression_repository;
+pub mod template_repository;
+pub mod webpush_repository;
@@ -4,7 +4,11 @@ use log::info;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::module::notification_service_module::models::notification::NotificationRequest;
+use crate::module::notification_service_module::models::{
+    email_recipient::{NotificationRecipient, RecipientRecord},
+    notification::{NotificationRequest, NotificationStatus},
+    timeline::{DeliveryAttemptRecord, NotificationRecord, ProviderEventRecord},
+};
 
 pub struct NotificationRepo {
     pool: Arc<PgPool>,
@@ -20,6 +24,7 @@ impl NotificationRepo {
     pub async fn insert(
         &self,
         notification_request: &NotificationRequest,
+        status: NotificationStatus,
     ) -> Result<String, sqlx::Error> {
         // generate id
         let uuid = Uuid::new_v4();
@@ -30,10 +35,7 @@ impl NotificationRepo {
         let user_id = Uuid::parse_str(&notification_request.user_id).unwrap();
 
         let template_id = notification_request.template_id.clone();
-        let template_id = match template_id {
-            Some(value) => Some(Uuid::parse_str(&value).unwrap()),
-            None => None,
-        };
+        let template_id = template_id.map(|value| Uuid::parse_str(&value).unwrap());
 
         let result = sqlx::query(stm)
             .bind(uuid)
@@ -41,7 +43,10 @@ impl NotificationRepo {
             .bind(notification_request.recipient.clone())
             .bind(notification_request.channel.to_string())
             .bind(template_id)
-            .bind("pending".to_string())
+            .bind(notification_request.template_version)
+            .bind(&notification_request.template_locale)
+            .bind(status.to_string())
+            .bind(&notification_request.category)
             .execute(&*self.pool)
             .await?;
 
@@ -49,4 +54,78 @@ impl NotificationRepo {
 
         Ok(uuid.to_string())
     }
+
+    pub async fn find_by_id(&self, id: &Uuid) -> Result<Option<NotificationRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_notification.sql");
+
+        sqlx::query_as(stm)
+            .bind(id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Returns the delivery attempts of a notification, oldest first
+    pub async fn find_attempts(
+        &self,
+        notification_id: &Uuid,
+    ) -> Result<Vec<DeliveryAttemptRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_delivery_attempts.sql");
+
+        sqlx::query_as(stm)
+            .bind(notification_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    /// Returns the events reported by providers about a notification, oldest first
+    pub async fn find_events(
+        &self,
+        notification_id: &Uuid,
+    ) -> Result<Vec<ProviderEventRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_notification_events.sql");
+
+        sqlx::query_as(stm)
+            .bind(notification_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    /// Stores the recipients of an email notification
+    pub async fn insert_recipients(
+        &self,
+        notification_id: &str,
+        recipients: &[NotificationRecipient],
+    ) -> Result<(), sqlx::Error> {
+        let stm = include_str!("../queries/insert_notification_recipient.sql");
+
+        let notification_id =
+            Uuid::parse_str(notification_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        let mut tx = self.pool.begin().await?;
+        for recipient in recipients {
+            sqlx::query(stm)
+                .bind(Uuid::new_v4())
+                .bind(notification_id)
+                .bind(&recipient.recipient.email)
+                .bind(&recipient.recipient.name)
+                .bind(recipient.kind.to_string())
+                .bind(recipient.status.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Returns the recipients of an email notification
+    pub async fn find_recipients(
+        &self,
+        notification_id: &Uuid,
+    ) -> Result<Vec<RecipientRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_notification_recipients.sql");
+
+        sqlx::query_as(stm)
+            .bind(notification_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
 }
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::preference::Preference;
+
+pub struct PreferenceRepo {
+    pool: Arc<PgPool>,
+}
+
+impl PreferenceRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl PreferenceRepo {
+    /// Stores the preferences of a recipient in a single transaction
+    pub async fn upsert_all(
+        &self,
+        user_id: &Uuid,
+        recipient: &str,
+        preferences: &[Preference],
+    ) -> Result<(), sqlx::Error> {
+        let stm = include_str!("../queries/upsert_preference.sql");
+
+        let mut tx = self.pool.begin().await?;
+        for preference in preferences {
+            sqlx::query(stm)
+                .bind(Uuid::new_v4())
+                .bind(user_id)
+                .bind(recipient)
+                .bind(&preference.channel)
+                .bind(&preference.category)
+                .bind(preference.enabled)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    pub async fn find(
+        &self,
+        user_id: &Uuid,
+        recipient: &str,
+    ) -> Result<Vec<Preference>, sqlx::Error> {
+        let stm = include_str!("../queries/select_preferences.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .bind(recipient)
+            .fetch_all(&*self.pool)
+            .await
+    }
+}
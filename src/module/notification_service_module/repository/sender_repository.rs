@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::sender_identity::{
+    SenderIdentity, SenderIdentityKind, SenderIdentityRequest,
+};
+
+pub struct SenderRepo {
+    pool: Arc<PgPool>,
+}
+
+impl SenderRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl SenderRepo {
+    /// Stores an unverified identity, `identity` being the normalized address or domain
+    pub async fn insert(
+        &self,
+        user_id: &Uuid,
+        kind: SenderIdentityKind,
+        identity: &str,
+        request: &SenderIdentityRequest,
+        verification_token: &str,
+    ) -> Result<SenderIdentity, sqlx::Error> {
+        let stm = include_str!("../queries/insert_sender_identity.sql");
+
+        sqlx::query_as(stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(kind.to_string())
+            .bind(identity)
+            .bind(&request.display_name)
+            .bind(&request.reply_to_email)
+            .bind(&request.reply_to_name)
+            .bind(verification_token)
+            .fetch_one(&*self.pool)
+            .await
+    }
+
+    pub async fn find_all(&self, user_id: &Uuid) -> Result<Vec<SenderIdentity>, sqlx::Error> {
+        let stm = include_str!("../queries/select_sender_identities.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    pub async fn find(
+        &self,
+        user_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<SenderIdentity>, sqlx::Error> {
+        let stm = include_str!("../queries/select_sender_identity.sql");
+
+        sqlx::query_as(stm)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Finds the verified identity allowing the user to send from `email`
+    pub async fn find_for_address(
+        &self,
+        user_id: &Uuid,
+        email: &str,
+        domain: &str,
+    ) -> Result<Option<SenderIdentity>, sqlx::Error> {
+        let stm = include_str!("../queries/select_sender_identity_for_address.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .bind(email)
+            .bind(domain)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Finds the verified default sender of the user
+    pub async fn find_default(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<SenderIdentity>, sqlx::Error> {
+        let stm = include_str!("../queries/select_default_sender_identity.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Marks an identity verified if the token matches, returns the number of rows updated
+    pub async fn mark_verified(
+        &self,
+        id: &Uuid,
+        verification_token: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_sender_identity_verified.sql");
+
+        let result = sqlx::query(stm)
+            .bind(id)
+            .bind(verification_token)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Makes a verified email identity the default sender of the user, in place of the
+    /// previous one
+    pub async fn set_default(&self, user_id: &Uuid, id: &Uuid) -> Result<u64, sqlx::Error> {
+        let clear_stm = include_str!("../queries/clear_default_sender_identity.sql");
+        let update_stm = include_str!("../queries/update_default_sender_identity.sql");
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(clear_stm)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(update_stm)
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Keep the previous default when the identity is not a verified email identity of the
+        // user
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(0);
+        }
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete(&self, user_id: &Uuid, id: &Uuid) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/delete_sender_identity.sql");
+
+        let result = sqlx::query(stm)
+            .bind(id)
+            .bind(user_id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::suppression::{
+    Suppression, SuppressionReason,
+};
+
+pub struct SuppressionRepo {
+    pool: Arc<PgPool>,
+}
+
+impl SuppressionRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl SuppressionRepo {
+    /// Suppresses an address, updating the reason if it was already suppressed
+    pub async fn upsert(
+        &self,
+        user_id: &Uuid,
+        email: &str,
+        reason: SuppressionReason,
+        detail: Option<&str>,
+    ) -> Result<String, sqlx::Error> {
+        let stm = include_str!("../queries/upsert_suppression.sql");
+
+        let (id,): (Uuid,) = sqlx::query_as(stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(email)
+            .bind(reason.to_string())
+            .bind(detail)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        Ok(id.to_string())
+    }
+
+    pub async fn delete(&self, user_id: &Uuid, email: &str) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/delete_suppression.sql");
+
+        let result = sqlx::query(stm)
+            .bind(user_id)
+            .bind(email)
+            .execute(&*self.pool)
+            .await?;
+
+        info!("Query delete result: {}", result.rows_affected());
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_by_user(&self, user_id: &Uuid) -> Result<Vec<Suppression>, sqlx::Error> {
+        let stm = include_str!("../queries/select_suppressions.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    pub async fn exists(&self, user_id: &Uuid, email: &str) -> Result<bool, sqlx::Error> {
+        let stm = include_str!("../queries/select_suppression_exists.sql");
+
+        let (exists,): (bool,) = sqlx::query_as(stm)
+            .bind(user_id)
+            .bind(email)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+}
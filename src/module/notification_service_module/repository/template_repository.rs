@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::template::{
+    Template, TemplateDraft, TemplateVersionRecord,
+};
+
+pub struct TemplateRepo {
+    pool: Arc<PgPool>,
+}
+
+impl TemplateRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl TemplateRepo {
+    /// Stores a template with its content as the draft of version 1
+    pub async fn insert(
+        &self,
+        user_id: &Uuid,
+        name: &str,
+        r#type: &str,
+        draft: &TemplateDraft,
+    ) -> Result<(Template, TemplateVersionRecord), sqlx::Error> {
+        let template_stm = include_str!("../queries/insert_template.sql");
+        let draft_stm = include_str!("../queries/upsert_template_draft.sql");
+
+        let mut tx = self.pool.begin().await?;
+        let template: Template = sqlx::query_as(template_stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(name)
+            .bind(r#type)
+            .fetch_one(&mut *tx)
+            .await?;
+        let template_id =
+            Uuid::parse_str(&template.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let version = sqlx::query_as(draft_stm)
+            .bind(Uuid::new_v4())
+            .bind(template_id)
+            .bind(&draft.content)
+            .bind(&draft.locales)
+            .bind(&draft.variables_schema)
+            .bind(draft.strict)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok((template, version))
+    }
+
+    pub async fn find_all(&self, user_id: &Uuid) -> Result<Vec<Template>, sqlx::Error> {
+        let stm = include_str!("../queries/select_templates.sql");
+
+        sqlx::query_as(stm)
+            .bind(user_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    pub async fn find(&self, user_id: &Uuid, id: &Uuid) -> Result<Option<Template>, sqlx::Error> {
+        let stm = include_str!("../queries/select_template.sql");
+
+        sqlx::query_as(stm)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Returns the versions of a template, latest first
+    pub async fn find_versions(
+        &self,
+        template_id: &Uuid,
+    ) -> Result<Vec<TemplateVersionRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_template_versions.sql");
+
+        sqlx::query_as(stm)
+            .bind(template_id)
+            .fetch_all(&*self.pool)
+            .await
+    }
+
+    pub async fn find_version(
+        &self,
+        template_id: &Uuid,
+        version: i32,
+    ) -> Result<Option<TemplateVersionRecord>, sqlx::Error> {
+        let stm = include_str!("../queries/select_template_version.sql");
+
+        sqlx::query_as(stm)
+            .bind(template_id)
+            .bind(version)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Replaces the content of the draft, or starts a draft after the latest version
+    pub async fn upsert_draft(
+        &self,
+        template_id: &Uuid,
+        draft: &TemplateDraft,
+    ) -> Result<TemplateVersionRecord, sqlx::Error> {
+        let stm = include_str!("../queries/upsert_template_draft.sql");
+
+        sqlx::query_as(stm)
+            .bind(Uuid::new_v4())
+            .bind(template_id)
+            .bind(&draft.content)
+            .bind(&draft.locales)
+            .bind(&draft.variables_schema)
+            .bind(draft.strict)
+            .fetch_one(&*self.pool)
+            .await
+    }
+
+    /// Publishes a version in place of the published one, which is retired
+    pub async fn publish(
+        &self,
+        template_id: &Uuid,
+        version: i32,
+    ) -> Result<Option<TemplateVersionRecord>, sqlx::Error> {
+        let retire_stm = include_str!("../queries/update_template_version_retired.sql");
+        let publish_stm = include_str!("../queries/update_template_version_published.sql");
+        let template_stm = include_str!("../queries/update_template_published_version.sql");
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(retire_stm)
+            .bind(template_id)
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        let published: Option<TemplateVersionRecord> = sqlx::query_as(publish_stm)
+            .bind(template_id)
+            .bind(version)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        // Keep the published version when the version does not exist
+        if published.is_none() {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        sqlx::query(template_stm)
+            .bind(template_id)
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(published)
+    }
+
+    /// Returns the version published before the current one
+    pub async fn find_previous_version(
+        &self,
+        template_id: &Uuid,
+    ) -> Result<Option<i32>, sqlx::Error> {
+        let stm = include_str!("../queries/select_template_previous_version.sql");
+
+        sqlx::query_scalar(stm)
+            .bind(template_id)
+            .fetch_optional(&*self.pool)
+            .await
+    }
+
+    /// Returns the locale set on the profile of the user
+    pub async fn find_user_locale(&self, user_id: &Uuid) -> Result<Option<String>, sqlx::Error> {
+        let stm = include_str!("../queries/select_user_locale.sql");
+
+        let locale: Option<Option<String>> = sqlx::query_scalar(stm)
+            .bind(user_id)
+            .fetch_optional(&*self.pool)
+            .await?;
+
+        Ok(locale.flatten())
+    }
+
+    /// Sets the locale of the profile of the user, returns the number of rows updated
+    pub async fn update_user_locale(
+        &self,
+        user_id: &Uuid,
+        locale: Option<&str>,
+    ) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/update_user_locale.sql");
+
+        let result = sqlx::query(stm)
+            .bind(user_id)
+            .bind(locale)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use log::info;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::models::webpush::PushSubscription;
+
+pub struct WebPushRepo {
+    pool: Arc<PgPool>,
+}
+
+impl WebPushRepo {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl WebPushRepo {
+    /// Stores a browser subscription, returning the id of the existing row if the endpoint is known
+    pub async fn upsert(
+        &self,
+        user_id: &Uuid,
+        subscription: &PushSubscription,
+    ) -> Result<String, sqlx::Error> {
+        let stm = include_str!("../queries/upsert_webpush_subscription.sql");
+
+        let (id,): (Uuid,) = sqlx::query_as(stm)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(&subscription.endpoint)
+            .bind(&subscription.keys.p256dh)
+            .bind(&subscription.keys.auth)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        Ok(id.to_string())
+    }
+
+    pub async fn delete(&self, id: &Uuid, user_id: &Uuid) -> Result<u64, sqlx::Error> {
+        let stm = include_str!("../queries/delete_webpush_subscription.sql");
+
+        let result = sqlx::query(stm)
+            .bind(id)
+            .bind(user_id)
+            .execute(&*self.pool)
+            .await?;
+
+        info!("Query delete result: {}", result.rows_affected());
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn exists(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, sqlx::Error> {
+        let stm = include_str!("../queries/select_webpush_subscription_exists.sql");
+
+        let (exists,): (bool,) = sqlx::query_as(stm)
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+}
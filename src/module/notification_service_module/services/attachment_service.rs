@@ -0,0 +1,254 @@
+use std::{collections::HashSet, sync::Arc};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{error, info};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::attachment::{AttachmentRecord, PayloadAttachment},
+    repository::attachment_repository::AttachmentRepo,
+    utils::{attachment_policy::AttachmentPolicy, email_options},
+};
+
+/// `AttachmentService` stores uploaded attachments and validates the attachments of emails
+///
+/// Attachments uploaded once are referenced by id in the payload of emails, only their id
+/// goes through the queue and the delivery workers load their content
+pub struct AttachmentService {
+    attachment_repo: Arc<AttachmentRepo>,
+    policy: Arc<AttachmentPolicy>,
+}
+
+impl AttachmentService {
+    pub fn new(attachment_repo: Arc<AttachmentRepo>, policy: Arc<AttachmentPolicy>) -> Self {
+        Self {
+            attachment_repo,
+            policy,
+        }
+    }
+
+    pub async fn upload(
+        &self,
+        user_id: &str,
+        filename: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<AttachmentRecord, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let content_type = self
+            .policy
+            .check(filename, content_type, content)
+            .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+
+        let attachment = self
+            .attachment_repo
+            .insert(&user_id, filename.trim(), &content_type, content)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        info!(
+            "Attachment {} uploaded ({} bytes)",
+            attachment.id, attachment.size
+        );
+        Ok(attachment)
+    }
+
+    pub async fn find(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<AttachmentRecord>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let id = Self::parse_uuid(id, "id")?;
+
+        self.attachment_repo.find(&user_id, &id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })
+    }
+
+    pub async fn delete(&self, user_id: &str, id: &str) -> Result<u64, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let id = Self::parse_uuid(id, "id")?;
+
+        self.attachment_repo
+            .delete(&user_id, &id)
+            .await
+            .map_err(|e| {
+                error!("Database delete error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    /// Validates the attachments of an email payload (`optionals.attachments`)
+    ///
+    /// Inline attachments must be valid base64 and references must point at attachments
+    /// uploaded by the user. Each attachment is checked against the policy and the total size
+    /// of the email is limited. Attachments are rewritten with their checked content type,
+    /// references with the metadata of the upload
+    ///
+    /// Every `cid:` URL of the HTML body must match the `content_id` of an inline attachment
+    pub async fn validate_payload(
+        &self,
+        user_id: &str,
+        payload: &mut Value,
+    ) -> Result<(), NotiSrvError> {
+        let html = Self::html_body(payload).unwrap_or_default();
+        let referenced: HashSet<String> = email_options::referenced_content_ids(&html)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+
+        let Some(attachments) = payload
+            .get_mut("optionals")
+            .and_then(|optionals| optionals.get_mut("attachments"))
+        else {
+            return Self::check_references(&referenced, &HashSet::new());
+        };
+
+        let parsed = serde_json::from_value::<Vec<PayloadAttachment>>(attachments.clone())
+            .map_err(|e| {
+                NotiSrvError::InvalidDataField(format!("Invalid attachments: {}", e).into())
+            })?;
+
+        let mut total_size = 0;
+        let mut content_ids = HashSet::new();
+        let mut checked = Vec::with_capacity(parsed.len());
+        for attachment in parsed {
+            let (attachment, size) = self.validate(user_id, attachment).await?;
+            if let Some(content_id) = &attachment.content_id {
+                if !content_ids.insert(content_id.clone()) {
+                    return Err(NotiSrvError::InvalidDataField(
+                        format!("Duplicate content id '{}'", content_id).into(),
+                    ));
+                }
+            }
+            total_size += size;
+            checked.push(attachment);
+        }
+        Self::check_references(&referenced, &content_ids)?;
+
+        if total_size > self.policy.max_total_size {
+            return Err(NotiSrvError::InvalidDataField(
+                format!(
+                    "Attachments exceed {} bytes in total",
+                    self.policy.max_total_size
+                )
+                .into(),
+            ));
+        }
+
+        *attachments = serde_json::json!(checked);
+        Ok(())
+    }
+
+    /// Checks one attachment, returning it normalized along with its size
+    async fn validate(
+        &self,
+        user_id: &str,
+        mut attachment: PayloadAttachment,
+    ) -> Result<(PayloadAttachment, usize), NotiSrvError> {
+        let disposition = attachment
+            .disposition
+            .get_or_insert_with(|| "attachment".to_string());
+        if disposition != "attachment" && disposition != "inline" {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid attachment disposition '{}'", disposition).into(),
+            ));
+        }
+
+        // Inline attachments are displayed in the HTML body through their content id
+        match (disposition.as_str(), &attachment.content_id) {
+            ("inline", Some(content_id)) => email_options::check_content_id(content_id)
+                .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?,
+            ("inline", None) => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "An inline attachment requires a 'content_id'".into(),
+                ))
+            }
+            (_, Some(_)) => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Only inline attachments have a 'content_id'".into(),
+                ))
+            }
+            (_, None) => {}
+        }
+
+        match (&attachment.id, &attachment.content) {
+            (Some(id), None) => {
+                let Some(upload) = self.find(user_id, id).await? else {
+                    return Err(NotiSrvError::InvalidDataField(
+                        format!("Attachment '{}' not found", id).into(),
+                    ));
+                };
+                // An uploaded file may be sent under another name, checked as an upload
+                if let Some(filename) = &attachment.filename {
+                    self.policy
+                        .check_filename(filename)
+                        .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+                }
+                attachment.id = Some(upload.id);
+                attachment.filename.get_or_insert(upload.filename);
+                attachment.r#type = Some(upload.content_type);
+                Ok((attachment, upload.size as usize))
+            }
+            (None, Some(content)) => {
+                let filename = attachment.filename.as_deref().unwrap_or_default();
+                let bytes = STANDARD.decode(content).map_err(|_| {
+                    NotiSrvError::InvalidDataField(
+                        format!("Attachment '{}' is not valid base64", filename).into(),
+                    )
+                })?;
+                let declared_type = attachment.r#type.as_deref().unwrap_or_default();
+
+                let content_type = self
+                    .policy
+                    .check(filename, declared_type, &bytes)
+                    .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+                attachment.r#type = Some(content_type);
+                Ok((attachment, bytes.len()))
+            }
+            _ => Err(NotiSrvError::InvalidDataField(
+                "An attachment has either an 'id' or a 'content'".into(),
+            )),
+        }
+    }
+
+    /// Returns the HTML body of an email payload, given as `html` or as an HTML `content`
+    fn html_body(payload: &Value) -> Option<String> {
+        if let Some(html) = payload.get("html").and_then(Value::as_str) {
+            return Some(html.to_string());
+        }
+        let is_html = payload
+            .get("content_type")
+            .and_then(Value::as_str)
+            .is_some_and(email_options::is_html);
+        payload
+            .get("content")
+            .and_then(Value::as_str)
+            .filter(|_| is_html)
+            .map(str::to_string)
+    }
+
+    fn check_references(
+        referenced: &HashSet<String>,
+        content_ids: &HashSet<String>,
+    ) -> Result<(), NotiSrvError> {
+        match referenced.difference(content_ids).next() {
+            Some(content_id) => Err(NotiSrvError::InvalidDataField(
+                format!("No inline attachment has the content id '{}'", content_id).into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
@@ -0,0 +1,314 @@
+use std::{env, sync::Arc};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use p256::{
+    ecdsa::{signature::Verifier, DerSignature, VerifyingKey},
+    pkcs8::DecodePublicKey,
+};
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::{
+        email_event::{ProviderEvent, SendGridEvent},
+        notification::NotificationStatus,
+    },
+    repository::{event_repository::EventRepo, suppression_repository::SuppressionRepo},
+    services::suppression_service::SuppressionService,
+};
+
+// How far the signed timestamp may be from now, older requests may be replayed
+const SIGNATURE_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// `EventService` ingests the delivery events posted by the email providers
+///
+/// Events are matched to notifications with the message id stored when the provider
+/// accepted the email, then recorded and applied to the status of their recipient. The
+/// notification status follows its recipients. Hard bounces and spam complaints add the
+/// recipient to the suppression list of the user
+pub struct EventService {
+    event_repo: Arc<EventRepo>,
+    suppression_repo: Arc<SuppressionRepo>,
+    // Key verifying the SendGrid signed event webhook, set with `SENDGRID_WEBHOOK_PUBLIC_KEY`
+    sendgrid_key: Option<VerifyingKey>,
+}
+
+impl EventService {
+    pub fn new(event_repo: Arc<EventRepo>, suppression_repo: Arc<SuppressionRepo>) -> Self {
+        // The key is shown base64 encoded (DER) in the SendGrid webhook settings
+        let sendgrid_key = env::var("SENDGRID_WEBHOOK_PUBLIC_KEY").ok().map(|key| {
+            let der = STANDARD
+                .decode(key.trim())
+                .expect("SENDGRID_WEBHOOK_PUBLIC_KEY must be base64 encoded");
+            VerifyingKey::from_public_key_der(&der).expect("Invalid SENDGRID_WEBHOOK_PUBLIC_KEY")
+        });
+        if sendgrid_key.is_none() {
+            warn!("SENDGRID_WEBHOOK_PUBLIC_KEY is not set, SendGrid events will be rejected");
+        }
+
+        Self {
+            event_repo,
+            suppression_repo,
+            sendgrid_key,
+        }
+    }
+
+    /// Checks the ECDSA signature SendGrid computes over the timestamp and the raw body
+    pub fn verify_sendgrid(
+        &self,
+        signature: &str,
+        timestamp: &str,
+        body: &[u8],
+    ) -> Result<(), NotiSrvError> {
+        let key = self
+            .sendgrid_key
+            .as_ref()
+            .ok_or(NotiSrvError::InvalidSignature)?;
+
+        verify_signature(key, signature, timestamp, body, Utc::now())
+    }
+
+    /// Records the SendGrid events and updates the status of their notifications
+    ///
+    /// Events about unknown messages (e.g. sent outside of this service) are ignored
+    pub async fn ingest_sendgrid(&self, events: Vec<SendGridEvent>) -> Result<(), NotiSrvError> {
+        for event in events {
+            let Some(message_id) = event.message_id() else {
+                continue;
+            };
+
+            let notification = self
+                .event_repo
+                .find_notification("sendgrid", message_id)
+                .await
+                .map_err(|e| {
+                    error!("Database select error: {}", e);
+                    NotiSrvError::DatabaseError(e)
+                })?;
+            let Some(notification) = notification else {
+                info!("No notification for SendGrid message {}", message_id);
+                continue;
+            };
+
+            let occurred_at = DateTime::from_timestamp(event.timestamp, 0)
+                .unwrap_or_default()
+                .naive_utc();
+
+            let inserted = self
+                .event_repo
+                .insert_event(&ProviderEvent {
+                    notification_id: notification.id,
+                    provider: "sendgrid".to_string(),
+                    provider_event_id: event.sg_event_id.clone(),
+                    event: event.event.clone(),
+                    detail: event.detail(),
+                    occurred_at,
+                })
+                .await
+                .map_err(|e| {
+                    error!("Database insert error: {}", e);
+                    NotiSrvError::DatabaseError(e)
+                })?;
+
+            // SendGrid retries deliveries, an event already stored was already applied
+            if inserted == 0 {
+                continue;
+            }
+
+            // Emails with several recipients report an event per recipient
+            let recipient = SuppressionService::normalize(
+                event.email.as_ref().unwrap_or(&notification.recipient),
+            );
+
+            if let Some(status) = event.status() {
+                self.event_repo
+                    .update_recipient_status(&notification.id, &recipient, status)
+                    .await
+                    .map_err(|e| {
+                        error!("Database update error: {}", e);
+                        NotiSrvError::DatabaseError(e)
+                    })?;
+                let statuses = self
+                    .event_repo
+                    .find_recipient_statuses(&notification.id)
+                    .await
+                    .map_err(|e| {
+                        error!("Database select error: {}", e);
+                        NotiSrvError::DatabaseError(e)
+                    })?;
+
+                // Notifications sent before their recipients were recorded have only one
+                let status = if statuses.is_empty() {
+                    Some(status)
+                } else {
+                    Self::notification_status(&statuses)
+                };
+                if let Some(status) = status {
+                    self.event_repo
+                        .update_status(&notification.id, status)
+                        .await
+                        .map_err(|e| {
+                            error!("Database update error: {}", e);
+                            NotiSrvError::DatabaseError(e)
+                        })?;
+                }
+            }
+
+            if let Some(reason) = event.suppression_reason() {
+                info!("Suppressing {} ({})", recipient, reason);
+                self.suppression_repo
+                    .upsert(
+                        &notification.user_id,
+                        &recipient,
+                        reason,
+                        event.detail().as_deref(),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Database upsert error: {}", e);
+                        NotiSrvError::DatabaseError(e)
+                    })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Status of a notification after the events of its recipients
+    ///
+    /// A notification is delivered once one of its recipients got it, and bounced only when
+    /// every recipient bounced or failed
+    fn notification_status(statuses: &[String]) -> Option<NotificationStatus> {
+        let delivered = NotificationStatus::Delivered.to_string();
+        let bounced = NotificationStatus::Bounced.to_string();
+        let failed = NotificationStatus::Failed.to_string();
+
+        if statuses.contains(&delivered) {
+            Some(NotificationStatus::Delivered)
+        } else if statuses.contains(&bounced)
+            && statuses
+                .iter()
+                .all(|status| *status == bounced || *status == failed)
+        {
+            Some(NotificationStatus::Bounced)
+        } else {
+            None
+        }
+    }
+}
+
+/// Verifies a SendGrid signature, the signed timestamp (in seconds) must be recent
+fn verify_signature(
+    key: &VerifyingKey,
+    signature: &str,
+    timestamp: &str,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Result<(), NotiSrvError> {
+    let signed_at: i64 = timestamp
+        .trim()
+        .parse()
+        .map_err(|_| NotiSrvError::InvalidSignature)?;
+    if (now.timestamp() - signed_at).abs() > SIGNATURE_TOLERANCE_SECS {
+        warn!("Stale SendGrid webhook timestamp: {}", timestamp);
+        return Err(NotiSrvError::InvalidSignature);
+    }
+
+    let signature = STANDARD
+        .decode(signature.trim())
+        .ok()
+        .and_then(|signature| DerSignature::from_bytes(&signature).ok())
+        .ok_or(NotiSrvError::InvalidSignature)?;
+
+    let mut payload = timestamp.as_bytes().to_vec();
+    payload.extend_from_slice(body);
+
+    key.verify(&payload, &signature).map_err(|_| {
+        warn!("Invalid SendGrid webhook signature");
+        NotiSrvError::InvalidSignature
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{signature::Signer, SigningKey};
+
+    use super::*;
+
+    const TIMESTAMP: &str = "1700000000";
+    const BODY: &[u8] = br#"[{"email":"john@example.com","event":"delivered"}]"#;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_slice(&[7u8; 32]).unwrap()
+    }
+
+    fn sign(key: &SigningKey, timestamp: &str, body: &[u8]) -> String {
+        let mut payload = timestamp.as_bytes().to_vec();
+        payload.extend_from_slice(body);
+        let signature: DerSignature = key.sign(&payload);
+        STANDARD.encode(signature.as_bytes())
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn accepts_a_fresh_signed_payload() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        let now = at(1_700_000_060);
+
+        assert!(verify_signature(key.verifying_key(), &signature, TIMESTAMP, BODY, now).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_tampered_body() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        let now = at(1_700_000_000);
+
+        assert!(verify_signature(key.verifying_key(), &signature, TIMESTAMP, b"[]", now).is_err());
+    }
+
+    #[test]
+    fn rejects_another_key() {
+        let signature = sign(
+            &SigningKey::from_slice(&[9u8; 32]).unwrap(),
+            TIMESTAMP,
+            BODY,
+        );
+        let now = at(1_700_000_000);
+
+        assert!(verify_signature(
+            signing_key().verifying_key(),
+            &signature,
+            TIMESTAMP,
+            BODY,
+            now
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_a_stale_timestamp() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        let verify = |now| verify_signature(key.verifying_key(), &signature, TIMESTAMP, BODY, now);
+
+        assert!(verify(at(1_700_000_000 + SIGNATURE_TOLERANCE_SECS)).is_ok());
+        assert!(verify(at(1_700_000_001 + SIGNATURE_TOLERANCE_SECS)).is_err());
+        assert!(verify(at(1_699_999_999 - SIGNATURE_TOLERANCE_SECS)).is_err());
+    }
+
+    #[test]
+    fn rejects_a_malformed_timestamp() {
+        let key = signing_key();
+        let signature = sign(&key, "yesterday", BODY);
+
+        assert!(
+            verify_signature(key.verifying_key(), &signature, "yesterday", BODY, at(0)).is_err()
+        );
+    }
+}
@@ -1 +1,9 @@
+pub mod attachment_service;
+pub mod event_service;
 pub mod notification_service;
+pub mod preference_service;
+pub mod sender_service;
+pub mod suppression_service;
+pub mod template_service;
+pub mod tracking_service;
+pub mod webpush_service;
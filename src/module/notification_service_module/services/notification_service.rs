@@ -1,36 +1,121 @@
-use std::{env, sync::Arc};
+use std::{collections::HashSet, env, sync::Arc};
 
 use log::error;
 use serde_json::Value;
+use uuid::Uuid;
 
 use crate::module::notification_service_module::{
     errors::NotiSrvError,
     models::{
+        email_recipient::{
+            EmailRecipient, EmailRecipients, NotificationRecipient, RecipientKind, RecipientStatus,
+        },
         notification::{
             NotificationChannel, NotificationEnQueue, NotificationRequest, NotificationResponse,
+            NotificationStatus, PushProvider, PushRecipientType,
         },
-        payload::{EmailPayload, Payload, PushPayload},
+        payload::{EmailPayload, Payload, PushPayload, WebPushPayload},
+        sender_identity::SenderIdentity,
+        template::TemplateTestRequest,
+        timeline::{NotificationTimeline, TimelineEvent},
+    },
+    repository::{
+        notification_repository::NotificationRepo, redis_repository::RedisRepository,
+        suppression_repository::SuppressionRepo, webpush_repository::WebPushRepo,
+    },
+    services::{
+        attachment_service::AttachmentService, preference_service::PreferenceService,
+        sender_service::SenderService, suppression_service::SuppressionService,
+        template_service::TemplateService, tracking_service::TrackingService,
     },
-    repository::{notification_repository::NotificationRepo, redis_repository::RedisRepository},
+    utils::{condition_parser::validate_condition, email_options},
 };
 
+// Recipients of an email across `to`, `cc` and `bcc`
+const MAX_EMAIL_RECIPIENTS: usize = 50;
+
+// Substitution filled by the gateway with the unsubscribe link of the recipient
+const UNSUBSCRIBE_URL_KEY: &str = "unsubscribe_url";
+
 pub struct NotificationService {
     noti_repo: Arc<NotificationRepo>,
     redis_repo: Arc<RedisRepository>,
+    webpush_repo: Arc<WebPushRepo>,
+    suppression_repo: Arc<SuppressionRepo>,
+    preference_service: Arc<PreferenceService>,
+    tracking_service: Arc<TrackingService>,
+    attachment_service: Arc<AttachmentService>,
+    sender_service: Arc<SenderService>,
+    template_service: Arc<TemplateService>,
 }
 
 impl NotificationService {
-    pub fn new(noti_repo: Arc<NotificationRepo>, redis_repo: Arc<RedisRepository>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        noti_repo: Arc<NotificationRepo>,
+        redis_repo: Arc<RedisRepository>,
+        webpush_repo: Arc<WebPushRepo>,
+        suppression_repo: Arc<SuppressionRepo>,
+        preference_service: Arc<PreferenceService>,
+        tracking_service: Arc<TrackingService>,
+        attachment_service: Arc<AttachmentService>,
+        sender_service: Arc<SenderService>,
+        template_service: Arc<TemplateService>,
+    ) -> Self {
         Self {
             noti_repo,
             redis_repo,
+            webpush_repo,
+            suppression_repo,
+            preference_service,
+            tracking_service,
+            attachment_service,
+            sender_service,
+            template_service,
         }
     }
 
     pub async fn send(
         &self,
-        notification_request: NotificationRequest,
+        mut notification_request: NotificationRequest,
     ) -> Result<NotificationResponse, NotiSrvError> {
+        // Render the template first, it provides fields of the payload. Test sends come
+        // already rendered
+        let template = notification_request
+            .template_id
+            .clone()
+            .filter(|_| notification_request.template_version.is_none());
+        if let Some(template) = template {
+            // Keys every `to` recipient substitutes are filled by the email worker
+            let substituted: Vec<String> = match notification_request.to.as_deref() {
+                Some([first, others @ ..]) => first
+                    .substitutions
+                    .keys()
+                    .filter(|key| {
+                        others
+                            .iter()
+                            .all(|recipient| recipient.substitutions.contains_key(*key))
+                    })
+                    .cloned()
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let rendered = self
+                .template_service
+                .render(
+                    &notification_request.user_id,
+                    &notification_request.channel.to_string(),
+                    &template,
+                    notification_request.locale.as_deref(),
+                    &substituted,
+                    &mut notification_request.payload,
+                )
+                .await?;
+            notification_request.template_id = Some(rendered.template_id);
+            notification_request.template_version = Some(rendered.version);
+            notification_request.template_locale = rendered.locale;
+        }
+
         // Validate payload
         if !self.validate_payload(&notification_request.channel, &notification_request.payload) {
             return Err(NotiSrvError::InvalidDataField(
@@ -46,48 +131,179 @@ impl NotificationService {
         let recipient_type = notification_request
             .recipient_type
             .clone()
-            .and_then(|value| Some(value.to_string()));
+            .map(|value| value.to_string());
 
         match notification_request.channel {
-            NotificationChannel::Push => {
-                if let None = recipient_type {
-                    return Err(NotiSrvError::InvalidDataField(
-                        "Missing required field 'recipient_type'".to_string().into(),
-                    ));
-                };
-            }
-            NotificationChannel::Email => {
-                if notification_request
-                    .sender
-                    .clone()
-                    .map_or(true, |value| value.is_empty())
-                {
-                    return Err(NotiSrvError::InvalidDataField(
-                        "Missing required field 'sender'".to_string().into(),
-                    ));
-                }
+            NotificationChannel::Push if recipient_type.is_none() => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Missing required field 'recipient_type'".to_string().into(),
+                ));
             }
             _ => (),
         }
 
+        // A push provider can only be chosen for push notifications, and APNs only
+        // delivers to device tokens when its worker is configured
+        match (
+            &notification_request.channel,
+            &notification_request.provider,
+        ) {
+            (NotificationChannel::Push, Some(PushProvider::Apns))
+                if env::var("APNS_KEY_PATH").is_err() =>
+            {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Provider 'apns' is not configured".to_string().into(),
+                ));
+            }
+            (NotificationChannel::Push, Some(PushProvider::Apns))
+                if notification_request.recipient_type != Some(PushRecipientType::Token) =>
+            {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Provider 'apns' only supports recipient_type 'token'"
+                        .to_string()
+                        .into(),
+                ));
+            }
+            (NotificationChannel::Push, _) | (_, None) => (),
+            (_, Some(_)) => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Field 'provider' is only supported for push channel"
+                        .to_string()
+                        .into(),
+                ));
+            }
+        }
+
+        let has_email_recipients = notification_request.to.is_some()
+            || notification_request.cc.is_some()
+            || notification_request.bcc.is_some();
+        if has_email_recipients
+            && !matches!(notification_request.channel, NotificationChannel::Email)
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                "Fields 'to', 'cc' and 'bcc' are only supported for email channel".into(),
+            ));
+        }
+        if notification_request.recipient.is_empty()
+            && !matches!(notification_request.channel, NotificationChannel::Email)
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                "Missing required field 'recipient'".into(),
+            ));
+        }
+
+        if notification_request.tracking.is_some()
+            && !matches!(notification_request.channel, NotificationChannel::Email)
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'tracking' is only supported for email channel".into(),
+            ));
+        }
+
+        // Reject malformed topic conditions before they reach FCM
+        if let Some(PushRecipientType::Condition) = notification_request.recipient_type {
+            validate_condition(&notification_request.recipient).map_err(|e| {
+                error!(
+                    "Invalid condition '{}': {}",
+                    notification_request.recipient, e
+                );
+                NotiSrvError::InvalidCondition(e)
+            })?;
+        }
+
+        // Web push recipients are the ids of subscriptions registered by the same user
+        if let NotificationChannel::WebPush = notification_request.channel {
+            self.validate_webpush_recipient(&notification_request)
+                .await?;
+        }
+
+        let user_id = Uuid::parse_str(&notification_request.user_id)
+            .map_err(|_| NotiSrvError::InvalidDataField("Invalid 'user_id'".into()))?;
+
+        let mut recipients = Vec::new();
+        if let NotificationChannel::Email = notification_request.channel {
+            // Reject broken attachments now rather than when the email is sent
+            self.attachment_service
+                .validate_payload(
+                    &notification_request.user_id,
+                    &mut notification_request.payload,
+                )
+                .await?;
+            Self::validate_email_optionals(&notification_request.payload)?;
+
+            // The sender must be an identity verified by the user
+            let (sender, identity) = self
+                .sender_service
+                .resolve(&user_id, notification_request.sender.as_deref())
+                .await?;
+            notification_request.sender = Some(sender);
+            Self::apply_default_reply_to(&mut notification_request.payload, &identity);
+
+            recipients = self
+                .email_recipients(&mut notification_request, &user_id)
+                .await?;
+
+            // Emails whose every `to` recipient is suppressed or opted out are recorded but
+            // never sent
+            if let Some(status) = Self::unsent_status(&recipients) {
+                let response = self.record_unsent(&notification_request, status).await?;
+                self.insert_recipients(&response.id, &recipients).await?;
+                return Ok(response);
+            }
+        } else if self
+            .preference_service
+            .is_opted_out(
+                &user_id,
+                &notification_request.recipient,
+                &notification_request.channel.to_string(),
+                notification_request.category.as_deref(),
+            )
+            .await?
+        {
+            // So are notifications the recipient opted out of
+            return self
+                .record_unsent(&notification_request, NotificationStatus::Skipped)
+                .await;
+        }
+
         // Save notification into database
         let noti_id = self
             .noti_repo
-            .insert(&notification_request)
+            .insert(&notification_request, NotificationStatus::Queued)
             .await
             .map_err(|e| {
                 error!("Database insert error: {}", e.to_string());
                 NotiSrvError::DatabaseError(e)
             })?;
 
+        let recipients = match notification_request.channel {
+            NotificationChannel::Email => {
+                self.insert_recipients(&noti_id, &recipients).await?;
+                Some(self.queued_recipients(&notification_request, recipients))
+            }
+            _ => None,
+        };
+
+        // Tracking links carry the notification id, so they are added once it is saved
+        let mut payload = notification_request.payload;
+        if let Some(tracking) = &notification_request.tracking {
+            self.tracking_service
+                .instrument(&noti_id, &mut payload, tracking);
+        }
+
         // Generate value
         let enqueue_value = NotificationEnQueue {
             notification_id: noti_id.clone(),
+            user_id: notification_request.user_id,
             recipient: notification_request.recipient,
             recipient_type,
             channel: notification_request.channel.to_string(),
+            provider: notification_request.provider.map(|value| value.to_string()),
+            category: notification_request.category,
+            recipients,
             template_id: notification_request.template_id,
-            payload: notification_request.payload,
+            template_locale: notification_request.template_locale,
+            payload,
             sender: notification_request.sender,
         };
 
@@ -99,8 +315,7 @@ impl NotificationService {
         })?;
 
         // Push job into redis queue
-        let _push_result = self
-            .redis_repo
+        self.redis_repo
             .push_to_queue(&queue_key, &job.to_string())
             .await
             .map_err(|e| {
@@ -110,17 +325,452 @@ impl NotificationService {
 
         let response = NotificationResponse {
             id: noti_id,
-            status: "queued".to_string(),
+            status: NotificationStatus::Queued.to_string(),
         };
 
         Ok(response)
     }
 
+    /// Sends a version of a template, drafts included, rendered with sample variables to an
+    /// allow-listed address
+    ///
+    /// Test emails go through the regular delivery, their subject is prefixed with `[Test]`.
+    /// `TEST_SEND_RECIPIENTS` lists the addresses, or whole `@domain` entries, allowed
+    pub async fn send_test(
+        &self,
+        user_id: &str,
+        template_id: &str,
+        request: TemplateTestRequest,
+    ) -> Result<Option<NotificationResponse>, NotiSrvError> {
+        let recipient = request.recipient.trim().to_lowercase();
+        let allowed = env::var("TEST_SEND_RECIPIENTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|entry| entry.trim().to_lowercase())
+            .any(|entry| {
+                !entry.is_empty()
+                    && (entry == recipient
+                        || (entry.starts_with('@') && recipient.ends_with(entry.as_str())))
+            });
+        if !allowed {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Recipient '{}' is not allowed for test sends", recipient).into(),
+            ));
+        }
+
+        let Some(preview) = self
+            .template_service
+            .preview(user_id, template_id, &request.preview)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if preview.r#type.as_deref() != Some("email") {
+            return Err(NotiSrvError::InvalidDataField(
+                "Test sends are only supported for email templates".into(),
+            ));
+        }
+        // The preview is sent as is, check its variables as a rendered notification would be
+        if !preview.invalid_variables.is_empty() {
+            return Err(NotiSrvError::InvalidVariables(preview.invalid_variables));
+        }
+        if preview.strict {
+            TemplateService::check_missing(&preview.missing_variables)?;
+        }
+
+        let mut payload = preview.content;
+        if let Some(subject) = payload.get_mut("subject") {
+            if let Some(text) = subject.as_str() {
+                *subject = Value::String(format!("[Test] {}", text));
+            }
+        }
+
+        let mut notification_request =
+            serde_json::from_value::<NotificationRequest>(serde_json::json!({
+                "user_id": user_id,
+                "recipient": recipient,
+                "sender": request.sender,
+                "channel": "email",
+                "template_id": template_id,
+                "payload": payload,
+            }))
+            .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+        notification_request.template_version = Some(preview.version);
+        notification_request.template_locale = preview.locale;
+
+        self.send(notification_request).await.map(Some)
+    }
+
+    /// Returns the history of a notification: when it was queued, every delivery attempt and
+    /// the events reported by the provider
+    pub async fn timeline(&self, id: &str) -> Result<Option<NotificationTimeline>, NotiSrvError> {
+        let id = Uuid::parse_str(id)
+            .map_err(|_| NotiSrvError::InvalidDataField("Invalid notification id".into()))?;
+
+        let Some(notification) = self.noti_repo.find_by_id(&id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?
+        else {
+            return Ok(None);
+        };
+
+        let attempts = self.noti_repo.find_attempts(&id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        let provider_events = self.noti_repo.find_events(&id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        let recipients = self.noti_repo.find_recipients(&id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        let mut events = vec![TimelineEvent::Queued {
+            at: notification.created_at,
+        }];
+        events.extend(attempts.into_iter().map(TimelineEvent::Attempt));
+        events.extend(provider_events.into_iter().map(TimelineEvent::Provider));
+        // Stable sort, attempts recorded within the same instant keep their order
+        events.sort_by_key(TimelineEvent::at);
+
+        Ok(Some(NotificationTimeline {
+            id: notification.id.to_string(),
+            channel: notification.channel,
+            status: notification.status,
+            provider: notification.provider,
+            provider_message_id: notification.provider_message_id,
+            template_id: notification.template_id.map(|id| id.to_string()),
+            template_version: notification.template_version,
+            template_locale: notification.template_locale,
+            recipients,
+            events,
+        }))
+    }
+
     fn validate_payload(&self, channel: &NotificationChannel, payload: &Value) -> bool {
         match channel {
             NotificationChannel::Push => PushPayload::validate_payload(payload),
             NotificationChannel::Email => EmailPayload::validate_payload(payload),
+            NotificationChannel::WebPush => WebPushPayload::validate_payload(payload),
             _ => false,
         }
     }
+
+    /// Sets the reply-to address of the sender identity on emails without one
+    fn apply_default_reply_to(payload: &mut Value, identity: &SenderIdentity) {
+        let Some(email) = &identity.reply_to_email else {
+            return;
+        };
+        let name = identity
+            .reply_to_name
+            .as_ref()
+            .or(identity.display_name.as_ref())
+            .cloned()
+            .unwrap_or_default();
+
+        let Some(payload) = payload.as_object_mut() else {
+            return;
+        };
+        let optionals = payload
+            .entry("optionals")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(optionals) = optionals.as_object_mut() {
+            optionals
+                .entry("reply_to")
+                .or_insert_with(|| serde_json::json!({ "email": email, "name": name }));
+        }
+    }
+
+    /// Checks the custom headers and the tags an email payload passes to the provider
+    fn validate_email_optionals(payload: &Value) -> Result<(), NotiSrvError> {
+        let Some(optionals) = payload.get("optionals") else {
+            return Ok(());
+        };
+        if let Some(headers) = optionals.get("headers") {
+            email_options::check_headers(headers)
+                .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+        }
+        if let Some(tags) = optionals.get("tags") {
+            email_options::check_tags(tags)
+                .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+        }
+        Ok(())
+    }
+
+    /// Records a notification that will not be sent, with the reason as status
+    async fn record_unsent(
+        &self,
+        notification_request: &NotificationRequest,
+        status: NotificationStatus,
+    ) -> Result<NotificationResponse, NotiSrvError> {
+        let noti_id = self
+            .noti_repo
+            .insert(notification_request, status)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e.to_string());
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        Ok(NotificationResponse {
+            id: noti_id,
+            status: status.to_string(),
+        })
+    }
+
+    /// Validates the recipients of an email and finds the ones it must not be sent to
+    ///
+    /// Recipients are given either as `recipient` or as `to`, with optional `cc` and `bcc`.
+    /// The notification keeps the first `to` address as its recipient
+    async fn email_recipients(
+        &self,
+        notification_request: &mut NotificationRequest,
+        user_id: &Uuid,
+    ) -> Result<Vec<NotificationRecipient>, NotiSrvError> {
+        let to = match (
+            notification_request.to.take(),
+            notification_request.recipient.is_empty(),
+        ) {
+            (Some(to), true) => to,
+            (None, false) => vec![EmailRecipient::new(&notification_request.recipient)],
+            (Some(_), false) => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Fields 'recipient' and 'to' cannot be combined".into(),
+                ))
+            }
+            (None, true) => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Missing required field 'recipient' or 'to'".into(),
+                ))
+            }
+        };
+        if to.is_empty() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'to' must not be empty".into(),
+            ));
+        }
+
+        let cc = notification_request.cc.take().unwrap_or_default();
+        let bcc = notification_request.bcc.take().unwrap_or_default();
+        if to.len() + cc.len() + bcc.len() > MAX_EMAIL_RECIPIENTS {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("An email has at most {} recipients", MAX_EMAIL_RECIPIENTS).into(),
+            ));
+        }
+
+        let mut addresses = HashSet::new();
+        let mut recipients = Vec::new();
+        let all = (to
+            .into_iter()
+            .map(|recipient| (RecipientKind::To, recipient)))
+        .chain(
+            cc.into_iter()
+                .map(|recipient| (RecipientKind::Cc, recipient)),
+        )
+        .chain(
+            bcc.into_iter()
+                .map(|recipient| (RecipientKind::Bcc, recipient)),
+        );
+        for (kind, mut recipient) in all {
+            recipient.email = SuppressionService::normalize(&recipient.email);
+            Self::validate_email_recipient(kind, &recipient)?;
+            if !addresses.insert(recipient.email.clone()) {
+                return Err(NotiSrvError::InvalidDataField(
+                    format!("Duplicate recipient '{}'", recipient.email).into(),
+                ));
+            }
+
+            let status = if self.is_suppressed(user_id, &recipient.email).await? {
+                RecipientStatus::Suppressed
+            } else if self
+                .preference_service
+                .is_opted_out(
+                    user_id,
+                    &recipient.email,
+                    &NotificationChannel::Email.to_string(),
+                    notification_request.category.as_deref(),
+                )
+                .await?
+            {
+                RecipientStatus::Skipped
+            } else {
+                RecipientStatus::Queued
+            };
+
+            recipients.push(NotificationRecipient {
+                kind,
+                recipient,
+                status,
+            });
+        }
+
+        notification_request.recipient = recipients[0].recipient.email.clone();
+        Ok(recipients)
+    }
+
+    fn validate_email_recipient(
+        kind: RecipientKind,
+        recipient: &EmailRecipient,
+    ) -> Result<(), NotiSrvError> {
+        let valid_address = recipient
+            .email
+            .split_once('@')
+            .is_some_and(|(local, domain)| {
+                !local.is_empty()
+                    && domain.contains('.')
+                    && !domain.contains('@')
+                    && !recipient.email.contains(|c: char| {
+                        c.is_whitespace() || c.is_control() || "<>\",;".contains(c)
+                    })
+            });
+        if !valid_address {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid email address '{}'", recipient.email).into(),
+            ));
+        }
+
+        // Display names end up in headers
+        if recipient
+            .name
+            .as_ref()
+            .is_some_and(|name| name.chars().any(char::is_control))
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid name of '{}'", recipient.email).into(),
+            ));
+        }
+
+        if kind != RecipientKind::To && !recipient.substitutions.is_empty() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Substitutions are only supported for 'to' recipients".into(),
+            ));
+        }
+        let valid_keys = recipient.substitutions.keys().all(|key| {
+            !key.is_empty()
+                && key != UNSUBSCRIBE_URL_KEY
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        });
+        if !valid_keys {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid substitution key of '{}'", recipient.email).into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Status of an email that none of its `to` recipients can receive
+    fn unsent_status(recipients: &[NotificationRecipient]) -> Option<NotificationStatus> {
+        let mut to = recipients
+            .iter()
+            .filter(|recipient| recipient.kind == RecipientKind::To);
+        if to
+            .clone()
+            .any(|recipient| recipient.status == RecipientStatus::Queued)
+        {
+            return None;
+        }
+
+        if to.any(|recipient| recipient.status == RecipientStatus::Suppressed) {
+            Some(NotificationStatus::Suppressed)
+        } else {
+            Some(NotificationStatus::Skipped)
+        }
+    }
+
+    /// Recipients the email is queued for, with their unsubscribe links
+    fn queued_recipients(
+        &self,
+        notification_request: &NotificationRequest,
+        recipients: Vec<NotificationRecipient>,
+    ) -> EmailRecipients {
+        let mut queued = EmailRecipients::default();
+        for NotificationRecipient {
+            kind,
+            mut recipient,
+            status,
+        } in recipients
+        {
+            if status != RecipientStatus::Queued {
+                continue;
+            }
+            match kind {
+                RecipientKind::To => {
+                    recipient.unsubscribe_url = self.preference_service.unsubscribe_url(
+                        &notification_request.user_id,
+                        &recipient.email,
+                        notification_request.category.as_deref(),
+                    );
+                    queued.to.push(recipient);
+                }
+                RecipientKind::Cc => queued.cc.push(recipient),
+                RecipientKind::Bcc => queued.bcc.push(recipient),
+            }
+        }
+        queued
+    }
+
+    async fn insert_recipients(
+        &self,
+        noti_id: &str,
+        recipients: &[NotificationRecipient],
+    ) -> Result<(), NotiSrvError> {
+        self.noti_repo
+            .insert_recipients(noti_id, recipients)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    async fn is_suppressed(&self, user_id: &Uuid, email: &str) -> Result<bool, NotiSrvError> {
+        self.suppression_repo
+            .exists(user_id, email)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    async fn validate_webpush_recipient(
+        &self,
+        notification_request: &NotificationRequest,
+    ) -> Result<(), NotiSrvError> {
+        let (subscription_id, user_id) = match (
+            Uuid::parse_str(&notification_request.recipient),
+            Uuid::parse_str(&notification_request.user_id),
+        ) {
+            (Ok(subscription_id), Ok(user_id)) => (subscription_id, user_id),
+            _ => {
+                return Err(NotiSrvError::InvalidDataField(
+                    "Field 'recipient' must be a web push subscription id".into(),
+                ))
+            }
+        };
+
+        let exists = self
+            .webpush_repo
+            .exists(&subscription_id, &user_id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        if !exists {
+            return Err(NotiSrvError::InvalidDataField(
+                "Web push subscription not found".into(),
+            ));
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,399 @@
+use std::{collections::HashSet, env, sync::Arc};
+
+use log::{error, info};
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::preference::{Preference, PreferenceRequest, PreferenceResponse, ANY},
+    repository::preference_repository::PreferenceRepo,
+    utils::signed_token::{TokenSigner, UnsubscribeClaims},
+};
+
+// Channels a preference can target, besides `*`
+const CHANNELS: [&str; 4] = ["email", "push", "sms", "webpush"];
+
+/// `PreferenceService` manages the opt-outs of end-recipients per channel and category
+///
+/// Categories listed in `NON_SUPPRESSIBLE_CATEGORIES` (comma separated, `security` and
+/// `transactional` if unset) are always delivered, whatever the preferences. Emails of the
+/// other categories carry a signed one-click unsubscribe link
+pub struct PreferenceService {
+    preference_repo: Arc<PreferenceRepo>,
+    non_suppressible: HashSet<String>,
+    signer: Option<TokenSigner>,
+}
+
+impl PreferenceService {
+    pub fn new(preference_repo: Arc<PreferenceRepo>) -> Self {
+        let non_suppressible = Self::parse_categories(
+            &env::var("NON_SUPPRESSIBLE_CATEGORIES")
+                .unwrap_or_else(|_| "security,transactional".to_string()),
+        );
+
+        Self {
+            preference_repo,
+            non_suppressible,
+            signer: TokenSigner::from_env("UNSUBSCRIBE_SECRET"),
+        }
+    }
+
+    pub async fn get(
+        &self,
+        user_id: &str,
+        recipient: &str,
+    ) -> Result<PreferenceResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let recipient = Self::normalize_recipient(recipient);
+
+        let preferences = self.find(&user_id, &recipient).await?;
+
+        Ok(PreferenceResponse {
+            recipient,
+            preferences,
+        })
+    }
+
+    pub async fn update(
+        &self,
+        request: PreferenceRequest,
+    ) -> Result<PreferenceResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(&request.user_id, "user_id")?;
+        let recipient = Self::normalize_recipient(&request.recipient);
+        if recipient.is_empty() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'recipient' must not be empty".into(),
+            ));
+        }
+
+        let mut preferences = request.preferences;
+        for preference in preferences.iter_mut() {
+            preference.channel = preference.channel.trim().to_lowercase();
+            preference.category = preference.category.trim().to_lowercase();
+            Self::validate(preference)?;
+        }
+
+        self.preference_repo
+            .upsert_all(&user_id, &recipient, &preferences)
+            .await
+            .map_err(|e| {
+                error!("Database upsert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        self.get(&request.user_id, &recipient).await
+    }
+
+    /// Whether the recipient opted out of notifications of this channel and category
+    ///
+    /// The most specific matching preference decides, recipients without one are opted in
+    pub async fn is_opted_out(
+        &self,
+        user_id: &Uuid,
+        recipient: &str,
+        channel: &str,
+        category: Option<&str>,
+    ) -> Result<bool, NotiSrvError> {
+        let category = category.map(|category| category.trim().to_lowercase());
+        if self.is_non_suppressible(category.as_deref()) {
+            return Ok(false);
+        }
+
+        let preferences = self
+            .find(user_id, &Self::normalize_recipient(recipient))
+            .await?;
+
+        let opted_out = Self::resolve_opt_out(&preferences, channel, category.as_deref());
+
+        if opted_out {
+            info!("{} opted out of {} {:?}", recipient, channel, category);
+        }
+        Ok(opted_out)
+    }
+
+    /// Returns the one-click unsubscribe URL of an email, `None` for emails without a
+    /// category, non-suppressible categories or when unsubscribe links are not configured
+    ///
+    /// An email without a category may be transactional (e.g. a password reset), a link
+    /// opting out of every email must never be attached to it
+    pub fn unsubscribe_url(
+        &self,
+        user_id: &str,
+        recipient: &str,
+        category: Option<&str>,
+    ) -> Option<String> {
+        let category = category
+            .map(|category| category.trim().to_lowercase())
+            .filter(|category| !category.is_empty())?;
+        if self.is_non_suppressible(Some(&category)) {
+            return None;
+        }
+
+        self.signer.as_ref().map(|signer| {
+            signer.url(
+                "unsubscribe",
+                &UnsubscribeClaims {
+                    user_id: user_id.to_string(),
+                    recipient: Self::normalize_recipient(recipient),
+                    category: Some(category),
+                },
+            )
+        })
+    }
+
+    /// Returns the URL the unsubscribe confirmation page posts to, `None` if the token is
+    /// invalid
+    pub fn confirm_unsubscribe_url(&self, token: &str) -> Option<String> {
+        let signer = self.signer.as_ref()?;
+        signer.verify::<UnsubscribeClaims>(token)?;
+        Some(signer.link("unsubscribe", token))
+    }
+
+    /// Records the email opt-out carried by an unsubscribe token
+    ///
+    /// Returns `None` if the token is invalid
+    pub async fn unsubscribe(
+        &self,
+        token: &str,
+    ) -> Result<Option<UnsubscribeClaims>, NotiSrvError> {
+        let Some(claims) = self
+            .signer
+            .as_ref()
+            .and_then(|signer| signer.verify::<UnsubscribeClaims>(token))
+        else {
+            return Ok(None);
+        };
+        let user_id = Self::parse_uuid(&claims.user_id, "user_id")?;
+
+        let preference = Preference {
+            channel: "email".to_string(),
+            category: claims.category.clone().unwrap_or_else(|| ANY.to_string()),
+            enabled: false,
+            updated_at: None,
+        };
+        self.preference_repo
+            .upsert_all(&user_id, &claims.recipient, &[preference])
+            .await
+            .map_err(|e| {
+                error!("Database upsert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        info!(
+            "{} unsubscribed from {:?}",
+            claims.recipient, claims.category
+        );
+        Ok(Some(claims))
+    }
+
+    fn is_non_suppressible(&self, category: Option<&str>) -> bool {
+        Self::contains_category(&self.non_suppressible, category)
+    }
+
+    fn contains_category(categories: &HashSet<String>, category: Option<&str>) -> bool {
+        category.is_some_and(|category| categories.contains(category))
+    }
+
+    /// Categories of a comma separated list, lowercased
+    fn parse_categories(value: &str) -> HashSet<String> {
+        value
+            .split(',')
+            .map(|category| category.trim().to_lowercase())
+            .filter(|category| !category.is_empty())
+            .collect()
+    }
+
+    /// Whether the most specific of the matching preferences disables the notification
+    fn resolve_opt_out(preferences: &[Preference], channel: &str, category: Option<&str>) -> bool {
+        preferences
+            .iter()
+            .filter_map(|preference| {
+                preference
+                    .specificity(channel, category)
+                    .map(|score| (score, preference.enabled))
+            })
+            .max_by_key(|(score, _)| *score)
+            .is_some_and(|(_, enabled)| !enabled)
+    }
+
+    async fn find(&self, user_id: &Uuid, recipient: &str) -> Result<Vec<Preference>, NotiSrvError> {
+        self.preference_repo
+            .find(user_id, recipient)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    fn validate(preference: &Preference) -> Result<(), NotiSrvError> {
+        if preference.channel != ANY && !CHANNELS.contains(&preference.channel.as_str()) {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Unsupported channel '{}'", preference.channel).into(),
+            ));
+        }
+
+        let valid_category = preference.category == ANY
+            || (!preference.category.is_empty()
+                && preference
+                    .category
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+        if !valid_category {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid category '{}'", preference.category).into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Email addresses are compared case-insensitively, device tokens are kept as is
+    pub fn normalize_recipient(recipient: &str) -> String {
+        let recipient = recipient.trim();
+        if recipient.contains('@') {
+            recipient.to_lowercase()
+        } else {
+            recipient.to_string()
+        }
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preference(channel: &str, category: &str, enabled: bool) -> Preference {
+        Preference {
+            channel: channel.to_string(),
+            category: category.to_string(),
+            enabled,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn ranks_specificity() {
+        let exact = preference("email", "newsletter", true);
+        let channel_wide = preference("email", ANY, true);
+        let category_wide = preference(ANY, "newsletter", true);
+        let global = preference(ANY, ANY, true);
+
+        assert_eq!(exact.specificity("email", Some("newsletter")), Some(3));
+        assert_eq!(
+            channel_wide.specificity("email", Some("newsletter")),
+            Some(2)
+        );
+        assert_eq!(
+            category_wide.specificity("email", Some("newsletter")),
+            Some(1)
+        );
+        assert_eq!(global.specificity("email", Some("newsletter")), Some(0));
+    }
+
+    #[test]
+    fn skips_preferences_of_other_channels_and_categories() {
+        assert_eq!(
+            preference("push", ANY, false).specificity("email", Some("newsletter")),
+            None
+        );
+        assert_eq!(
+            preference("email", "billing", false).specificity("email", Some("newsletter")),
+            None
+        );
+        // A category preference never matches a notification without a category
+        assert_eq!(
+            preference(ANY, "newsletter", false).specificity("email", None),
+            None
+        );
+        assert_eq!(
+            preference("email", ANY, false).specificity("email", None),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn most_specific_preference_decides() {
+        let preferences = [
+            preference(ANY, ANY, false),
+            preference("email", ANY, true),
+            preference("email", "newsletter", false),
+        ];
+
+        assert!(PreferenceService::resolve_opt_out(
+            &preferences,
+            "email",
+            Some("newsletter")
+        ));
+        assert!(!PreferenceService::resolve_opt_out(
+            &preferences,
+            "email",
+            Some("billing")
+        ));
+        assert!(PreferenceService::resolve_opt_out(
+            &preferences,
+            "push",
+            Some("billing")
+        ));
+    }
+
+    #[test]
+    fn opted_in_without_preferences() {
+        assert!(!PreferenceService::resolve_opt_out(
+            &[],
+            "email",
+            Some("newsletter")
+        ));
+        assert!(!PreferenceService::resolve_opt_out(
+            &[preference("push", ANY, false)],
+            "email",
+            None
+        ));
+    }
+
+    #[test]
+    fn parses_non_suppressible_categories() {
+        let categories = PreferenceService::parse_categories(" Security, transactional,, ");
+
+        assert_eq!(categories.len(), 2);
+        assert!(PreferenceService::contains_category(
+            &categories,
+            Some("security")
+        ));
+        assert!(PreferenceService::contains_category(
+            &categories,
+            Some("transactional")
+        ));
+        assert!(!PreferenceService::contains_category(
+            &categories,
+            Some("newsletter")
+        ));
+        assert!(!PreferenceService::contains_category(&categories, None));
+    }
+
+    #[test]
+    fn validates_channel_and_category() {
+        assert!(PreferenceService::validate(&preference("email", "newsletter", false)).is_ok());
+        assert!(PreferenceService::validate(&preference(ANY, ANY, false)).is_ok());
+        assert!(PreferenceService::validate(&preference("fax", ANY, false)).is_err());
+        assert!(PreferenceService::validate(&preference("email", "", false)).is_err());
+        assert!(PreferenceService::validate(&preference("email", "news letter", false)).is_err());
+    }
+
+    #[test]
+    fn normalizes_email_recipients_only() {
+        assert_eq!(
+            PreferenceService::normalize_recipient(" John@Example.com "),
+            "john@example.com"
+        );
+        assert_eq!(
+            PreferenceService::normalize_recipient("fcm:AbC123"),
+            "fcm:AbC123"
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::{env, sync::Arc};
+
+use log::{error, info, warn};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::{
+        notification::{NotificationEnQueue, NotificationRequest, NotificationStatus},
+        sender_identity::{
+            DnsRecord, SenderIdentity, SenderIdentityKind, SenderIdentityRequest,
+            SenderIdentityResponse,
+        },
+    },
+    repository::{
+        notification_repository::NotificationRepo, redis_repository::RedisRepository,
+        sender_repository::SenderRepo,
+    },
+    utils::signed_token::{SenderVerificationClaims, TokenSigner},
+};
+
+// Prefix of the TXT record, and of its value, proving the ownership of a domain
+const DNS_RECORD_PREFIX: &str = "_notification-gateway";
+const DNS_VALUE_PREFIX: &str = "notification-gateway-verification=";
+
+/// `SenderService` manages the identities users send emails from
+///
+/// An email identity is verified through a signed link emailed to the address, a domain
+/// identity through a TXT record published on the domain. Configured with:
+/// - `SENDER_VERIFICATION_SECRET`: key signing the verification links
+/// - `SENDER_VERIFICATION_FROM`: sender of the verification emails
+/// - `DNS_OVER_HTTPS_URL`: JSON DNS-over-HTTPS resolver looking up the TXT records,
+///   Cloudflare if unset
+pub struct SenderService {
+    sender_repo: Arc<SenderRepo>,
+    noti_repo: Arc<NotificationRepo>,
+    redis_repo: Arc<RedisRepository>,
+    signer: Option<TokenSigner>,
+    verification_from: Option<String>,
+    client: reqwest::Client,
+    dns_url: String,
+}
+
+impl SenderService {
+    pub fn new(
+        sender_repo: Arc<SenderRepo>,
+        noti_repo: Arc<NotificationRepo>,
+        redis_repo: Arc<RedisRepository>,
+    ) -> Self {
+        let verification_from = env::var("SENDER_VERIFICATION_FROM").ok();
+        if verification_from.is_none() {
+            warn!("SENDER_VERIFICATION_FROM not set, email identities cannot be verified");
+        }
+
+        Self {
+            sender_repo,
+            noti_repo,
+            redis_repo,
+            signer: TokenSigner::from_env("SENDER_VERIFICATION_SECRET"),
+            verification_from,
+            client: reqwest::Client::new(),
+            dns_url: env::var("DNS_OVER_HTTPS_URL")
+                .unwrap_or_else(|_| "https://cloudflare-dns.com/dns-query".to_string()),
+        }
+    }
+
+    /// Registers an unverified identity and starts its verification
+    pub async fn create(
+        &self,
+        request: SenderIdentityRequest,
+    ) -> Result<SenderIdentityResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(&request.user_id, "user_id")?;
+        let identity = request.identity.trim().to_lowercase();
+
+        let kind = if identity.contains('@') {
+            SenderIdentityKind::Email
+        } else {
+            SenderIdentityKind::Domain
+        };
+        let valid_identity = match kind {
+            SenderIdentityKind::Email => Self::is_valid_email(&identity),
+            SenderIdentityKind::Domain => Self::is_valid_domain(&identity),
+        };
+        if !valid_identity {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid sender identity '{}'", request.identity).into(),
+            ));
+        }
+        if request
+            .reply_to_email
+            .as_deref()
+            .is_some_and(|email| !Self::is_valid_email(email))
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                "Invalid 'reply_to_email'".into(),
+            ));
+        }
+        // Names end up in headers
+        let names = [&request.display_name, &request.reply_to_name];
+        if names.iter().any(|name| {
+            name.as_ref()
+                .is_some_and(|name| name.chars().any(char::is_control))
+        }) {
+            return Err(NotiSrvError::InvalidDataField(
+                "Invalid sender display name".into(),
+            ));
+        }
+        // The default sender is used without checks, it must be verified first
+        if request.is_default {
+            return Err(NotiSrvError::InvalidDataField(
+                "Only a verified identity can be the default sender".into(),
+            ));
+        }
+
+        let verification_token = hex::encode(rand::random::<[u8; 16]>());
+        let sender = self
+            .sender_repo
+            .insert(&user_id, kind, &identity, &request, &verification_token)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        if kind == SenderIdentityKind::Email {
+            self.send_verification_email(&request.user_id, &sender)
+                .await?;
+        }
+
+        info!("Sender identity {} registered", sender.id);
+        Ok(Self::response(sender))
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<SenderIdentityResponse>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        let senders = self.sender_repo.find_all(&user_id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        Ok(senders.into_iter().map(Self::response).collect())
+    }
+
+    /// Checks the TXT record of a domain identity, or sends the verification link of an
+    /// email identity again
+    pub async fn verify(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<SenderIdentityResponse>, NotiSrvError> {
+        let parsed_user_id = Self::parse_uuid(user_id, "user_id")?;
+        let parsed_id = Self::parse_uuid(id, "id")?;
+
+        let Some(sender) = self.find(&parsed_user_id, &parsed_id).await? else {
+            return Ok(None);
+        };
+        if sender.verified_at.is_some() {
+            return Ok(Some(Self::response(sender)));
+        }
+
+        if sender.kind == SenderIdentityKind::Email.to_string() {
+            self.send_verification_email(user_id, &sender).await?;
+            return Ok(Some(Self::response(sender)));
+        }
+
+        let record = Self::dns_record(&sender);
+        if !self.has_txt_record(&record).await {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("TXT record '{}' not found on {}", record.value, record.name).into(),
+            ));
+        }
+        self.mark_verified(&parsed_id, &sender.verification_token)
+            .await?;
+
+        Ok(self
+            .find(&parsed_user_id, &parsed_id)
+            .await?
+            .map(Self::response))
+    }
+
+    /// Verifies an email identity from the link of its verification email, returns `None`
+    /// when the link is invalid or the identity is already verified or deleted
+    pub async fn confirm(&self, token: &str) -> Result<Option<()>, NotiSrvError> {
+        let Some(claims) = self
+            .signer
+            .as_ref()
+            .and_then(|signer| signer.verify::<SenderVerificationClaims>(token))
+        else {
+            return Ok(None);
+        };
+        let Ok(id) = Uuid::parse_str(&claims.identity_id) else {
+            return Ok(None);
+        };
+
+        if self.mark_verified(&id, &claims.verification_token).await? == 0 {
+            return Ok(None);
+        }
+        info!("Sender identity {} verified", id);
+        Ok(Some(()))
+    }
+
+    /// Makes a verified email identity the default sender of the user
+    pub async fn set_default(&self, user_id: &str, id: &str) -> Result<u64, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let id = Self::parse_uuid(id, "id")?;
+
+        let Some(sender) = self.find(&user_id, &id).await? else {
+            return Ok(0);
+        };
+        if sender.verified_at.is_none() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Only a verified identity can be the default sender".into(),
+            ));
+        }
+
+        self.sender_repo
+            .set_default(&user_id, &id)
+            .await
+            .map_err(|e| {
+                error!("Database update error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    pub async fn delete(&self, user_id: &str, id: &str) -> Result<u64, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let id = Self::parse_uuid(id, "id")?;
+
+        self.sender_repo.delete(&user_id, &id).await.map_err(|e| {
+            error!("Database delete error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })
+    }
+
+    /// Returns the `From` of an email and the identity allowing it
+    ///
+    /// The sender must be covered by a verified identity of the user, the default sender of
+    /// the user is used when it is omitted. The display name of the identity is used when the
+    /// sender has none
+    pub async fn resolve(
+        &self,
+        user_id: &Uuid,
+        sender: Option<&str>,
+    ) -> Result<(String, SenderIdentity), NotiSrvError> {
+        let sender = sender.map(str::trim).filter(|sender| !sender.is_empty());
+
+        let Some(sender) = sender else {
+            let identity = self
+                .sender_repo
+                .find_default(user_id)
+                .await
+                .map_err(|e| {
+                    error!("Database select error: {}", e);
+                    NotiSrvError::DatabaseError(e)
+                })?
+                .ok_or_else(|| {
+                    NotiSrvError::InvalidDataField(
+                        "Missing required field 'sender' and no default sender".into(),
+                    )
+                })?;
+            return Ok((Self::mailbox(None, &identity.identity, &identity), identity));
+        };
+
+        // `Name <address>` or a bare address
+        let (name, email) = match sender.rsplit_once('<') {
+            Some((name, address)) => {
+                let name = name.trim().trim_matches('"').trim();
+                let name = Some(name).filter(|name| !name.is_empty());
+                (name, address.trim_end_matches('>').trim())
+            }
+            None => (None, sender),
+        };
+        let email = email.to_lowercase();
+        let Some((_, domain)) = email
+            .split_once('@')
+            .filter(|_| Self::is_valid_email(&email))
+        else {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid sender '{}'", sender).into(),
+            ));
+        };
+
+        let identity = self
+            .sender_repo
+            .find_for_address(user_id, &email, domain)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?
+            .ok_or_else(|| {
+                NotiSrvError::SenderNotAllowed(format!(
+                    "'{}' is not a verified sender identity",
+                    email
+                ))
+            })?;
+
+        Ok((Self::mailbox(name, &email, &identity), identity))
+    }
+
+    fn mailbox(name: Option<&str>, email: &str, identity: &SenderIdentity) -> String {
+        match name.or(identity.display_name.as_deref()) {
+            Some(name) => format!(
+                "\"{}\" <{}>",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                email
+            ),
+            None => email.to_string(),
+        }
+    }
+
+    /// Queues the email carrying the verification link of an email identity
+    async fn send_verification_email(
+        &self,
+        user_id: &str,
+        sender: &SenderIdentity,
+    ) -> Result<(), NotiSrvError> {
+        let (Some(signer), Some(from)) = (&self.signer, &self.verification_from) else {
+            warn!(
+                "Sender verification not configured, identity {} stays unverified",
+                sender.id
+            );
+            return Ok(());
+        };
+
+        let link = signer.url(
+            "sender/verify",
+            &SenderVerificationClaims {
+                identity_id: sender.id.clone(),
+                verification_token: sender.verification_token.clone(),
+            },
+        );
+        let request = serde_json::from_value::<NotificationRequest>(serde_json::json!({
+            "user_id": user_id,
+            "recipient": sender.identity,
+            "sender": from,
+            "channel": "email",
+            "category": "transactional",
+            "payload": {
+                "subject": "Verify your sender address",
+                "text": format!(
+                    "Confirm that emails may be sent from {} by opening {}",
+                    sender.identity, link
+                ),
+            }
+        }))
+        .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+
+        let noti_id = self
+            .noti_repo
+            .insert(&request, NotificationStatus::Queued)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        let job = serde_json::json!(NotificationEnQueue {
+            notification_id: noti_id,
+            user_id: request.user_id,
+            recipient: request.recipient,
+            recipient_type: None,
+            sender: request.sender,
+            channel: request.channel.to_string(),
+            provider: None,
+            category: request.category,
+            recipients: None,
+            template_id: None,
+            template_locale: None,
+            payload: request.payload,
+        });
+
+        let queue_key = env::var("QUEUE_KEY").map_err(|e| {
+            error!("Missing env: {}", e);
+            NotiSrvError::MissingEnvError(e)
+        })?;
+
+        self.redis_repo
+            .push_to_queue(&queue_key, &job.to_string())
+            .await
+            .map_err(|e| {
+                error!("Redis push error: {}", e);
+                NotiSrvError::RedisQueuePushError(e)
+            })
+    }
+
+    /// Looks up the TXT records of the domain through DNS-over-HTTPS
+    async fn has_txt_record(&self, record: &DnsRecord) -> bool {
+        let response = self
+            .client
+            .get(&self.dns_url)
+            .query(&[("name", record.name.as_str()), ("type", "TXT")])
+            .header("Accept", "application/dns-json")
+            .send()
+            .await;
+
+        let body = match response {
+            Ok(response) => response.text().await.unwrap_or_default(),
+            Err(e) => {
+                error!("Can not send request: {}", e);
+                return false;
+            }
+        };
+
+        let answers = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|body| body.get("Answer").cloned())
+            .and_then(|answer| answer.as_array().cloned())
+            .unwrap_or_default();
+
+        // Long TXT values are split into several quoted strings
+        answers.iter().any(|answer| {
+            answer
+                .get("data")
+                .and_then(Value::as_str)
+                .is_some_and(|data| data.replace("\" \"", "").trim_matches('"') == record.value)
+        })
+    }
+
+    async fn find(
+        &self,
+        user_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<SenderIdentity>, NotiSrvError> {
+        self.sender_repo.find(user_id, id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })
+    }
+
+    async fn mark_verified(
+        &self,
+        id: &Uuid,
+        verification_token: &str,
+    ) -> Result<u64, NotiSrvError> {
+        self.sender_repo
+            .mark_verified(id, verification_token)
+            .await
+            .map_err(|e| {
+                error!("Database update error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    fn response(identity: SenderIdentity) -> SenderIdentityResponse {
+        let dns_record = (identity.kind == SenderIdentityKind::Domain.to_string())
+            .then(|| Self::dns_record(&identity));
+        SenderIdentityResponse {
+            identity,
+            dns_record,
+        }
+    }
+
+    fn dns_record(identity: &SenderIdentity) -> DnsRecord {
+        DnsRecord {
+            name: format!("{}.{}", DNS_RECORD_PREFIX, identity.identity),
+            value: format!("{}{}", DNS_VALUE_PREFIX, identity.verification_token),
+        }
+    }
+
+    fn is_valid_email(email: &str) -> bool {
+        email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !local.contains(|c: char| {
+                    c.is_whitespace() || c.is_control() || "<>\",;@".contains(c)
+                })
+                && Self::is_valid_domain(domain)
+        })
+    }
+
+    fn is_valid_domain(domain: &str) -> bool {
+        domain.contains('.')
+            && domain.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
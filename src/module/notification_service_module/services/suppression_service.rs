@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use log::error;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::suppression::{
+        Suppression, SuppressionReason, SuppressionRequest, SuppressionResponse,
+    },
+    repository::suppression_repository::SuppressionRepo,
+};
+
+/// `SuppressionService` manages the addresses a user no longer sends emails to
+///
+/// Addresses are added manually through the API, or automatically by `EventService` when
+/// an email hard-bounces or is reported as spam
+pub struct SuppressionService {
+    suppression_repo: Arc<SuppressionRepo>,
+}
+
+impl SuppressionService {
+    pub fn new(suppression_repo: Arc<SuppressionRepo>) -> Self {
+        Self { suppression_repo }
+    }
+
+    pub async fn suppress(
+        &self,
+        request: SuppressionRequest,
+    ) -> Result<SuppressionResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(&request.user_id, "user_id")?;
+        let email = Self::normalize(&request.email);
+        if !email.contains('@') {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'email' must be an email address".into(),
+            ));
+        }
+
+        let id = self
+            .suppression_repo
+            .upsert(
+                &user_id,
+                &email,
+                request.reason.unwrap_or(SuppressionReason::Manual),
+                request.detail.as_deref(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Database upsert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        Ok(SuppressionResponse { id })
+    }
+
+    pub async fn unsuppress(&self, user_id: &str, email: &str) -> Result<u64, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        self.suppression_repo
+            .delete(&user_id, &Self::normalize(email))
+            .await
+            .map_err(|e| {
+                error!("Database delete error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Suppression>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        self.suppression_repo
+            .find_by_user(&user_id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })
+    }
+
+    /// Addresses are compared case-insensitively
+    pub fn normalize(email: &str) -> String {
+        email.trim().to_lowercase()
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::module::notification_service_module::models::email_event::SendGridEvent;
+
+    use super::*;
+
+    fn event(value: serde_json::Value) -> SendGridEvent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn normalizes_addresses() {
+        assert_eq!(
+            SuppressionService::normalize("  John.Doe@Example.COM "),
+            "john.doe@example.com"
+        );
+    }
+
+    #[test]
+    fn suppresses_hard_bounces() {
+        let bounce = event(json!({"event": "bounce", "type": "bounce", "timestamp": 0}));
+
+        assert_eq!(bounce.suppression_reason(), Some(SuppressionReason::Bounce));
+    }
+
+    #[test]
+    fn keeps_blocked_bounces() {
+        let blocked = event(json!({"event": "bounce", "type": "blocked", "timestamp": 0}));
+
+        assert_eq!(blocked.suppression_reason(), None);
+    }
+
+    #[test]
+    fn suppresses_spam_complaints() {
+        let complaint = event(json!({"event": "spamreport", "timestamp": 0}));
+
+        assert_eq!(
+            complaint.suppression_reason(),
+            Some(SuppressionReason::Complaint)
+        );
+    }
+
+    #[test]
+    fn keeps_other_events() {
+        for name in ["delivered", "deferred", "dropped", "open", "click"] {
+            assert_eq!(
+                event(json!({"event": name, "timestamp": 0})).suppression_reason(),
+                None
+            );
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use std::sync::Arc;
+
+use log::{error, info};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::template::{
+        RenderedTemplate, Template, TemplateDraft, TemplateDraftRequest, TemplatePreview,
+        TemplatePreviewRequest, TemplateRequest, TemplateResponse, TemplateVersion,
+        TemplateVersionRecord, TemplateVersionStatus, UserLocale,
+    },
+    repository::template_repository::TemplateRepo,
+    utils::{locale_format, template_renderer},
+};
+
+// Placeholder filled by the delivery workers with the unsubscribe link of the recipient
+const UNSUBSCRIBE_URL_KEY: &str = "unsubscribe_url";
+
+// Channels a template can render payloads of
+const TEMPLATE_TYPES: [&str; 4] = ["email", "push", "sms", "webpush"];
+
+/// `TemplateService` manages versioned templates and renders them into notification payloads
+///
+/// Only the draft of a template is edited. Publishing it makes it the version rendered by new
+/// notifications, while notifications may pin any published or retired version with
+/// `template_id@version`, so in-flight sends never change under them
+pub struct TemplateService {
+    template_repo: Arc<TemplateRepo>,
+}
+
+impl TemplateService {
+    pub fn new(template_repo: Arc<TemplateRepo>) -> Self {
+        Self { template_repo }
+    }
+
+    /// Creates a template whose content is the draft of version 1
+    pub async fn create(&self, request: TemplateRequest) -> Result<TemplateResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(&request.user_id, "user_id")?;
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'name' must not be empty".into(),
+            ));
+        }
+        let r#type = request.r#type.trim().to_lowercase();
+        if !TEMPLATE_TYPES.contains(&r#type.as_str()) {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Invalid template type '{}'", request.r#type).into(),
+            ));
+        }
+        let draft = TemplateDraft {
+            content: Self::validate_content(&request.content)?,
+            locales: Self::validate_locales(&request.locales)?,
+            variables_schema: Self::validate_schema(request.variables_schema.as_ref())?,
+            strict: request.strict,
+        };
+
+        let (template, draft) = self
+            .template_repo
+            .insert(&user_id, name, &r#type, &draft)
+            .await
+            .map_err(|e| {
+                error!("Database insert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        info!("Template {} created", template.id);
+        Ok(TemplateResponse {
+            template,
+            versions: vec![draft.into()],
+        })
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Template>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        self.template_repo.find_all(&user_id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })
+    }
+
+    /// Returns a template with all its versions, latest first
+    pub async fn find(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<TemplateResponse>, NotiSrvError> {
+        let Some((template, id)) = self.find_template(user_id, id).await? else {
+            return Ok(None);
+        };
+
+        let versions = self.template_repo.find_versions(&id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        Ok(Some(TemplateResponse {
+            template,
+            versions: versions.into_iter().map(TemplateVersion::from).collect(),
+        }))
+    }
+
+    /// Replaces the content of the draft, starting a new draft after the latest version if
+    /// there is none
+    pub async fn update_draft(
+        &self,
+        user_id: &str,
+        id: &str,
+        request: &TemplateDraftRequest,
+    ) -> Result<Option<TemplateVersion>, NotiSrvError> {
+        let draft = TemplateDraft {
+            content: Self::validate_content(&request.content)?,
+            locales: Self::validate_locales(&request.locales)?,
+            variables_schema: Self::validate_schema(request.variables_schema.as_ref())?,
+            strict: request.strict,
+        };
+        let Some((_, id)) = self.find_template(user_id, id).await? else {
+            return Ok(None);
+        };
+
+        let draft = self
+            .template_repo
+            .upsert_draft(&id, &draft)
+            .await
+            .map_err(|e| {
+                error!("Database upsert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        Ok(Some(draft.into()))
+    }
+
+    /// Publishes a version, a draft or a retired one, retiring the published version
+    pub async fn publish(
+        &self,
+        user_id: &str,
+        id: &str,
+        version: i32,
+    ) -> Result<Option<TemplateVersion>, NotiSrvError> {
+        let Some((_, id)) = self.find_template(user_id, id).await? else {
+            return Ok(None);
+        };
+
+        let published = self
+            .template_repo
+            .publish(&id, version)
+            .await
+            .map_err(|e| {
+                error!("Database update error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        if published.is_some() {
+            info!("Template {} version {} published", id, version);
+        }
+        Ok(published.map(TemplateVersion::from))
+    }
+
+    /// Publishes again the version published before the current one
+    pub async fn rollback(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<TemplateVersion>, NotiSrvError> {
+        let Some((_, template_id)) = self.find_template(user_id, id).await? else {
+            return Ok(None);
+        };
+
+        let previous = self
+            .template_repo
+            .find_previous_version(&template_id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?
+            .ok_or_else(|| {
+                NotiSrvError::InvalidDataField("No previous version to roll back to".into())
+            })?;
+
+        self.publish(user_id, id, previous).await
+    }
+
+    /// Sets the locale the templates of the user are rendered in when a notification gives
+    /// none, returns `None` if the user does not exist
+    pub async fn set_user_locale(
+        &self,
+        user_id: &str,
+        request: &UserLocale,
+    ) -> Result<Option<UserLocale>, NotiSrvError> {
+        let parsed_user_id = Self::parse_uuid(user_id, "user_id")?;
+        let locale = match &request.locale {
+            Some(locale) => Some(locale_format::normalize(locale).ok_or_else(|| {
+                NotiSrvError::InvalidDataField(format!("Invalid locale '{}'", locale).into())
+            })?),
+            None => None,
+        };
+
+        let updated = self
+            .template_repo
+            .update_user_locale(&parsed_user_id, locale.as_deref())
+            .await
+            .map_err(|e| {
+                error!("Database update error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+        if updated == 0 {
+            return Ok(None);
+        }
+
+        info!("Locale of user {} set to {:?}", user_id, locale);
+        Ok(Some(UserLocale { locale }))
+    }
+
+    /// Renders a version of a template with sample variables, drafts included
+    pub async fn preview(
+        &self,
+        user_id: &str,
+        id: &str,
+        request: &TemplatePreviewRequest,
+    ) -> Result<Option<TemplatePreview>, NotiSrvError> {
+        let locale = self
+            .resolve_locale(user_id, request.locale.as_deref())
+            .await?;
+        let Some((template, template_id)) = self.find_template(user_id, id).await? else {
+            return Ok(None);
+        };
+
+        let versions = self
+            .template_repo
+            .find_versions(&template_id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+        let draft = TemplateVersionStatus::Draft.to_string();
+        let record = match request.version {
+            Some(version) => versions.iter().find(|record| record.version == version),
+            None => versions
+                .iter()
+                .find(|record| record.status == draft)
+                .or_else(|| {
+                    versions
+                        .iter()
+                        .find(|record| Some(record.version) == template.published_version)
+                }),
+        };
+        let Some(record) = record else {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Template '{}' has no version to preview", id).into(),
+            ));
+        };
+
+        let (content, variant) = Self::localized_content(record, locale.as_deref())?;
+        let missing_variables = Self::missing_variables(&content, &request.variables, &[]);
+        let invalid_variables = Self::variable_errors(record, &request.variables)?;
+
+        Ok(Some(TemplatePreview {
+            r#type: template.r#type,
+            version: record.version,
+            status: record.status.clone(),
+            locale: variant,
+            content: template_renderer::render(&content, &request.variables, locale.as_deref()),
+            strict: record.strict,
+            missing_variables,
+            invalid_variables,
+        }))
+    }
+
+    /// Renders a template into the payload of a notification
+    ///
+    /// `template` is a template id, rendered at its published version, or
+    /// `template_id@version`. Payload fields take precedence over the fields of the template,
+    /// placeholders are filled with `variables` of the payload
+    ///
+    /// The variables must match the schema of the version. A strict version also requires a
+    /// variable other than null or an empty string for each placeholder, except the ones the
+    /// delivery workers fill: the unsubscribe link and the keys of `substituted` by every
+    /// recipient
+    ///
+    /// The variant of `locale`, or else of the locale of the user profile, is resolved with
+    /// fallback: `pt-BR` uses the fields of `pt-BR`, then of `pt`, then the default content.
+    /// Dates and numbers are formatted for that locale
+    pub async fn render(
+        &self,
+        user_id: &str,
+        channel: &str,
+        template: &str,
+        locale: Option<&str>,
+        substituted: &[String],
+        payload: &mut Value,
+    ) -> Result<RenderedTemplate, NotiSrvError> {
+        let locale = self.resolve_locale(user_id, locale).await?;
+
+        let (id, pinned) = match template.split_once('@') {
+            Some((id, version)) => {
+                let version = version.parse::<i32>().map_err(|_| {
+                    NotiSrvError::InvalidDataField(
+                        format!("Invalid template version '{}'", version).into(),
+                    )
+                })?;
+                (id, Some(version))
+            }
+            None => (template, None),
+        };
+
+        let Some((template, template_id)) = self.find_template(user_id, id).await? else {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Template '{}' not found", id).into(),
+            ));
+        };
+        if template.r#type.as_deref() != Some(channel) {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Template '{}' is not a {} template", id, channel).into(),
+            ));
+        }
+
+        let Some(version) = pinned.or(template.published_version) else {
+            return Err(NotiSrvError::InvalidDataField(
+                format!("Template '{}' has no published version", id).into(),
+            ));
+        };
+        let record = self
+            .template_repo
+            .find_version(&template_id, version)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?
+            .filter(|record| record.status != TemplateVersionStatus::Draft.to_string())
+            .ok_or_else(|| {
+                NotiSrvError::InvalidDataField(
+                    format!("Template '{}' has no published version {}", id, version).into(),
+                )
+            })?;
+
+        let (content, variant) = Self::localized_content(&record, locale.as_deref())?;
+        let variables = payload
+            .get("variables")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+
+        let errors = Self::variable_errors(&record, &variables)?;
+        if !errors.is_empty() {
+            return Err(NotiSrvError::InvalidVariables(errors));
+        }
+        if record.strict {
+            Self::check_missing(&Self::missing_variables(&content, &variables, substituted))?;
+        }
+
+        let rendered = template_renderer::render(&content, &variables, locale.as_deref());
+
+        if !payload.is_object() {
+            *payload = serde_json::json!({});
+        }
+        if let (Some(payload), Value::Object(fields)) = (payload.as_object_mut(), rendered) {
+            for (key, value) in fields {
+                payload.entry(key).or_insert(value);
+            }
+        }
+
+        Ok(RenderedTemplate {
+            template_id: template_id.to_string(),
+            version,
+            locale: variant,
+        })
+    }
+
+    /// Returns the content of a version for a locale, with the locale of the most specific
+    /// variant found
+    ///
+    /// Variants only need the fields they translate, the others come from less specific
+    /// variants and the default content
+    fn localized_content(
+        record: &TemplateVersionRecord,
+        locale: Option<&str>,
+    ) -> Result<(Value, Option<String>), NotiSrvError> {
+        let mut content = serde_json::from_str::<Value>(&record.content)
+            .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+        let Some(locale) = locale else {
+            return Ok((content, None));
+        };
+        let locales = serde_json::from_str::<Map<String, Value>>(&record.locales)
+            .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+
+        let mut variant = None;
+        // Least specific first, so that more specific variants override it
+        for candidate in locale_format::fallbacks(locale).into_iter().rev() {
+            let found = locales
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&candidate));
+            if let (Some((key, Value::Object(fields))), Some(content)) =
+                (found, content.as_object_mut())
+            {
+                content.extend(fields.clone());
+                variant = Some(key.clone());
+            }
+        }
+
+        Ok((content, variant))
+    }
+
+    /// Returns the placeholders of `content` which neither a variable nor the delivery workers
+    /// fill, null and empty variables count as missing
+    fn missing_variables(
+        content: &Value,
+        variables: &Map<String, Value>,
+        substituted: &[String],
+    ) -> Vec<String> {
+        template_renderer::placeholders(content)
+            .into_iter()
+            .filter(|name| {
+                let filled = variables
+                    .get(name)
+                    .is_some_and(|value| !value.is_null() && value.as_str() != Some(""));
+                name != UNSUBSCRIBE_URL_KEY && !filled && !substituted.contains(name)
+            })
+            .collect()
+    }
+
+    /// Rejects the variables of a strict version when placeholders are missing
+    pub fn check_missing(missing: &[String]) -> Result<(), NotiSrvError> {
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(NotiSrvError::InvalidVariables(
+            missing
+                .iter()
+                .map(|name| format!("Missing variable '{}'", name))
+                .collect(),
+        ))
+    }
+
+    /// Returns the mismatches of variables with the schema of a version, prefixed with the
+    /// path of the variable
+    fn variable_errors(
+        record: &TemplateVersionRecord,
+        variables: &Map<String, Value>,
+    ) -> Result<Vec<String>, NotiSrvError> {
+        let Some(schema) = &record.variables_schema else {
+            return Ok(Vec::new());
+        };
+        let schema = serde_json::from_str::<Value>(schema)
+            .map_err(|e| NotiSrvError::InvalidDataField(e.into()))?;
+        let validator = jsonschema::validator_for(&schema).map_err(|e| {
+            NotiSrvError::InvalidDataField(format!("Invalid 'variables_schema': {}", e).into())
+        })?;
+
+        let variables = Value::Object(variables.clone());
+        let errors = validator
+            .iter_errors(&variables)
+            .map(|error| match error.instance_path.as_str() {
+                "" => error.to_string(),
+                path => format!("{}: {}", path, error),
+            })
+            .collect();
+        Ok(errors)
+    }
+
+    /// Returns the locale given, or else the locale of the user profile
+    async fn resolve_locale(
+        &self,
+        user_id: &str,
+        locale: Option<&str>,
+    ) -> Result<Option<String>, NotiSrvError> {
+        match locale {
+            Some(locale) => Ok(Some(locale_format::normalize(locale).ok_or_else(|| {
+                NotiSrvError::InvalidDataField(format!("Invalid locale '{}'", locale).into())
+            })?)),
+            None => self.user_locale(user_id).await,
+        }
+    }
+
+    async fn user_locale(&self, user_id: &str) -> Result<Option<String>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        let locale = self
+            .template_repo
+            .find_user_locale(&user_id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        // A malformed profile locale renders the default content
+        Ok(locale.as_deref().and_then(locale_format::normalize))
+    }
+
+    async fn find_template(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<(Template, Uuid)>, NotiSrvError> {
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+        let id = Self::parse_uuid(id, "template_id")?;
+
+        let template = self.template_repo.find(&user_id, &id).await.map_err(|e| {
+            error!("Database select error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        Ok(template.map(|template| (template, id)))
+    }
+
+    /// Returns the content of a version as stored, a JSON object of payload fields
+    fn validate_content(content: &Value) -> Result<String, NotiSrvError> {
+        if content.as_object().is_none_or(|fields| fields.is_empty()) {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'content' must be an object of payload fields".into(),
+            ));
+        }
+        Self::check_filters(content)?;
+        Ok(content.to_string())
+    }
+
+    fn check_filters(content: &Value) -> Result<(), NotiSrvError> {
+        match template_renderer::filter_errors(content).into_iter().next() {
+            Some(error) => Err(NotiSrvError::InvalidDataField(error.into())),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the schema of the variables as stored, which must be a valid JSON Schema
+    fn validate_schema(schema: Option<&Value>) -> Result<Option<String>, NotiSrvError> {
+        let Some(schema) = schema.filter(|schema| !schema.is_null()) else {
+            return Ok(None);
+        };
+        if !schema.is_object() {
+            return Err(NotiSrvError::InvalidDataField(
+                "Field 'variables_schema' must be a JSON Schema object".into(),
+            ));
+        }
+        jsonschema::validator_for(schema).map_err(|e| {
+            NotiSrvError::InvalidDataField(format!("Invalid 'variables_schema': {}", e).into())
+        })?;
+        Ok(Some(schema.to_string()))
+    }
+
+    /// Returns the variants of a version as stored, payload fields per normalized locale
+    fn validate_locales(locales: &Map<String, Value>) -> Result<String, NotiSrvError> {
+        let mut normalized = Map::new();
+        for (locale, content) in locales {
+            let Some(tag) = locale_format::normalize(locale) else {
+                return Err(NotiSrvError::InvalidDataField(
+                    format!("Invalid locale '{}'", locale).into(),
+                ));
+            };
+            if content.as_object().is_none_or(|fields| fields.is_empty()) {
+                return Err(NotiSrvError::InvalidDataField(
+                    format!("Locale '{}' must be an object of payload fields", locale).into(),
+                ));
+            }
+            Self::check_filters(content)?;
+            if normalized.insert(tag, content.clone()).is_some() {
+                return Err(NotiSrvError::InvalidDataField(
+                    format!("Duplicate locale '{}'", locale).into(),
+                ));
+            }
+        }
+        Ok(Value::Object(normalized).to_string())
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use log::{error, info, warn};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::{
+        email_event::ProviderEvent,
+        engagement::{TemplateEngagement, TrackingOptions},
+    },
+    repository::event_repository::EventRepo,
+    utils::{
+        email_options,
+        html_tracking::{insert_pixel, rewrite_links},
+        signed_token::{TokenSigner, TrackingClaims},
+    },
+};
+
+// Provider of the engagement events recorded by the gateway itself
+const TRACKING_PROVIDER: &str = "gateway";
+
+/// `TrackingService` measures the opens and clicks of HTML emails
+///
+/// Tracked emails get a signed pixel and links rewritten to a signed redirect before they are
+/// queued, both endpoints record a `NotificationEvent`. Configured with `TRACKING_SECRET` and
+/// `PUBLIC_BASE_URL`, tracking is ignored when they are not set
+pub struct TrackingService {
+    event_repo: Arc<EventRepo>,
+    signer: Option<TokenSigner>,
+}
+
+impl TrackingService {
+    pub fn new(event_repo: Arc<EventRepo>) -> Self {
+        Self {
+            event_repo,
+            signer: TokenSigner::from_env("TRACKING_SECRET"),
+        }
+    }
+
+    /// Adds the requested tracking to the HTML part of an email payload
+    ///
+    /// The HTML part is `html`, or `content` when its `content_type` is `text/html`. Plain
+    /// text parts are left untouched
+    pub fn instrument(
+        &self,
+        notification_id: &str,
+        payload: &mut Value,
+        options: &TrackingOptions,
+    ) {
+        let Some(signer) = &self.signer else {
+            return;
+        };
+        let is_html_content = payload
+            .get("content_type")
+            .and_then(Value::as_str)
+            .is_some_and(email_options::is_html);
+        let field = if payload.get("html").is_some() {
+            "html"
+        } else if is_html_content {
+            "content"
+        } else {
+            return;
+        };
+        let Some(html) = payload.get(field).and_then(Value::as_str) else {
+            return;
+        };
+
+        let mut html = html.to_string();
+        if options.clicks {
+            html = rewrite_links(&html, |url| {
+                Some(signer.url(
+                    "tracking/click",
+                    &TrackingClaims {
+                        notification_id: notification_id.to_string(),
+                        url: Some(url.to_string()),
+                    },
+                ))
+            });
+        }
+        if options.opens {
+            let pixel_url = signer.url(
+                "tracking/open",
+                &TrackingClaims {
+                    notification_id: notification_id.to_string(),
+                    url: None,
+                },
+            );
+            html = insert_pixel(&html, &pixel_url);
+        }
+
+        payload[field] = Value::String(html);
+    }
+
+    /// Records the open carried by a pixel token, invalid tokens are ignored
+    pub async fn record_open(&self, token: &str) -> Result<(), NotiSrvError> {
+        let Some(claims) = self.verify(token) else {
+            return Ok(());
+        };
+
+        self.record(&claims, "open").await
+    }
+
+    /// Records the click carried by a link token and returns the target of the link
+    ///
+    /// Returns `None` if the token is invalid
+    pub async fn record_click(&self, token: &str) -> Result<Option<String>, NotiSrvError> {
+        let Some(claims) = self.verify(token) else {
+            return Ok(None);
+        };
+        let Some(url) = claims.url.clone() else {
+            return Ok(None);
+        };
+
+        // The redirect does not depend on the event being stored
+        if let Err(e) = self.record(&claims, "click").await {
+            error!("Cannot record click: {}", e);
+        }
+        Ok(Some(url))
+    }
+
+    /// Aggregates the engagement of the notifications sent with a template
+    pub async fn template_engagement(
+        &self,
+        template_id: &str,
+    ) -> Result<TemplateEngagement, NotiSrvError> {
+        let id = Uuid::parse_str(template_id)
+            .map_err(|_| NotiSrvError::InvalidDataField("Invalid template id".into()))?;
+
+        let counters = self
+            .event_repo
+            .template_engagement(&id)
+            .await
+            .map_err(|e| {
+                error!("Database select error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        let rate = |count: i64| {
+            if counters.sent == 0 {
+                0.0
+            } else {
+                count as f64 / counters.sent as f64
+            }
+        };
+
+        Ok(TemplateEngagement {
+            template_id: id.to_string(),
+            open_rate: rate(counters.opened),
+            click_rate: rate(counters.clicked),
+            counters,
+        })
+    }
+
+    fn verify(&self, token: &str) -> Option<TrackingClaims> {
+        let claims = self
+            .signer
+            .as_ref()
+            .and_then(|signer| signer.verify::<TrackingClaims>(token));
+        if claims.is_none() {
+            warn!("Invalid tracking token");
+        }
+        claims
+    }
+
+    async fn record(&self, claims: &TrackingClaims, event: &str) -> Result<(), NotiSrvError> {
+        let notification_id = Uuid::parse_str(&claims.notification_id)
+            .map_err(|_| NotiSrvError::InvalidDataField("Invalid notification id".into()))?;
+
+        let event = ProviderEvent {
+            notification_id,
+            provider: TRACKING_PROVIDER.to_string(),
+            // Every open and click is counted
+            provider_event_id: None,
+            event: event.to_string(),
+            detail: claims.url.clone(),
+            occurred_at: Utc::now().naive_utc(),
+        };
+
+        self.event_repo.insert_event(&event).await.map_err(|e| {
+            error!("Database insert error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })?;
+
+        info!(
+            "Recorded {} of notification {}",
+            event.event, notification_id
+        );
+        Ok(())
+    }
+}
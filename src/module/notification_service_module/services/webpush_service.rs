@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::error;
+use uuid::Uuid;
+
+use crate::module::notification_service_module::{
+    errors::NotiSrvError,
+    models::webpush::{PushSubscription, WebPushSubscriptionRequest, WebPushSubscriptionResponse},
+    repository::webpush_repository::WebPushRepo,
+};
+
+pub struct WebPushService {
+    webpush_repo: Arc<WebPushRepo>,
+}
+
+impl WebPushService {
+    pub fn new(webpush_repo: Arc<WebPushRepo>) -> Self {
+        Self { webpush_repo }
+    }
+
+    pub async fn subscribe(
+        &self,
+        request: WebPushSubscriptionRequest,
+    ) -> Result<WebPushSubscriptionResponse, NotiSrvError> {
+        let user_id = Self::parse_uuid(&request.user_id, "user_id")?;
+        Self::validate_subscription(&request.subscription)?;
+
+        let id = self
+            .webpush_repo
+            .upsert(&user_id, &request.subscription)
+            .await
+            .map_err(|e| {
+                error!("Database upsert error: {}", e);
+                NotiSrvError::DatabaseError(e)
+            })?;
+
+        Ok(WebPushSubscriptionResponse { id })
+    }
+
+    pub async fn unsubscribe(&self, id: &str, user_id: &str) -> Result<u64, NotiSrvError> {
+        let id = Self::parse_uuid(id, "id")?;
+        let user_id = Self::parse_uuid(user_id, "user_id")?;
+
+        self.webpush_repo.delete(&id, &user_id).await.map_err(|e| {
+            error!("Database delete error: {}", e);
+            NotiSrvError::DatabaseError(e)
+        })
+    }
+
+    /// Ensures the subscription carries an https endpoint and well-formed RFC 8291 keys
+    fn validate_subscription(subscription: &PushSubscription) -> Result<(), NotiSrvError> {
+        if !subscription.endpoint.starts_with("https://")
+            || reqwest::Url::parse(&subscription.endpoint).is_err()
+        {
+            return Err(NotiSrvError::InvalidDataField(
+                "Subscription 'endpoint' must be an https URL".into(),
+            ));
+        }
+
+        // p256dh is an uncompressed P-256 point, auth a 16 bytes secret
+        let p256dh = Self::decode_key(&subscription.keys.p256dh);
+        if p256dh.is_none_or(|key| key.len() != 65 || key[0] != 0x04) {
+            return Err(NotiSrvError::InvalidDataField(
+                "Subscription key 'p256dh' is invalid".into(),
+            ));
+        }
+        if Self::decode_key(&subscription.keys.auth).is_none_or(|key| key.len() != 16) {
+            return Err(NotiSrvError::InvalidDataField(
+                "Subscription key 'auth' is invalid".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn decode_key(value: &str) -> Option<Vec<u8>> {
+        URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()
+    }
+
+    fn parse_uuid(value: &str, field: &str) -> Result<Uuid, NotiSrvError> {
+        Uuid::parse_str(value)
+            .map_err(|_| NotiSrvError::InvalidDataField(format!("Invalid '{}'", field).into()))
+    }
+}
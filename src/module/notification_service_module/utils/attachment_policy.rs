@@ -0,0 +1,279 @@
+use std::{collections::HashSet, env};
+
+// Defaults stay under the limits of the email providers (SendGrid accepts 30 MB per email)
+const DEFAULT_MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+const DEFAULT_MAX_TOTAL_SIZE: usize = 25 * 1024 * 1024;
+const DEFAULT_BLOCKED_EXTENSIONS: &str = "bat,cmd,com,exe,jar,js,msi,ps1,scr,sh,vbs";
+// Room left in a request for the rest of the payload of an email
+const MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
+
+// Content types of executables, never accepted whatever their declared type
+const EXECUTABLE_TYPES: [&str; 2] = ["application/x-msdownload", "application/x-executable"];
+
+/// `AttachmentPolicy` decides which attachments emails may carry
+///
+/// Configured with:
+/// - `ATTACHMENT_MAX_FILE_SIZE`: maximum size of one attachment in bytes, 10 MiB if unset
+/// - `ATTACHMENT_MAX_TOTAL_SIZE`: maximum size of the attachments of an email in bytes,
+///   25 MiB if unset
+/// - `ATTACHMENT_BLOCKED_EXTENSIONS`: comma separated file extensions refused, executables
+///   and scripts if unset
+///
+/// It is read once at startup, an invalid size is returned as an error
+pub struct AttachmentPolicy {
+    pub max_file_size: usize,
+    pub max_total_size: usize,
+    blocked_extensions: HashSet<String>,
+}
+
+impl AttachmentPolicy {
+    pub fn from_env() -> Result<Self, String> {
+        let size = |name: &str, default: usize| match env::var(name) {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{} must be a number of bytes", name)),
+            Err(_) => Ok(default),
+        };
+
+        let blocked_extensions = env::var("ATTACHMENT_BLOCKED_EXTENSIONS")
+            .unwrap_or_else(|_| DEFAULT_BLOCKED_EXTENSIONS.to_string())
+            .split(',')
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+
+        Ok(Self {
+            max_file_size: size("ATTACHMENT_MAX_FILE_SIZE", DEFAULT_MAX_FILE_SIZE)?,
+            max_total_size: size("ATTACHMENT_MAX_TOTAL_SIZE", DEFAULT_MAX_TOTAL_SIZE)?,
+            blocked_extensions,
+        })
+    }
+
+    /// Maximum size of a JSON request carrying an email with inline attachments
+    ///
+    /// Inline attachments are base64 encoded, 4 bytes for every 3 bytes of content
+    pub fn max_request_size(&self) -> usize {
+        self.max_total_size.div_ceil(3) * 4 + MAX_PAYLOAD_SIZE
+    }
+
+    /// Checks an attachment and returns its content type
+    ///
+    /// The declared type must match the type sniffed from the content when it is recognized,
+    /// `application/octet-stream` is replaced by the sniffed type
+    pub fn check(
+        &self,
+        filename: &str,
+        declared_type: &str,
+        content: &[u8],
+    ) -> Result<String, String> {
+        if content.is_empty() {
+            return Err(format!("Attachment '{}' is empty", filename));
+        }
+        if content.len() > self.max_file_size {
+            return Err(format!(
+                "Attachment '{}' exceeds {} bytes",
+                filename, self.max_file_size
+            ));
+        }
+
+        self.check_filename(filename)?;
+
+        let declared_type = declared_type.trim().to_lowercase();
+        let valid_type = declared_type
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| {
+                let token = |value: &str| {
+                    !value.is_empty()
+                        && value
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+                };
+                token(kind) && token(subtype)
+            });
+        if !valid_type {
+            return Err(format!(
+                "Invalid content type '{}' of attachment '{}'",
+                declared_type, filename
+            ));
+        }
+
+        match sniff(content) {
+            Some(sniffed) if EXECUTABLE_TYPES.contains(&sniffed) => {
+                Err(format!("Attachment '{}' is an executable", filename))
+            }
+            Some(sniffed) if declared_type == "application/octet-stream" => Ok(sniffed.to_string()),
+            Some(sniffed) if !is_compatible(sniffed, &declared_type) => Err(format!(
+                "Attachment '{}' is declared as {} but contains {}",
+                filename, declared_type, sniffed
+            )),
+            _ => Ok(declared_type),
+        }
+    }
+
+    /// Checks the name an attachment is sent with, which must not have a blocked extension
+    pub fn check_filename(&self, filename: &str) -> Result<(), String> {
+        let valid_filename = !filename.trim().is_empty()
+            && !filename.contains(|c: char| c.is_control() || c == '/' || c == '\\');
+        if !valid_filename {
+            return Err(format!("Invalid attachment filename '{}'", filename));
+        }
+        if let Some((_, extension)) = filename.rsplit_once('.') {
+            if self.blocked_extensions.contains(&extension.to_lowercase()) {
+                return Err(format!("Attachment '{}' has a blocked extension", filename));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Recognizes the type of a content from its first bytes
+fn sniff(content: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 8] = [
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+
+    if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    // Windows executables start with `MZ` and point at a `PE` header at offset 0x3c
+    if content.starts_with(b"MZ") && content.len() >= 0x40 {
+        let offset =
+            u32::from_le_bytes([content[0x3c], content[0x3d], content[0x3e], content[0x3f]])
+                as usize;
+        if content.get(offset..offset.saturating_add(4)) == Some(&b"PE\0\0"[..]) {
+            return Some("application/x-msdownload");
+        }
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+        .map(|(_, content_type)| *content_type)
+}
+
+fn is_compatible(sniffed: &str, declared: &str) -> bool {
+    match sniffed {
+        "image/jpeg" => declared == "image/jpeg" || declared == "image/jpg",
+        // Office documents, OpenDocument files and EPUBs are zip containers
+        "application/zip" => declared.starts_with("application/"),
+        _ => sniffed == declared,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> AttachmentPolicy {
+        AttachmentPolicy {
+            max_file_size: 64,
+            max_total_size: 128,
+            blocked_extensions: ["exe", "js"].map(str::to_string).into(),
+        }
+    }
+
+    /// A minimal Windows executable, with the `PE` header at `offset`
+    fn portable_executable(offset: usize) -> Vec<u8> {
+        let mut content = vec![0; offset + 4];
+        content[..2].copy_from_slice(b"MZ");
+        content[0x3c..0x40].copy_from_slice(&(offset as u32).to_le_bytes());
+        content[offset..].copy_from_slice(b"PE\0\0");
+        content
+    }
+
+    #[test]
+    fn checks_filenames() {
+        let policy = policy();
+        assert!(policy.check_filename("report.pdf").is_ok());
+        assert!(policy.check_filename("README").is_ok());
+        assert!(policy.check_filename("invoice.EXE").is_err());
+        assert!(policy.check_filename("app.js").is_err());
+        assert!(policy.check_filename("../etc/passwd").is_err());
+        assert!(policy.check_filename("a\\b.txt").is_err());
+        assert!(policy.check_filename("a\r\nb.txt").is_err());
+        assert!(policy.check_filename("  ").is_err());
+    }
+
+    #[test]
+    fn checks_sizes() {
+        let policy = policy();
+        assert!(policy.check("a.txt", "text/plain", b"").is_err());
+        assert!(policy.check("a.txt", "text/plain", &[b'a'; 65]).is_err());
+        assert_eq!(
+            policy.check("a.txt", "Text/Plain", &[b'a'; 64]).as_deref(),
+            Ok("text/plain")
+        );
+    }
+
+    #[test]
+    fn checks_declared_types() {
+        let policy = policy();
+        assert!(policy.check("a.pdf", "pdf", b"%PDF-1.7").is_err());
+        assert!(policy.check("a.pdf", "text/plain; x", b"%PDF-1.7").is_err());
+        assert!(policy.check("a.pdf", "image/png", b"%PDF-1.7").is_err());
+        assert_eq!(
+            policy
+                .check("a.pdf", "application/octet-stream", b"%PDF-1.7")
+                .as_deref(),
+            Ok("application/pdf")
+        );
+        assert_eq!(
+            policy
+                .check("a.jpg", "image/jpg", b"\xff\xd8\xff\xe0")
+                .as_deref(),
+            Ok("image/jpg")
+        );
+        assert_eq!(
+            policy
+                .check("a.docx", "application/vnd.ms-word", b"PK\x03\x04")
+                .as_deref(),
+            Ok("application/vnd.ms-word")
+        );
+    }
+
+    #[test]
+    fn rejects_executables_whatever_their_type() {
+        let policy = policy();
+        assert!(policy
+            .check("a.pdf", "application/pdf", &portable_executable(0x40))
+            .is_err());
+        assert!(policy
+            .check("a.bin", "application/octet-stream", b"\x7fELF\x02")
+            .is_err());
+    }
+
+    #[test]
+    fn sniffs_content_types() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+        assert_eq!(sniff(b"GIF89a"), Some("image/gif"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"\x1f\x8b\x08"), Some("application/gzip"));
+        assert_eq!(sniff(b"plain text"), None);
+    }
+
+    #[test]
+    fn sniffs_windows_executables_from_their_pe_header() {
+        assert_eq!(
+            sniff(&portable_executable(0x80)),
+            Some("application/x-msdownload")
+        );
+
+        // `MZ` alone, a header pointing elsewhere or past the end is not an executable
+        let mut no_header = portable_executable(0x80);
+        no_header[0x80] = b'X';
+        assert_eq!(sniff(&no_header), None);
+        let mut out_of_bounds = portable_executable(0x80);
+        out_of_bounds[0x3c..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(sniff(&out_of_bounds), None);
+        assert_eq!(sniff(b"MZ short"), None);
+    }
+}
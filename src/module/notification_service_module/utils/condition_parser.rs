@@ -0,0 +1,281 @@
+//! Parser for FCM topic condition expressions.
+//!
+//! A condition combines `'<topic>' in topics` clauses with `!`, `&&`, `||` and parentheses,
+//! e.g. `'a' in topics && !('b' in topics || 'c' in topics)`. Topics are single quoted, `!`
+//! binds tighter than `&&`, which binds tighter than `||`.
+
+use derive_more::Display;
+
+// FCM allows at most five topics in a single condition
+const MAX_CONDITION_TOPICS: usize = 5;
+// FCM topic names are limited to 900 characters
+const MAX_TOPIC_LENGTH: usize = 900;
+
+#[derive(Debug, PartialEq, Display)]
+enum Token {
+    #[display("'{_0}'")]
+    Topic(String),
+    #[display("'in'")]
+    In,
+    #[display("'topics'")]
+    Topics,
+    #[display("'&&'")]
+    And,
+    #[display("'||'")]
+    Or,
+    #[display("'!'")]
+    Not,
+    #[display("'('")]
+    LeftParen,
+    #[display("')'")]
+    RightParen,
+}
+
+/// Validates a topic condition and returns the distinct topics it references
+pub fn validate_condition(condition: &str) -> Result<Vec<String>, String> {
+    let tokens = tokenize(condition)?;
+    if tokens.is_empty() {
+        return Err("condition is empty".to_string());
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        topics: Vec::new(),
+    };
+    parser.parse_or()?;
+
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {} after end of expression", token));
+    }
+
+    if parser.topics.len() > MAX_CONDITION_TOPICS {
+        return Err(format!(
+            "condition references {} topics, at most {} are allowed",
+            parser.topics.len(),
+            MAX_CONDITION_TOPICS
+        ));
+    }
+
+    Ok(parser.topics)
+}
+
+fn tokenize(condition: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = condition.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' => tokens.push(Token::LeftParen),
+            ')' => tokens.push(Token::RightParen),
+            '!' => tokens.push(Token::Not),
+            '&' | '|' => {
+                if chars.next_if(|(_, next)| *next == c).is_none() {
+                    return Err(format!("expected '{}{}' at position {}", c, c, index));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '\'' => {
+                let mut topic = String::new();
+                let mut closed = false;
+                for (_, next) in chars.by_ref() {
+                    if next == '\'' {
+                        closed = true;
+                        break;
+                    }
+                    topic.push(next);
+                }
+                if !closed {
+                    return Err(format!("unterminated topic name at position {}", index));
+                }
+                validate_topic(&topic)?;
+                tokens.push(Token::Topic(topic));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.next_if(|(_, next)| next.is_ascii_alphabetic()) {
+                    word.push(next);
+                }
+                match word.as_str() {
+                    "in" => tokens.push(Token::In),
+                    "topics" => tokens.push(Token::Topics),
+                    _ => return Err(format!("unexpected word '{}' at position {}", word, index)),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "unexpected character '{}' at position {}",
+                    c, index
+                ))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Topic names must match `[a-zA-Z0-9-_.~%]{1,900}`
+fn validate_topic(topic: &str) -> Result<(), String> {
+    let valid = !topic.is_empty()
+        && topic.len() <= MAX_TOPIC_LENGTH
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.~%".contains(c));
+
+    if !valid {
+        return Err(format!("invalid topic name '{}'", topic));
+    }
+    Ok(())
+}
+
+/// Recursive descent parser over the token stream
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    topics: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, found {}", expected, token)),
+            None => Err(format!("expected {}, found end of expression", expected)),
+        }
+    }
+
+    // or := and ( '||' and )*
+    fn parse_or(&mut self) -> Result<(), String> {
+        self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            self.parse_and()?;
+        }
+        Ok(())
+    }
+
+    // and := unary ( '&&' unary )*
+    fn parse_and(&mut self) -> Result<(), String> {
+        self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            self.parse_unary()?;
+        }
+        Ok(())
+    }
+
+    // unary := '!' unary | primary
+    fn parse_unary(&mut self) -> Result<(), String> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return self.parse_unary();
+        }
+        self.parse_primary()
+    }
+
+    // primary := '(' or ')' | TOPIC 'in' 'topics'
+    fn parse_primary(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::LeftParen) => {
+                self.parse_or()?;
+                self.expect(Token::RightParen)
+            }
+            Some(Token::Topic(topic)) => {
+                let topic = topic.clone();
+                self.expect(Token::In)?;
+                self.expect(Token::Topics)?;
+                if !self.topics.contains(&topic) {
+                    self.topics.push(topic);
+                }
+                Ok(())
+            }
+            Some(token) => Err(format!("expected topic, '!' or '(', found {}", token)),
+            None => Err("expected topic, '!' or '(', found end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_single_clause() {
+        assert_eq!(
+            validate_condition("'news' in topics"),
+            Ok(vec!["news".to_string()])
+        );
+    }
+
+    #[test]
+    fn accepts_nested_and_or() {
+        let topics = validate_condition("'a' in topics && ('b' in topics || 'c' in topics)");
+        assert_eq!(topics, Ok(vec!["a".into(), "b".into(), "c".into()]));
+    }
+
+    #[test]
+    fn accepts_negation() {
+        assert!(validate_condition("'a' in topics && !('b' in topics)").is_ok());
+        assert!(validate_condition("!'a' in topics").is_ok());
+        assert!(validate_condition("!!('a' in topics)").is_ok());
+    }
+
+    #[test]
+    fn deduplicates_topics() {
+        let topics = validate_condition("'a' in topics || ('a' in topics && 'b' in topics)");
+        assert_eq!(topics, Ok(vec!["a".into(), "b".into()]));
+    }
+
+    #[test]
+    fn rejects_double_quotes() {
+        assert!(validate_condition("\"a\" in topics").is_err());
+    }
+
+    #[test]
+    fn rejects_dangling_negation() {
+        assert!(validate_condition("'a' in topics && !").is_err());
+        assert!(validate_condition("'a' in topics !").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(validate_condition("").is_err());
+        assert!(validate_condition("   ").is_err());
+        assert!(validate_condition("'a' in").is_err());
+        assert!(validate_condition("'a' topics").is_err());
+        assert!(validate_condition("'a' in topics &").is_err());
+        assert!(validate_condition("('a' in topics").is_err());
+        assert!(validate_condition("'a' in topics)").is_err());
+        assert!(validate_condition("'a' in topics 'b' in topics").is_err());
+        assert!(validate_condition("'a in topics").is_err());
+        assert!(validate_condition("'a' in channels").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_topic_names() {
+        assert!(validate_condition("'' in topics").is_err());
+        assert!(validate_condition("'a b' in topics").is_err());
+        assert!(validate_condition("'a/b' in topics").is_err());
+        assert!(validate_condition(&format!("'{}' in topics", "a".repeat(901))).is_err());
+        assert!(validate_condition("'a-b_c.d~e%f' in topics").is_ok());
+    }
+
+    #[test]
+    fn limits_topics_to_five() {
+        let five =
+            "'a' in topics && 'b' in topics && 'c' in topics && 'd' in topics && 'e' in topics";
+        assert!(validate_condition(five).is_ok());
+        assert!(validate_condition(&format!("{} && 'f' in topics", five)).is_err());
+    }
+}
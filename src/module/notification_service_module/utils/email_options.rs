@@ -0,0 +1,197 @@
+use serde_json::Value;
+
+const MAX_HEADERS: usize = 20;
+const MAX_HEADER_LENGTH: usize = 998;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 100;
+const MAX_CONTENT_ID_LENGTH: usize = 255;
+
+/// Whether a content type is HTML, parameters such as `charset` aside
+pub fn is_html(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .eq_ignore_ascii_case("text/html")
+}
+
+// Headers set by the delivery workers or the providers, which payloads cannot override
+const RESERVED_HEADERS: [&str; 17] = [
+    "bcc",
+    "cc",
+    "content-disposition",
+    "content-transfer-encoding",
+    "content-type",
+    "date",
+    "dkim-signature",
+    "from",
+    "list-unsubscribe",
+    "list-unsubscribe-post",
+    "message-id",
+    "mime-version",
+    "reply-to",
+    "return-path",
+    "sender",
+    "subject",
+    "to",
+];
+
+/// Checks the custom headers of an email payload (`optionals.headers`)
+///
+/// Headers are an object of string values, named with printable ASCII and without line
+/// breaks so that they cannot inject other headers
+pub fn check_headers(headers: &Value) -> Result<(), String> {
+    let Some(headers) = headers.as_object() else {
+        return Err("Field 'headers' must be an object".to_string());
+    };
+    if headers.len() > MAX_HEADERS {
+        return Err(format!("An email has at most {} headers", MAX_HEADERS));
+    }
+
+    for (name, value) in headers {
+        let valid_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic() && c != ':');
+        if !valid_name {
+            return Err(format!("Invalid header name '{}'", name));
+        }
+        if RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) {
+            return Err(format!("Header '{}' cannot be set", name));
+        }
+
+        let Some(value) = value.as_str() else {
+            return Err(format!("Header '{}' must be a string", name));
+        };
+        if value.len() > MAX_HEADER_LENGTH || value.contains(['\r', '\n']) {
+            return Err(format!("Invalid value of header '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the tags of an email payload (`optionals.tags`)
+///
+/// Tags are passed to the providers for their statistics, as SendGrid categories, Mailgun
+/// tags or SES message tags, so they are limited to the characters every provider accepts.
+/// Mailgun only keeps the first 3 tags of an email
+pub fn check_tags(tags: &Value) -> Result<(), String> {
+    let Some(tags) = tags.as_array() else {
+        return Err("Field 'tags' must be an array".to_string());
+    };
+    if tags.len() > MAX_TAGS {
+        return Err(format!("An email has at most {} tags", MAX_TAGS));
+    }
+
+    for tag in tags {
+        let valid = tag.as_str().is_some_and(|tag| {
+            !tag.is_empty()
+                && tag.len() <= MAX_TAG_LENGTH
+                && tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+        if !valid {
+            return Err(format!("Invalid tag {}", tag));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the content id of an inline attachment, referenced as `cid:<content_id>` in HTML
+pub fn check_content_id(content_id: &str) -> Result<(), String> {
+    let valid = !content_id.is_empty()
+        && content_id.len() <= MAX_CONTENT_ID_LENGTH
+        && content_id
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"<>\"()[]\\,;:".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid content id '{}'", content_id))
+    }
+}
+
+/// Returns the content ids referenced as `cid:` URLs in an HTML body
+pub fn referenced_content_ids(html: &str) -> Vec<&str> {
+    html.match_indices("cid:")
+        .map(|(index, prefix)| {
+            let rest = &html[index + prefix.len()..];
+            let end = rest
+                .find(|c: char| c == '"' || c == '\'' || c == ')' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            &rest[..end]
+        })
+        .filter(|content_id| !content_id.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn accepts_custom_headers() {
+        assert!(check_headers(&json!({"X-Campaign": "spring", "X-Entity-Ref-ID": "42"})).is_ok());
+        assert!(check_headers(&json!({})).is_ok());
+    }
+
+    #[test]
+    fn rejects_reserved_headers() {
+        assert!(check_headers(&json!({"From": "admin@example.com"})).is_err());
+        assert!(check_headers(&json!({"BCC": "spy@example.com"})).is_err());
+        assert!(check_headers(&json!({"List-Unsubscribe": "<https://example.com>"})).is_err());
+    }
+
+    #[test]
+    fn rejects_header_injection() {
+        assert!(check_headers(&json!({"X-Campaign": "spring\r\nBcc: spy@example.com"})).is_err());
+        assert!(check_headers(&json!({"X-Campaign": "spring\nBcc: spy@example.com"})).is_err());
+        assert!(check_headers(&json!({"X-Bcc: spy@example.com": "x"})).is_err());
+        assert!(check_headers(&json!({"X Campaign": "spring"})).is_err());
+        assert!(check_headers(&json!({"": "spring"})).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert!(check_headers(&json!(["X-Campaign"])).is_err());
+        assert!(check_headers(&json!({"X-Count": 1})).is_err());
+        assert!(check_headers(&json!({"X-Long": "a".repeat(MAX_HEADER_LENGTH + 1)})).is_err());
+
+        let too_many: serde_json::Map<String, Value> = (0..=MAX_HEADERS)
+            .map(|i| (format!("X-Header-{}", i), json!("value")))
+            .collect();
+        assert!(check_headers(&Value::Object(too_many)).is_err());
+    }
+
+    #[test]
+    fn checks_tags() {
+        assert!(check_tags(&json!(["welcome", "onboarding_2024", "a-b"])).is_ok());
+        assert!(check_tags(&json!("welcome")).is_err());
+        assert!(check_tags(&json!([""])).is_err());
+        assert!(check_tags(&json!(["spring sale"])).is_err());
+        assert!(check_tags(&json!([42])).is_err());
+        assert!(check_tags(&json!(["a".repeat(MAX_TAG_LENGTH + 1)])).is_err());
+        assert!(check_tags(&json!(vec!["tag"; MAX_TAGS + 1])).is_err());
+    }
+
+    #[test]
+    fn checks_content_ids() {
+        assert!(check_content_id("logo.png@example.com").is_ok());
+        assert!(check_content_id("").is_err());
+        assert!(check_content_id("<logo>").is_err());
+        assert!(check_content_id("logo png").is_err());
+        assert!(check_content_id(&"a".repeat(MAX_CONTENT_ID_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn finds_referenced_content_ids() {
+        let html = r#"<img src="cid:logo"><img src='cid:banner@example.com'>
+            <div style="background: url(cid:bg)"></div><p>cid: alone</p>"#;
+
+        assert_eq!(
+            referenced_content_ids(html),
+            vec!["logo", "banner@example.com", "bg"]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+/// Rewrites the `http(s)` links (`href` attributes of `<a>` and `<area>` tags) of an HTML
+/// document
+///
+/// `rewrite` receives the target of each link, with `&amp;` decoded, and returns its
+/// replacement or `None` to keep the link as is. Other links (`mailto:`, anchors), links
+/// with placeholders (`{{unsubscribe_url}}`, `https://example.com/{{key}}`) which the
+/// delivery workers substitute per recipient later, other tags (e.g. `<link href>`) and other
+/// attributes (e.g. `data-href`) are left untouched, as are comments
+pub fn rewrite_links(html: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(html.len());
+    // Bytes of `html` before `cursor` are in `output`
+    let mut cursor = 0;
+    let mut position = 0;
+
+    while let Some(found) = html[position..].find('<') {
+        let tag_start = position + found;
+        if html[tag_start..].starts_with("<!--") {
+            position = html[tag_start..]
+                .find("-->")
+                .map_or(html.len(), |end| tag_start + end + "-->".len());
+            continue;
+        }
+
+        let name_start = tag_start + 1;
+        let name_length = html[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(html.len() - name_start);
+        let name = &html[name_start..name_start + name_length];
+        position = name_start + name_length;
+        if !name.eq_ignore_ascii_case("a") && !name.eq_ignore_ascii_case("area") {
+            continue;
+        }
+
+        // Walk the attributes up to the end of the tag
+        while let Some(attribute) = next_attribute(html, position) {
+            position = attribute.end;
+            let Some((url_start, url_end)) = attribute.value else {
+                continue;
+            };
+            if !attribute.name.eq_ignore_ascii_case("href") {
+                continue;
+            }
+
+            let url = &html[url_start..url_end];
+            let tracked = (url.to_ascii_lowercase().starts_with("http://")
+                || url.to_ascii_lowercase().starts_with("https://"))
+                && !url.contains("{{");
+            if let Some(replacement) = tracked
+                .then(|| rewrite(&url.replace("&amp;", "&")))
+                .flatten()
+            {
+                output.push_str(&html[cursor..url_start]);
+                output.push_str(&replacement);
+                cursor = url_end;
+            }
+        }
+    }
+
+    output.push_str(&html[cursor..]);
+    output
+}
+
+/// An attribute of a tag, positions are byte offsets in the document
+struct Attribute<'a> {
+    name: &'a str,
+    /// Start and end of the value, without its quotes
+    value: Option<(usize, usize)>,
+    /// Where the next attribute starts
+    end: usize,
+}
+
+/// Returns the attribute of the tag at `position`, `None` at the end of the tag
+fn next_attribute(html: &str, mut position: usize) -> Option<Attribute<'_>> {
+    loop {
+        let rest = &html[position..];
+        let trimmed = rest.trim_start();
+        position += rest.len() - trimmed.len();
+        match trimmed.chars().next() {
+            None | Some('>') => return None,
+            Some('/') => position += 1,
+            Some(_) => break,
+        }
+    }
+
+    let rest = &html[position..];
+    let name_length = rest
+        .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+        .unwrap_or(rest.len());
+    let name = &rest[..name_length];
+    let name_end = position + name_length;
+
+    let after_name = html[name_end..].trim_start();
+    let Some(after_equal) = after_name.strip_prefix('=') else {
+        return Some(Attribute {
+            name,
+            value: None,
+            end: name_end,
+        });
+    };
+    let value = after_equal.trim_start();
+    let value_start = html.len() - value.len();
+
+    let (value, end) = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let length = value[1..].find(quote)?;
+            let start = value_start + 1;
+            ((start, start + length), start + length + 1)
+        }
+        _ => {
+            let length = value
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .unwrap_or(value.len());
+            ((value_start, value_start + length), value_start + length)
+        }
+    };
+
+    Some(Attribute {
+        name,
+        value: Some(value),
+        end,
+    })
+}
+
+/// Adds an invisible 1x1 image loading `pixel_url`, before `</body>` when there is one
+pub fn insert_pixel(html: &str, pixel_url: &str) -> String {
+    let pixel = format!(
+        r#"<img src="{}" width="1" height="1" alt="" style="display:none;border:0" />"#,
+        pixel_url
+    );
+
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(position) => format!("{}{}{}", &html[..position], pixel, &html[position..]),
+        None => format!("{}{}", html, pixel),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(html: &str) -> String {
+        rewrite_links(html, |url| Some(format!("https://t.example/?u={}", url)))
+    }
+
+    #[test]
+    fn rewrites_anchor_and_area_links() {
+        assert_eq!(
+            track(r#"<a href="https://a.example/x">x</a>"#),
+            r#"<a href="https://t.example/?u=https://a.example/x">x</a>"#
+        );
+        assert_eq!(
+            track(r#"<area shape="rect" href='http://a.example'>"#),
+            r#"<area shape="rect" href='https://t.example/?u=http://a.example'>"#
+        );
+        assert_eq!(
+            track("<A class=btn HREF=https://a.example>x</A>"),
+            "<A class=btn HREF=https://t.example/?u=https://a.example>x</A>"
+        );
+        assert_eq!(
+            track(r#"<a href = "https://a.example">"#),
+            r#"<a href = "https://t.example/?u=https://a.example">"#
+        );
+    }
+
+    #[test]
+    fn decodes_ampersands() {
+        assert_eq!(
+            track(r#"<a href="https://a.example/?a=1&amp;b=2">"#),
+            r#"<a href="https://t.example/?u=https://a.example/?a=1&b=2">"#
+        );
+    }
+
+    #[test]
+    fn ignores_other_tags_and_attributes() {
+        let html = concat!(
+            r#"<head><link rel="stylesheet" href="https://a.example/style.css"></head>"#,
+            r#"<abbr href="https://a.example">x</abbr>"#,
+            r#"<a data-href="https://a.example/data" title="href=https://a.example">x</a>"#,
+        );
+        assert_eq!(track(html), html);
+    }
+
+    #[test]
+    fn rewrites_only_the_href_of_a_tag() {
+        assert_eq!(
+            track(r#"<a data-href="https://a.example/data" href="https://a.example">"#),
+            r#"<a data-href="https://a.example/data" href="https://t.example/?u=https://a.example">"#
+        );
+    }
+
+    #[test]
+    fn keeps_other_links() {
+        let html = concat!(
+            r##"<a href="mailto:team@example.com">m</a><a href="#top">t</a>"##,
+            r#"<a href="{{unsubscribe_url}}">u</a><a href>e</a>"#,
+            r#"<a href="https://a.example/orders/{{order_id}}">o</a>"#,
+        );
+        assert_eq!(track(html), html);
+    }
+
+    #[test]
+    fn skips_comments() {
+        let html = r#"<!-- <a href="https://a.example"> --><p>x</p>"#;
+        assert_eq!(track(html), html);
+    }
+
+    #[test]
+    fn keeps_links_rewrite_declines() {
+        let html = r#"<a href="https://a.example">x</a>"#;
+        assert_eq!(rewrite_links(html, |_| None), html);
+    }
+
+    #[test]
+    fn survives_malformed_html() {
+        assert_eq!(
+            track(r#"<a href="https://a.example"#),
+            r#"<a href="https://a.example"#
+        );
+        assert_eq!(track("<a href="), "<a href=");
+        assert_eq!(track("<"), "<");
+        assert_eq!(track("<a"), "<a");
+        assert_eq!(track("<!-- x"), "<!-- x");
+    }
+
+    #[test]
+    fn inserts_pixel_before_body_end() {
+        assert_eq!(
+            insert_pixel("<body>x</BODY>", "https://p.example"),
+            concat!(
+                r#"<body>x<img src="https://p.example" width="1" height="1" alt="" "#,
+                r#"style="display:none;border:0" /></BODY>"#
+            )
+        );
+        assert!(insert_pixel("x", "https://p.example").starts_with("x<img"));
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike};
+use serde_json::Value;
+
+/// Normalizes a locale tag, e.g. `pt_br` to `pt-BR`, `None` if it is not a locale
+pub fn normalize(locale: &str) -> Option<String> {
+    let mut parts = locale.trim().split(['-', '_']);
+
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut tag = language.to_ascii_lowercase();
+    for part in parts {
+        if part.is_empty() || part.len() > 8 || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        tag.push('-');
+        match part.len() {
+            // Region, e.g. `BR`
+            2 => tag.push_str(&part.to_ascii_uppercase()),
+            // Script, e.g. `Hant`
+            4 if part.chars().all(|c| c.is_ascii_alphabetic()) => {
+                tag.push_str(&part[..1].to_ascii_uppercase());
+                tag.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => tag.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Some(tag)
+}
+
+/// Returns the locales to look for, most specific first: `zh-Hant-TW`, `zh-Hant`, `zh`
+pub fn fallbacks(locale: &str) -> Vec<String> {
+    let mut fallbacks = vec![locale.to_string()];
+    let mut current = locale;
+    while let Some((parent, _)) = current.rsplit_once('-') {
+        fallbacks.push(parent.to_string());
+        current = parent;
+    }
+    fallbacks
+}
+
+#[derive(Clone, Copy)]
+enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How a locale writes numbers and dates
+struct Conventions {
+    group_separator: &'static str,
+    decimal_separator: &'static str,
+    date_order: DateOrder,
+    date_separator: &'static str,
+    twelve_hour: bool,
+}
+
+fn conventions(locale: Option<&str>) -> Conventions {
+    let mut parts = locale.unwrap_or_default().split('-');
+    let language = parts.next().unwrap_or_default();
+    let region = parts.find(|part| part.len() == 2);
+
+    let (group_separator, decimal_separator) = match (language, region) {
+        ("de", Some("CH")) => ("\u{2019}", "."),
+        ("pt", Some("PT")) => ("\u{a0}", ","),
+        (
+            "de" | "es" | "it" | "pt" | "nl" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr" | "vi",
+            _,
+        ) => (".", ","),
+        (
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" | "bg"
+            | "lt" | "lv" | "et",
+            _,
+        ) => ("\u{a0}", ","),
+        _ => (",", "."),
+    };
+
+    let (date_order, date_separator) = match (language, region) {
+        ("", _) => (DateOrder::YearMonthDay, "-"),
+        ("en", None | Some("US") | Some("PH")) => (DateOrder::MonthDayYear, "/"),
+        ("en", Some("CA")) | ("sv" | "lt", _) => (DateOrder::YearMonthDay, "-"),
+        ("ja" | "zh" | "ko", _) => (DateOrder::YearMonthDay, "/"),
+        ("hu", _) => (DateOrder::YearMonthDay, "."),
+        ("nl", _) => (DateOrder::DayMonthYear, "-"),
+        (
+            "de" | "ru" | "pl" | "cs" | "sk" | "nb" | "no" | "fi" | "da" | "tr" | "uk" | "ro"
+            | "bg",
+            _,
+        ) => (DateOrder::DayMonthYear, "."),
+        _ => (DateOrder::DayMonthYear, "/"),
+    };
+
+    let twelve_hour = language == "en" && !matches!(region, Some("GB") | Some("IE"));
+
+    Conventions {
+        group_separator,
+        decimal_separator,
+        date_order,
+        date_separator,
+        twelve_hour,
+    }
+}
+
+/// Formats a number, or a numeric string, with the separators of the locale
+///
+/// `decimals` fixes the number of decimals, otherwise they are kept as given
+pub fn format_number(
+    value: &Value,
+    decimals: Option<usize>,
+    locale: Option<&str>,
+) -> Option<String> {
+    let number = match value {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => text.trim().parse::<f64>().ok()?,
+        _ => return None,
+    };
+    if !number.is_finite() {
+        return None;
+    }
+
+    let digits = match decimals {
+        Some(decimals) => format!("{:.*}", decimals, number.abs()),
+        None => number.abs().to_string(),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+    let conventions = conventions(locale);
+    let mut output = String::new();
+    if number < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        output.push('-');
+    }
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            output.push_str(conventions.group_separator);
+        }
+        output.push(digit);
+    }
+    if !fraction.is_empty() {
+        output.push_str(conventions.decimal_separator);
+        output.push_str(fraction);
+    }
+    Some(output)
+}
+
+/// Formats a date in the numeric style of the locale, with the time if `with_time`
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD[ HH:MM:SS]` strings and Unix timestamps in
+/// seconds. Timestamps are written in their own offset
+pub fn format_date(value: &Value, with_time: bool, locale: Option<&str>) -> Option<String> {
+    let datetime = match value {
+        Value::String(text) => {
+            let text = text.trim();
+            DateTime::parse_from_rfc3339(text)
+                .map(|datetime| datetime.naive_local())
+                .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S"))
+                .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+                })
+                .ok()?
+        }
+        Value::Number(number) => DateTime::from_timestamp(number.as_i64()?, 0)?.naive_utc(),
+        _ => return None,
+    };
+
+    let conventions = conventions(locale);
+    let separator = conventions.date_separator;
+    let pattern = match conventions.date_order {
+        DateOrder::DayMonthYear => format!("%d{separator}%m{separator}%Y"),
+        DateOrder::MonthDayYear => format!("%m{separator}%d{separator}%Y"),
+        DateOrder::YearMonthDay => format!("%Y{separator}%m{separator}%d"),
+    };
+    let date = datetime.format(&pattern).to_string();
+
+    if !with_time {
+        return Some(date);
+    }
+    let time = if conventions.twelve_hour {
+        let (pm, hour) = datetime.hour12();
+        format!(
+            "{}:{:02} {}",
+            hour,
+            datetime.minute(),
+            if pm { "PM" } else { "AM" }
+        )
+    } else {
+        datetime.format("%H:%M").to_string()
+    };
+    Some(format!("{} {}", date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn normalizes_locales() {
+        assert_eq!(normalize("pt_br").as_deref(), Some("pt-BR"));
+        assert_eq!(normalize(" zh-hant-tw ").as_deref(), Some("zh-Hant-TW"));
+        assert_eq!(normalize("EN").as_deref(), Some("en"));
+        assert_eq!(normalize("english"), None);
+        assert_eq!(normalize("en-"), None);
+        assert_eq!(normalize("e1"), None);
+        assert_eq!(normalize(""), None);
+    }
+
+    #[test]
+    fn lists_fallbacks() {
+        assert_eq!(fallbacks("zh-Hant-TW"), vec!["zh-Hant-TW", "zh-Hant", "zh"]);
+        assert_eq!(fallbacks("en"), vec!["en"]);
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(
+            format_number(&json!(1234567.5), None, Some("en-US")).as_deref(),
+            Some("1,234,567.5")
+        );
+        assert_eq!(
+            format_number(&json!(1234.5), Some(2), Some("de")).as_deref(),
+            Some("1.234,50")
+        );
+        assert_eq!(
+            format_number(&json!("-1234"), None, Some("fr")).as_deref(),
+            Some("-1\u{a0}234")
+        );
+        assert_eq!(
+            format_number(&json!(1234), None, Some("de-CH")).as_deref(),
+            Some("1\u{2019}234")
+        );
+        assert_eq!(
+            format_number(&json!(-0.001), Some(2), None).as_deref(),
+            Some("0.00")
+        );
+        assert_eq!(format_number(&json!("abc"), None, None), None);
+        assert_eq!(format_number(&json!(true), None, None), None);
+    }
+
+    #[test]
+    fn formats_dates() {
+        let due = json!("2024-03-05T14:30:00+02:00");
+        assert_eq!(
+            format_date(&due, false, Some("en-US")).as_deref(),
+            Some("03/05/2024")
+        );
+        assert_eq!(
+            format_date(&due, true, Some("en-US")).as_deref(),
+            Some("03/05/2024 2:30 PM")
+        );
+        assert_eq!(
+            format_date(&due, true, Some("de")).as_deref(),
+            Some("05.03.2024 14:30")
+        );
+        assert_eq!(
+            format_date(&due, false, Some("ja")).as_deref(),
+            Some("2024/03/05")
+        );
+        assert_eq!(
+            format_date(&json!("2024-03-05"), false, None).as_deref(),
+            Some("2024-03-05")
+        );
+        assert_eq!(
+            format_date(&json!(0), true, Some("en-GB")).as_deref(),
+            Some("01/01/1970 00:00")
+        );
+        assert_eq!(format_date(&json!("soon"), false, None), None);
+    }
+}
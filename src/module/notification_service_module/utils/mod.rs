@@ -0,0 +1,7 @@
+pub mod attachment_policy;
+pub mod condition_parser;
+pub mod email_options;
+pub mod html_tracking;
+pub mod locale_format;
+pub mod signed_token;
+pub mod template_renderer;
@@ -0,0 +1,182 @@
+use std::env;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What an unsubscribe token opts out of
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnsubscribeClaims {
+    #[serde(rename = "u")]
+    pub user_id: String,
+    #[serde(rename = "r")]
+    pub recipient: String,
+    /// Category to opt out of, every category if `None`
+    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+/// What a tracking token records: the open of a notification, or the click of one of its
+/// links when `url` is set
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrackingClaims {
+    #[serde(rename = "n")]
+    pub notification_id: String,
+    #[serde(rename = "l", skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// What a sender verification token proves: the control of the address of an identity
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SenderVerificationClaims {
+    #[serde(rename = "i")]
+    pub identity_id: String,
+    #[serde(rename = "t")]
+    pub verification_token: String,
+}
+
+/// `TokenSigner` issues the tokens of the public links found in emails and verifies them
+///
+/// A token is `<base64url claims>.<base64url HMAC-SHA256 of the claims>`, so the public
+/// endpoints need no lookup nor login. Configured with:
+/// - a secret read from the variable given to `from_env`, used as HMAC key
+/// - `PUBLIC_BASE_URL`: base URL the gateway is reachable at, used to build links
+pub struct TokenSigner {
+    secret: Vec<u8>,
+    base_url: String,
+}
+
+impl TokenSigner {
+    /// Returns `None` when the links signed with `secret_var` are not configured
+    pub fn from_env(secret_var: &str) -> Option<Self> {
+        match (env::var(secret_var), env::var("PUBLIC_BASE_URL")) {
+            (Ok(secret), Ok(base_url)) if !secret.is_empty() => Some(Self {
+                secret: secret.into_bytes(),
+                base_url: base_url.trim_end_matches('/').to_string(),
+            }),
+            _ => {
+                warn!(
+                    "{} or PUBLIC_BASE_URL not set, signed links disabled",
+                    secret_var
+                );
+                None
+            }
+        }
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::json!(claims).to_string());
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.mac(payload.as_bytes()).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Returns the claims of a token, if its signature is valid
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Option<T> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        // Constant time comparison
+        self.mac(payload.as_bytes()).verify_slice(&signature).ok()?;
+
+        let claims = URL_SAFE_NO_PAD.decode(payload).ok()?;
+        serde_json::from_slice(&claims).ok()
+    }
+
+    /// Public URL carrying the signed claims, e.g. `url("unsubscribe", claims)`
+    pub fn url<T: Serialize>(&self, path: &str, claims: &T) -> String {
+        self.link(path, &self.sign(claims))
+    }
+
+    /// Public URL of an already signed token
+    pub fn link(&self, path: &str, token: &str) -> String {
+        format!("{}/{}/{}", self.base_url, path, token)
+    }
+
+    fn mac(&self, payload: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any size");
+        mac.update(payload);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(secret: &str) -> TokenSigner {
+        TokenSigner {
+            secret: secret.as_bytes().to_vec(),
+            base_url: "https://notify.example.com".to_string(),
+        }
+    }
+
+    fn claims() -> UnsubscribeClaims {
+        UnsubscribeClaims {
+            user_id: "6f1c2a9e-5b7d-4c3e-9a8f-1d2e3f4a5b6c".to_string(),
+            recipient: "john@example.com".to_string(),
+            category: Some("newsletter".to_string()),
+        }
+    }
+
+    #[test]
+    fn verifies_signed_claims() {
+        let signer = signer("secret");
+        let verified: UnsubscribeClaims = signer.verify(&signer.sign(&claims())).unwrap();
+
+        assert_eq!(verified.user_id, claims().user_id);
+        assert_eq!(verified.recipient, "john@example.com");
+        assert_eq!(verified.category.as_deref(), Some("newsletter"));
+    }
+
+    #[test]
+    fn rejects_tampered_payload() {
+        let signer = signer("secret");
+        let token = signer.sign(&claims());
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = URL_SAFE_NO_PAD
+            .encode(r#"{"u":"6f1c2a9e-5b7d-4c3e-9a8f-1d2e3f4a5b6c","r":"jane@example.com"}"#);
+
+        assert!(signer
+            .verify::<UnsubscribeClaims>(&format!("{}.{}", forged, signature))
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_tampered_signature() {
+        let signer = signer("secret");
+        let token = signer.sign(&claims());
+        let (payload, _) = token.split_once('.').unwrap();
+        let signature = URL_SAFE_NO_PAD.encode([0u8; 32]);
+
+        assert!(signer
+            .verify::<UnsubscribeClaims>(&format!("{}.{}", payload, signature))
+            .is_none());
+        assert!(signer.verify::<UnsubscribeClaims>(payload).is_none());
+    }
+
+    #[test]
+    fn rejects_another_secret() {
+        let token = signer("secret").sign(&claims());
+
+        assert!(signer("other")
+            .verify::<UnsubscribeClaims>(&token)
+            .is_none());
+    }
+
+    #[test]
+    fn links_under_the_base_url() {
+        let signer = signer("secret");
+        let token = signer.sign(&claims());
+
+        assert_eq!(
+            signer.url("unsubscribe", &claims()),
+            format!("https://notify.example.com/unsubscribe/{}", token)
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use serde_json::{Map, Value};
+
+use super::{email_options, locale_format};
+
+// Keeps `number` from writing arbitrarily long strings
+const MAX_DECIMALS: usize = 20;
+
+/// Replaces the `{{name}}` placeholders of every string of `content` with `variables`
+///
+/// Strings are rendered as is, null as an empty string and other values as JSON. Placeholders
+/// without a variable are kept, they may be filled later by the delivery workers (recipient
+/// substitutions, `{{unsubscribe_url}}`)
+///
+/// Variables are HTML escaped in the HTML body of an email, `html` or a `content` whose
+/// `content_type` is HTML. A triple-brace placeholder, `{{{name}}}`, writes its variable
+/// unescaped
+///
+/// A placeholder may format its variable for `locale` with a filter:
+/// - `{{amount | number}}`, or `{{amount | number:2}}` with a fixed number of decimals
+/// - `{{due | date}}` and `{{due | datetime}}`
+///
+/// Filters are checked when a version is saved, see `filter_errors`
+pub fn render(content: &Value, variables: &Map<String, Value>, locale: Option<&str>) -> Value {
+    render_value(content, variables, locale, false)
+}
+
+fn render_value(
+    content: &Value,
+    variables: &Map<String, Value>,
+    locale: Option<&str>,
+    html: bool,
+) -> Value {
+    match content {
+        Value::String(text) => Value::String(render_text(text, variables, locale, html)),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_value(value, variables, locale, html))
+                .collect(),
+        ),
+        Value::Object(fields) => {
+            let html_content = fields
+                .get("content_type")
+                .and_then(Value::as_str)
+                .is_some_and(email_options::is_html);
+            Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let html = key == "html" || (key == "content" && html_content);
+                        (key.clone(), render_value(value, variables, locale, html))
+                    })
+                    .collect(),
+            )
+        }
+        other => other.clone(),
+    }
+}
+
+/// Returns the variable names of the placeholders of every string of `content`, in order of
+/// first appearance
+pub fn placeholders(content: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_placeholders(content, &mut names);
+    names
+}
+
+/// Checks the filters of the placeholders of every string of `content`
+///
+/// Returns one message per placeholder with an unknown filter or an invalid argument
+pub fn filter_errors(content: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    visit_placeholders(content, &mut |placeholder| {
+        if placeholder
+            .filter
+            .is_some_and(|filter| parse_filter(filter).is_none())
+        {
+            errors.push(format!(
+                "Invalid filter '{}' of placeholder '{}'",
+                placeholder.filter.unwrap_or_default(),
+                placeholder.name
+            ));
+        }
+    });
+    errors
+}
+
+fn collect_placeholders(content: &Value, names: &mut Vec<String>) {
+    visit_placeholders(content, &mut |placeholder| {
+        if !names.iter().any(|known| known == placeholder.name) {
+            names.push(placeholder.name.to_string());
+        }
+    });
+}
+
+fn visit_placeholders(content: &Value, visit: &mut impl FnMut(&Placeholder)) {
+    match content {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(placeholder) = Placeholder::find(rest) {
+                visit(&placeholder);
+                rest = &rest[placeholder.end..];
+            }
+        }
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| visit_placeholders(value, visit)),
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|value| visit_placeholders(value, visit)),
+        _ => {}
+    }
+}
+
+/// A `{{name | filter}}` or `{{{name | filter}}}` placeholder of a text
+struct Placeholder<'a> {
+    /// Range of the placeholder in the text
+    start: usize,
+    end: usize,
+    name: &'a str,
+    filter: Option<&'a str>,
+    raw: bool,
+}
+
+impl<'a> Placeholder<'a> {
+    /// Finds the first placeholder of `text`
+    fn find(text: &'a str) -> Option<Self> {
+        let start = text.find("{{")?;
+        let raw = text[start..].starts_with("{{{");
+        let (open, close) = if raw { ("{{{", "}}}") } else { ("{{", "}}") };
+        let length = text[start + open.len()..].find(close)?;
+        let expression = &text[start + open.len()..start + open.len() + length];
+
+        let (name, filter) = match expression.split_once('|') {
+            Some((name, filter)) => (name.trim(), Some(filter.trim())),
+            None => (expression.trim(), None),
+        };
+        Some(Self {
+            start,
+            end: start + open.len() + length + close.len(),
+            name,
+            filter,
+            raw,
+        })
+    }
+}
+
+/// Replaces the `{{name}}` placeholders of `text` with `variables`, HTML escaped if `html`
+///
+/// Renders a single string as `render` does, e.g. a part of an email with the substitutions
+/// of its recipient
+pub fn render_text(
+    text: &str,
+    variables: &Map<String, Value>,
+    locale: Option<&str>,
+    html: bool,
+) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(placeholder) = Placeholder::find(rest) {
+        output.push_str(&rest[..placeholder.start]);
+        match variables.get(placeholder.name) {
+            Some(value) => {
+                let formatted = format_value(value, placeholder.filter, locale);
+                if html && !placeholder.raw {
+                    output.push_str(&escape_html(&formatted));
+                } else {
+                    output.push_str(&formatted);
+                }
+            }
+            None => output.push_str(&rest[placeholder.start..placeholder.end]),
+        }
+        rest = &rest[placeholder.end..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A filter of a placeholder
+enum Filter {
+    /// `number`, with its number of decimals
+    Number(Option<usize>),
+    /// `date` or `datetime`, with the time for `datetime`
+    Date(bool),
+}
+
+/// Parses a filter, `None` if it is unknown or its argument is invalid
+fn parse_filter(filter: &str) -> Option<Filter> {
+    let (name, argument) = match filter.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (filter.trim(), None),
+    };
+    match (name, argument) {
+        ("number", None) => Some(Filter::Number(None)),
+        ("number", Some(decimals)) => decimals
+            .parse()
+            .ok()
+            .filter(|decimals| *decimals <= MAX_DECIMALS)
+            .map(|decimals| Filter::Number(Some(decimals))),
+        ("date", None) => Some(Filter::Date(false)),
+        ("datetime", None) => Some(Filter::Date(true)),
+        _ => None,
+    }
+}
+
+/// Formats a variable with its filter, values the filter cannot format are written as is
+fn format_value(value: &Value, filter: Option<&str>, locale: Option<&str>) -> String {
+    let formatted = filter
+        .and_then(parse_filter)
+        .and_then(|filter| match filter {
+            Filter::Number(decimals) => locale_format::format_number(value, decimals, locale),
+            Filter::Date(with_time) => locale_format::format_date(value, with_time, locale),
+        });
+
+    match (formatted, value) {
+        (Some(formatted), _) => formatted,
+        (None, Value::Null) => String::new(),
+        (None, Value::String(value)) => value.clone(),
+        (None, value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn variables(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn renders_nested_strings() {
+        let content = json!({"title": "Hi {{name}}", "data": {"ids": ["{{ id }}"]}, "n": 1});
+        let rendered = render(&content, &variables(json!({"name": "Ann", "id": 7})), None);
+        assert_eq!(
+            rendered,
+            json!({"title": "Hi Ann", "data": {"ids": ["7"]}, "n": 1})
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_placeholders() {
+        let rendered = render(
+            &json!("{{name}} {{unsubscribe_url}} {{open"),
+            &variables(json!({"name": "Ann"})),
+            None,
+        );
+        assert_eq!(rendered, json!("Ann {{unsubscribe_url}} {{open"));
+    }
+
+    #[test]
+    fn renders_null_as_empty() {
+        let rendered = render(
+            &json!("[{{middle}}]"),
+            &variables(json!({"middle": null})),
+            None,
+        );
+        assert_eq!(rendered, json!("[]"));
+    }
+
+    #[test]
+    fn escapes_html_fields() {
+        let content = json!({
+            "subject": "{{name}}",
+            "html": "<p>{{name}}</p>{{{banner}}}",
+            "text": "{{name}}"
+        });
+        let variables = variables(json!({"name": "<b>A & \"B\"</b>", "banner": "<hr>"}));
+        assert_eq!(
+            render(&content, &variables, None),
+            json!({
+                "subject": "<b>A & \"B\"</b>",
+                "html": "<p>&lt;b&gt;A &amp; &quot;B&quot;&lt;/b&gt;</p><hr>",
+                "text": "<b>A & \"B\"</b>"
+            })
+        );
+    }
+
+    #[test]
+    fn escapes_html_content() {
+        let variables = variables(json!({"name": "<i>"}));
+        assert_eq!(
+            render(
+                &json!({"content": "{{name}}", "content_type": "text/html; charset=utf-8"}),
+                &variables,
+                None
+            )["content"],
+            json!("&lt;i&gt;")
+        );
+        assert_eq!(
+            render(
+                &json!({"content": "{{name}}", "content_type": "text/plain"}),
+                &variables,
+                None
+            )["content"],
+            json!("<i>")
+        );
+    }
+
+    #[test]
+    fn applies_filters() {
+        let rendered = render(
+            &json!("{{amount | number:2}} {{name | unknown}}"),
+            &variables(json!({"amount": 1234.5, "name": "Ann"})),
+            Some("en"),
+        );
+        assert_eq!(rendered, json!("1,234.50 Ann"));
+    }
+
+    #[test]
+    fn reports_invalid_filters() {
+        let content = json!({
+            "a": "{{x | number}} {{x | number:2}} {{d | date}} {{d | datetime}}",
+            "b": ["{{x | upper}}", "{{x | number:many}}", "{{d | date:long}}"]
+        });
+        assert_eq!(
+            filter_errors(&content),
+            vec![
+                "Invalid filter 'upper' of placeholder 'x'",
+                "Invalid filter 'number:many' of placeholder 'x'",
+                "Invalid filter 'date:long' of placeholder 'd'",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_placeholders_once() {
+        let content = json!({"a": "{{x}} {{ y | date }}", "b": ["{{{z}}}", "{{x}}"]});
+        assert_eq!(placeholders(&content), vec!["x", "y", "z"]);
+    }
+}